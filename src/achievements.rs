@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::food::FoodType;
+use crate::events::{GameEvent, Subscriber};
+use crate::game::{Game, TICK_DURATION};
+use crate::stats::TRACKED_PLAYER;
+use crate::storage;
+
+// @note: Badges unlocked by things that happen during play. Like the stats
+//  they only follow player 1 in games a person plays for real. Unlocked ids are
+//  saved one per line, ids nobody knows about any more are kept as they are so
+//  downgrading the game doesn't lose anything.
+
+const ACHIEVEMENTS_FILE: &str = "achievements.txt";
+
+pub const QUICK_EXIT_TICKS:     u64 = 20;
+pub const SURVIVOR_SECONDS:     f32 = 60.0;
+pub const MARATHON_SECONDS:     f32 = 180.0;
+pub const CLUTCH_TIME_LEFT:     f32 = 0.5;
+
+#[derive(PartialEq, Debug)]
+pub struct Achievement {
+    pub id:             &'static str,
+    pub name:           &'static str,
+    pub description:    &'static str
+}
+
+pub const ACHIEVEMENTS: [Achievement; 10] = [
+    Achievement { id: "first_bite",  name: "First Bite",   description: "Eat an apple" },
+    Achievement { id: "gold_digger", name: "Gold Digger",  description: "Eat a golden apple" },
+    Achievement { id: "length_10",   name: "Growing Up",   description: "Reach a length of 10" },
+    Achievement { id: "length_25",   name: "Long Boi",     description: "Reach a length of 25" },
+    Achievement { id: "length_50",   name: "Python",       description: "Reach a length of 50" },
+    Achievement { id: "quick_exit",  name: "Quick Exit",   description: "Die within 20 ticks of the start" },
+    Achievement { id: "survivor",    name: "Survivor",     description: "Stay alive for a minute" },
+    Achievement { id: "marathon",    name: "Marathon",     description: "Stay alive for three minutes" },
+    Achievement { id: "clutch",      name: "Clutch",       description: "Eat food with under half a second left" },
+    Achievement { id: "full_board",  name: "No Vacancy",   description: "Fill the whole board" },
+];
+
+pub fn find_achievement(id: &str) -> Option<&'static Achievement> {
+    ACHIEVEMENTS.iter().find(|achievement| achievement.id == id)
+}
+
+pub struct Achievements {
+    unlocked:   Vec<String>,
+    // Newly unlocked ones waiting to be shown
+    toasts:     VecDeque<&'static Achievement>,
+    path:       PathBuf
+}
+
+impl Achievements {
+    pub fn load() -> Self {
+        let path = storage::data_file(ACHIEVEMENTS_FILE);
+        let unlocked = fs::read_to_string(&path)
+            .map(|text| text.lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect())
+            .unwrap_or_default();
+
+        Achievements {
+            unlocked:   unlocked,
+            toasts:     VecDeque::new(),
+            path:       path
+        }
+    }
+
+    pub fn is_unlocked(self: &Self, id: &str) -> bool {
+        self.unlocked.iter().any(|unlocked| unlocked == id)
+    }
+
+    pub fn unlocked_count(self: &Self) -> usize {
+        ACHIEVEMENTS.iter().filter(|achievement| self.is_unlocked(achievement.id)).count()
+    }
+
+    pub fn next_toast(self: &mut Self) -> Option<&'static Achievement> {
+        self.toasts.pop_front()
+    }
+
+    // Returns true if it wasn't unlocked already
+    fn unlock(self: &mut Self, id: &str) -> bool {
+        if self.is_unlocked(id) {
+            return false;
+        }
+        let Some(achievement) = find_achievement(id) else { return false };
+
+        self.unlocked.push(id.to_string());
+        self.toasts.push_back(achievement);
+        true
+    }
+
+    fn save(self: &Self) {
+        let mut text = self.unlocked.join("\n");
+        text.push('\n');
+        if let Err(error) = storage::write_atomically(&self.path, &text) {
+            println!("WARN: Failed to save achievements to {}: {}", self.path.display(), error);
+        }
+    }
+
+    // Unlocks whatever the event earns without saving, returns true if
+    //  anything new was unlocked
+    fn apply(self: &mut Self, game: &Game, event: &GameEvent) -> bool {
+        let mut unlocked = false;
+        match *event {
+            GameEvent::TICKED => {
+                let Some(player) = game.players.get(TRACKED_PLAYER) else { return false };
+                if player.alive {
+                    let seconds_alive = game.tick as f32 * TICK_DURATION;
+                    if seconds_alive >= SURVIVOR_SECONDS {
+                        unlocked |= self.unlock("survivor");
+                    }
+                    if seconds_alive >= MARATHON_SECONDS {
+                        unlocked |= self.unlock("marathon");
+                    }
+                }
+            },
+            GameEvent::EATEN(TRACKED_PLAYER, apple) => {
+                unlocked |= self.unlock("first_bite");
+                if apple.food_type == FoodType::GOLDEN {
+                    unlocked |= self.unlock("gold_digger");
+                }
+                if apple.time_left < CLUTCH_TIME_LEFT {
+                    unlocked |= self.unlock("clutch");
+                }
+            },
+            GameEvent::GREW(TRACKED_PLAYER, length) => {
+                for (threshold, id) in [(10, "length_10"), (25, "length_25"), (50, "length_50")] {
+                    if length >= threshold {
+                        unlocked |= self.unlock(id);
+                    }
+                }
+            },
+            GameEvent::DIED(TRACKED_PLAYER, death) if death.tick <= QUICK_EXIT_TICKS => unlocked |= self.unlock("quick_exit"),
+            GameEvent::FILLED(TRACKED_PLAYER) => unlocked |= self.unlock("full_board"),
+            _ => {},
+        }
+        unlocked
+    }
+}
+
+impl Subscriber for Achievements {
+    fn on_event(self: &mut Self, game: &Game, event: &GameEvent) {
+        if self.apply(game, event) {
+            self.save();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::{Apple, FoodTable};
+    use crate::game::{play_area_for_screen, Death, DeathCause, GameMode, WallSide};
+
+    // Nothing unlocked and nowhere to save, `apply` never writes
+    fn achievements() -> Achievements {
+        Achievements {
+            unlocked:   Vec::new(),
+            toasts:     VecDeque::new(),
+            path:       PathBuf::new()
+        }
+    }
+
+    fn game() -> Game {
+        Game::new(GameMode::VERSUS, play_area_for_screen(640, 480), 1)
+    }
+
+    fn apple(food_type: FoodType, time_left: f32) -> Apple {
+        let mut apple = Apple::new(0.0, 0.0, food_type, FoodTable::default().get(food_type));
+        apple.time_left = time_left;
+        apple
+    }
+
+    fn died(player_index: usize, tick: u64) -> GameEvent {
+        GameEvent::DIED(player_index, Death { cause: DeathCause::WALL(WallSide::LEFT), x: 0.0, y: 0.0, tick: tick, boosting: false, length: 3 })
+    }
+
+    fn unlocked(achievements: &Achievements) -> Vec<&str> {
+        ACHIEVEMENTS.iter().map(|achievement| achievement.id).filter(|id| achievements.is_unlocked(id)).collect()
+    }
+
+    #[test]
+    fn quick_exit_is_up_to_the_limit() {
+        let mut achievements = achievements();
+        assert!(!achievements.apply(&game(), &died(TRACKED_PLAYER, QUICK_EXIT_TICKS + 1)));
+        assert!(achievements.apply(&game(), &died(TRACKED_PLAYER, QUICK_EXIT_TICKS)));
+        assert_eq!(unlocked(&achievements), vec!["quick_exit"]);
+        assert!(!achievements.apply(&game(), &died(TRACKED_PLAYER, 1)));
+    }
+
+    #[test]
+    fn clutch_needs_under_half_a_second() {
+        let mut achievements = achievements();
+        achievements.apply(&game(), &GameEvent::EATEN(TRACKED_PLAYER, apple(FoodType::APPLE, CLUTCH_TIME_LEFT)));
+        assert_eq!(unlocked(&achievements), vec!["first_bite"]);
+        achievements.apply(&game(), &GameEvent::EATEN(TRACKED_PLAYER, apple(FoodType::GOLDEN, CLUTCH_TIME_LEFT - 0.1)));
+        assert_eq!(unlocked(&achievements), vec!["first_bite", "gold_digger", "clutch"]);
+        assert_eq!(achievements.next_toast().map(|achievement| achievement.id), Some("first_bite"));
+    }
+
+    #[test]
+    fn survivor_and_marathon_count_the_ticks_alive() {
+        let mut achievements = achievements();
+        let mut game = game();
+        let ticks = |seconds: f32| (seconds / TICK_DURATION) as u64;
+
+        game.tick = ticks(SURVIVOR_SECONDS) - 10;
+        assert!(!achievements.apply(&game, &GameEvent::TICKED));
+        game.tick = ticks(SURVIVOR_SECONDS) + 1;
+        achievements.apply(&game, &GameEvent::TICKED);
+        assert_eq!(unlocked(&achievements), vec!["survivor"]);
+
+        game.tick = ticks(MARATHON_SECONDS) + 1;
+        game.players[TRACKED_PLAYER].alive = false;
+        assert!(!achievements.apply(&game, &GameEvent::TICKED));
+        game.players[TRACKED_PLAYER].alive = true;
+        achievements.apply(&game, &GameEvent::TICKED);
+        assert_eq!(unlocked(&achievements), vec!["survivor", "marathon"]);
+    }
+
+    #[test]
+    fn lengths_unlock_at_their_thresholds() {
+        let mut achievements = achievements();
+        achievements.apply(&game(), &GameEvent::GREW(TRACKED_PLAYER, 9));
+        assert!(unlocked(&achievements).is_empty());
+        achievements.apply(&game(), &GameEvent::GREW(TRACKED_PLAYER, 10));
+        assert_eq!(unlocked(&achievements), vec!["length_10"]);
+        achievements.apply(&game(), &GameEvent::GREW(TRACKED_PLAYER, 49));
+        assert_eq!(unlocked(&achievements), vec!["length_10", "length_25"]);
+        achievements.apply(&game(), &GameEvent::GREW(TRACKED_PLAYER, 50));
+        assert_eq!(unlocked(&achievements), vec!["length_10", "length_25", "length_50"]);
+    }
+
+    #[test]
+    fn filling_the_board_unlocks_full_board() {
+        let mut achievements = achievements();
+        assert!(achievements.apply(&game(), &GameEvent::FILLED(TRACKED_PLAYER)));
+        assert_eq!(unlocked(&achievements), vec!["full_board"]);
+    }
+
+    #[test]
+    fn other_players_dont_unlock_anything() {
+        let mut achievements = achievements();
+        let other = TRACKED_PLAYER + 1;
+        let events = [
+            GameEvent::EATEN(other, apple(FoodType::GOLDEN, 0.1)),
+            GameEvent::GREW(other, 50),
+            died(other, 1),
+            GameEvent::FILLED(other)
+        ];
+        for event in &events {
+            assert!(!achievements.apply(&game(), event));
+        }
+        assert!(unlocked(&achievements).is_empty());
+        assert!(achievements.next_toast().is_none());
+    }
+}
@@ -0,0 +1,529 @@
+use std::collections::VecDeque;
+
+use crate::game::{Direction, Game, CELL_SIZE};
+
+// @note: Computer controlled snakes. Every strategy looks at the board through
+//  a `Board`, a grid of cells in column/row space rather than pixels, and hands
+//  back the direction it wants. The caller steers with it like any other input
+//  so the movement and collision rules are exactly the same as for players.
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Strategy {
+    GREEDY,
+    PATHFINDING,
+    HAMILTONIAN
+}
+
+pub const STRATEGIES: [Strategy; 3] = [Strategy::GREEDY, Strategy::PATHFINDING, Strategy::HAMILTONIAN];
+
+pub fn strategy_name(strategy: Strategy) -> &'static str {
+    match strategy {
+        Strategy::GREEDY      => "greedy",
+        Strategy::PATHFINDING => "pathfinding",
+        Strategy::HAMILTONIAN => "hamiltonian",
+    }
+}
+
+pub fn parse_strategy(name: &str) -> Option<Strategy> {
+    STRATEGIES.iter().copied().find(|strategy| strategy_name(*strategy) == name.to_lowercase())
+}
+
+const DIRECTIONS: [Direction; 4] = [Direction::UP, Direction::RIGHT, Direction::DOWN, Direction::LEFT];
+
+type Cell = (i32, i32);
+
+fn step_cell(cell: Cell, direction: Direction) -> Cell {
+    match direction {
+        Direction::UP    => (cell.0, cell.1 - 1),
+        Direction::LEFT  => (cell.0 - 1, cell.1),
+        Direction::DOWN  => (cell.0, cell.1 + 1),
+        Direction::RIGHT => (cell.0 + 1, cell.1),
+    }
+}
+
+fn direction_between(from: Cell, to: Cell) -> Option<Direction> {
+    DIRECTIONS.iter().copied().find(|direction| step_cell(from, *direction) == to)
+}
+
+struct Board {
+    columns:    i32,
+    rows:       i32,
+    // How many more moves each cell stays occupied for. 0 is free. Our own
+    //  tail is 1 because it moves out of the way on the next move.
+    blocked:    Vec<u32>,
+    // Our own body, tail first and head last, like `Snake::parts`
+    body:       VecDeque<Cell>,
+    growing:    bool,
+    current:    Direction,
+    apple:      Cell
+}
+
+impl Board {
+    fn from_game(game: &Game, player_index: usize) -> Self {
+        let area    = game.play_area;
+        let columns = (area.width  / CELL_SIZE) as i32;
+        let rows    = (area.height / CELL_SIZE) as i32;
+        let to_cell = |x: f32, y: f32| -> Cell {
+            (((x - area.x) / CELL_SIZE).floor() as i32, ((y - area.y) / CELL_SIZE).floor() as i32)
+        };
+
+        let mut board = Board {
+            columns:    columns,
+            rows:       rows,
+            blocked:    vec![0; (columns * rows).max(0) as usize],
+            body:       VecDeque::new(),
+            growing:    game.players[player_index].enlarge_snake,
+            current:    game.players[player_index].snake.head().direction,
+            apple:      to_cell(game.apple.x, game.apple.y)
+        };
+
+        // Walls never move out of the way
+        for (x, y) in &game.walls {
+            let cell = to_cell(*x, *y);
+            if board.in_bounds(cell) {
+                let slot = board.index(cell);
+                board.blocked[slot] = u32::MAX;
+            }
+        }
+
+        for (index, player) in game.players.iter().enumerate() {
+            if !player.alive && index != player_index {
+                continue;
+            }
+
+            for (part_index, part) in player.snake.parts.iter().enumerate() {
+                let cell = to_cell(part.x, part.y);
+                if index == player_index {
+                    board.body.push_back(cell);
+                }
+                if !board.in_bounds(cell) {
+                    continue;
+                }
+
+                // Parts nearer the tail free up sooner. Other snakes might grow
+                //  so for them we play it safe and count every part as staying.
+                //  The starting snake stacks several parts on one cell, taking
+                //  the max keeps the count right for those.
+                let moves_until_free = if index == player_index { part_index as u32 + 1 } else { u32::MAX };
+                let slot = board.index(cell);
+                board.blocked[slot] = board.blocked[slot].max(moves_until_free);
+            }
+        }
+
+        board
+    }
+
+    fn in_bounds(self: &Self, cell: Cell) -> bool {
+        cell.0 >= 0 && cell.1 >= 0 && cell.0 < self.columns && cell.1 < self.rows
+    }
+
+    fn index(self: &Self, cell: Cell) -> usize {
+        (cell.1 * self.columns + cell.0) as usize
+    }
+
+    fn head(self: &Self) -> Cell {
+        *self.body.back().unwrap()
+    }
+
+    fn tail(self: &Self) -> Cell {
+        *self.body.front().unwrap()
+    }
+
+    // Is it safe to be in `cell` after `moves` more moves?
+    fn is_free_after(self: &Self, cell: Cell, moves: u32) -> bool {
+        if !self.in_bounds(cell) {
+            return false;
+        }
+        let extra = if self.growing { 1 } else { 0 };
+        self.blocked[self.index(cell)].saturating_add(extra) <= moves
+    }
+
+    fn safe_directions(self: &Self) -> Vec<Direction> {
+        DIRECTIONS.iter().copied()
+            .filter(|direction| *direction != self.current.opposite())
+            .filter(|direction| self.is_free_after(step_cell(self.head(), *direction), 1))
+            .collect()
+    }
+
+    // Breadth first search from our head, returns the path (excluding the head)
+    //  to the first cell that matches `is_goal`. Cells are considered walkable
+    //  if they will have been vacated by the time we get there.
+    fn shortest_path(self: &Self, is_goal: impl Fn(Cell) -> bool) -> Option<Vec<Cell>> {
+        let start = self.head();
+        let mut previous: Vec<Option<Cell>> = vec![None; self.blocked.len()];
+        let mut visited:  Vec<bool>         = vec![false; self.blocked.len()];
+        let mut queue: VecDeque<(Cell, u32)> = VecDeque::new();
+
+        visited[self.index(start)] = true;
+        queue.push_back((start, 0));
+
+        while let Some((cell, distance)) = queue.pop_front() {
+            if cell != start && is_goal(cell) {
+                let mut path = vec![cell];
+                let mut current = cell;
+                while let Some(before) = previous[self.index(current)] {
+                    if before == start {
+                        break;
+                    }
+                    path.push(before);
+                    current = before;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for direction in DIRECTIONS {
+                if cell == start && direction == self.current.opposite() {
+                    continue;
+                }
+
+                let next = step_cell(cell, direction);
+                if !self.in_bounds(next) || visited[self.index(next)] {
+                    continue;
+                }
+                if !is_goal(next) && !self.is_free_after(next, distance + 1) {
+                    continue;
+                }
+
+                visited[self.index(next)] = true;
+                previous[self.index(next)] = Some(cell);
+                queue.push_back((next, distance + 1));
+            }
+        }
+
+        None
+    }
+
+    // Number of cells reachable from `start`, used to avoid walking into pockets
+    fn reachable_area(self: &Self, start: Cell) -> usize {
+        if !self.is_free_after(start, 1) {
+            return 0;
+        }
+
+        let mut visited = vec![false; self.blocked.len()];
+        let mut queue: VecDeque<Cell> = VecDeque::new();
+        visited[self.index(start)] = true;
+        queue.push_back(start);
+
+        let mut count = 0;
+        while let Some(cell) = queue.pop_front() {
+            count += 1;
+            for direction in DIRECTIONS {
+                let next = step_cell(cell, direction);
+                if self.in_bounds(next) && !visited[self.index(next)] && self.is_free_after(next, 2) {
+                    visited[self.index(next)] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        count
+    }
+
+    // What the board would look like after following `path`, eating the apple
+    //  at the end of it. Only our own snake moves.
+    fn after_following(self: &Self, path: &[Cell]) -> Board {
+        let mut body = self.body.clone();
+        let mut growing = self.growing;
+        for cell in path {
+            body.push_back(*cell);
+            if !growing {
+                body.pop_front();
+            }
+            growing = *cell == self.apple;
+        }
+
+        let mut blocked: Vec<u32> = self.blocked.iter()
+            .map(|moves| if *moves == u32::MAX { u32::MAX } else { 0 })
+            .collect();
+        for (part_index, cell) in body.iter().enumerate() {
+            if self.in_bounds(*cell) {
+                let slot = self.index(*cell);
+                blocked[slot] = blocked[slot].max(part_index as u32 + 1);
+            }
+        }
+
+        let current = if path.len() >= 2 {
+            direction_between(path[path.len() - 2], path[path.len() - 1]).unwrap_or(self.current)
+        } else if path.len() == 1 {
+            direction_between(self.head(), path[0]).unwrap_or(self.current)
+        } else {
+            self.current
+        };
+
+        Board {
+            columns:    self.columns,
+            rows:       self.rows,
+            blocked:    blocked,
+            body:       body,
+            growing:    growing,
+            current:    current,
+            apple:      (-1, -1)
+        }
+    }
+
+    fn can_reach_tail(self: &Self) -> bool {
+        let tail = self.tail();
+        self.shortest_path(|cell| cell == tail).is_some()
+    }
+
+    // Fallback when nothing better is found, go where there is the most room
+    fn most_room(self: &Self) -> Option<Direction> {
+        self.safe_directions().into_iter()
+            .max_by_key(|direction| self.reachable_area(step_cell(self.head(), *direction)))
+    }
+}
+
+pub fn choose_direction(game: &Game, player_index: usize, strategy: Strategy) -> Direction {
+    let current = game.players[player_index].snake.head_direction;
+    if !game.players[player_index].alive {
+        return current;
+    }
+
+    let board = Board::from_game(game, player_index);
+    let choice = match strategy {
+        Strategy::GREEDY      => choose_greedy(&board),
+        Strategy::PATHFINDING => choose_pathfinding(&board),
+        Strategy::HAMILTONIAN => choose_hamiltonian(&board),
+    };
+
+    choice.unwrap_or(current)
+}
+
+// Heads straight for the apple, only avoiding moves that are instantly fatal
+fn choose_greedy(board: &Board) -> Option<Direction> {
+    let head = board.head();
+    let distance = |cell: Cell| (cell.0 - board.apple.0).abs() + (cell.1 - board.apple.1).abs();
+
+    board.safe_directions().into_iter()
+        .min_by_key(|direction| {
+            // Prefer carrying on straight when two moves are just as good
+            let keep_going = if *direction == board.current { 0 } else { 1 };
+            (distance(step_cell(head, *direction)), keep_going)
+        })
+}
+
+// Shortest path to the apple, but only if we could still reach our own tail
+//  after eating it. Otherwise chase our tail until it is safe again.
+fn choose_pathfinding(board: &Board) -> Option<Direction> {
+    let head = board.head();
+    let apple = board.apple;
+
+    if let Some(path) = board.shortest_path(|cell| cell == apple) {
+        if board.after_following(&path).can_reach_tail() {
+            return direction_between(head, path[0]);
+        }
+    }
+
+    let tail = board.tail();
+    if let Some(path) = board.shortest_path(|cell| cell == tail) {
+        // Take the neighbour toward the tail that leaves the most room
+        if let Some(direction) = direction_between(head, path[0]) {
+            if board.reachable_area(path[0]) > 1 {
+                return Some(direction);
+            }
+        }
+    }
+
+    board.most_room()
+}
+
+// @note: Builds a cycle that visits every cell exactly once. With an even
+//  number of rows it goes along the top row, zig-zags back and forth over
+//  every column but the first, then comes back up the first column. With odd
+//  rows but even columns we do the same thing transposed. If both are odd there
+//  is no such cycle and None is returned.
+fn hamiltonian_order(columns: i32, rows: i32) -> Option<Vec<usize>> {
+    if columns < 2 || rows < 2 {
+        return None;
+    }
+    if rows % 2 != 0 {
+        if columns % 2 != 0 {
+            return None;
+        }
+        let transposed = hamiltonian_order(rows, columns)?;
+        let mut order = vec![0; (columns * rows) as usize];
+        for column in 0..columns {
+            for row in 0..rows {
+                order[(row * columns + column) as usize] = transposed[(column * rows + row) as usize];
+            }
+        }
+        return Some(order);
+    }
+
+    let mut cycle: Vec<Cell> = Vec::new();
+    for column in 0..columns {
+        cycle.push((column, 0));
+    }
+    for row in 1..rows {
+        if row % 2 == 1 {
+            for column in (1..columns).rev() {
+                cycle.push((column, row));
+            }
+        } else {
+            for column in 1..columns {
+                cycle.push((column, row));
+            }
+        }
+    }
+    for row in (1..rows).rev() {
+        cycle.push((0, row));
+    }
+
+    let mut order = vec![0; (columns * rows) as usize];
+    for (position, cell) in cycle.iter().enumerate() {
+        order[(cell.1 * columns + cell.0) as usize] = position;
+    }
+    Some(order)
+}
+
+// Follows the cycle, which can never lose on an empty board. While the snake is
+//  short it is allowed to cut across the cycle toward the apple as long as the
+//  shortcut doesn't jump past its own tail.
+fn choose_hamiltonian(board: &Board) -> Option<Direction> {
+    let order = match hamiltonian_order(board.columns, board.rows) {
+        Some(order) => order,
+        None => return choose_pathfinding(board),
+    };
+
+    let cell_count = order.len();
+    let position = |cell: Cell| order[board.index(cell)];
+    let distance = |from: usize, to: usize| (to + cell_count - from) % cell_count;
+
+    let head = board.head();
+    if !board.in_bounds(head) {
+        return None;
+    }
+    let head_position = position(head);
+
+    let tail = board.tail();
+    let to_tail  = if board.in_bounds(tail) { distance(head_position, position(tail)) } else { cell_count };
+    let to_apple = if board.in_bounds(board.apple) { distance(head_position, position(board.apple)) } else { 1 };
+
+    let safe = board.safe_directions();
+    let mut best: Option<(Direction, usize)> = None;
+    for direction in &safe {
+        let next = step_cell(head, *direction);
+        let skip = distance(head_position, position(next));
+        if skip == 0 {
+            continue;
+        }
+
+        let is_next_on_cycle = skip == 1;
+        let allow_shortcut = board.body.len() * 2 < cell_count
+            && skip <= to_apple
+            && skip + 4 < to_tail;
+
+        if (is_next_on_cycle || allow_shortcut) && best.is_none_or(|(_, best_skip)| skip > best_skip) {
+            best = Some((*direction, skip));
+        }
+    }
+
+    match best {
+        Some((direction, _)) => Some(direction),
+        // The snake isn't on the cycle yet (it can start facing the wrong way
+        //  for it), find our way onto it like a normal pathfinder would.
+        None => choose_pathfinding(board),
+    }
+}
+
+// Steers every computer controlled player. `strategies` is indexed by player,
+//  None means that player is controlled by someone else.
+pub fn steer_ai_players(game: &mut Game, strategies: &[Option<Strategy>]) {
+    for (index, strategy) in strategies.iter().enumerate() {
+        if index >= game.players.len() {
+            break;
+        }
+        if let Some(strategy) = strategy {
+            let direction = choose_direction(game, index, *strategy);
+            crate::game::steer_snake(&mut game.players[index].snake, direction);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{self, GameMode, TICKS_PER_MOVE};
+
+    // The small, medium and large boards from the settings
+    const BOARD_SIZES: [(i32, i32); 3] = [(16, 10), (24, 14), (30, 20)];
+
+    fn is_cycle(order: &[usize], columns: i32, rows: i32) -> bool {
+        let count = (columns * rows) as usize;
+        let mut cells: Vec<Cell> = vec![(0, 0); count];
+        for row in 0..rows {
+            for column in 0..columns {
+                let position = order[(row * columns + column) as usize];
+                if position >= count {
+                    return false;
+                }
+                cells[position] = (column, row);
+            }
+        }
+        (0..count).all(|position| {
+            let (from, to) = (cells[position], cells[(position + 1) % count]);
+            (from.0 - to.0).abs() + (from.1 - to.1).abs() == 1
+        })
+    }
+
+    #[test]
+    fn hamiltonian_cycle_covers_every_board_size() {
+        for (columns, rows) in BOARD_SIZES {
+            let order = hamiltonian_order(columns, rows).unwrap();
+            assert!(is_cycle(&order, columns, rows), "{}x{}", columns, rows);
+        }
+    }
+
+    #[test]
+    fn hamiltonian_cycle_handles_odd_sides() {
+        // Odd rows are done transposed
+        let order = hamiltonian_order(4, 5).unwrap();
+        assert!(is_cycle(&order, 4, 5));
+        assert!(hamiltonian_order(5, 5).is_none());
+        assert!(hamiltonian_order(1, 6).is_none());
+    }
+
+    // Running head first into the other snake as it moves into the same cell
+    //  doesn't count, that cell was free when the move was picked
+    fn survives(game: &Game, direction: Direction) -> bool {
+        let mut game = game.clone();
+        game::steer_snake(&mut game.players[0].snake, direction);
+        for _ in 0..TICKS_PER_MOVE {
+            game.step();
+        }
+        let head_on = game.players[0].death.is_some_and(|death| {
+            game.players.iter().skip(1).any(|other| (other.snake.head().x, other.snake.head().y) == (death.x, death.y))
+        });
+        game.players[0].alive || head_on
+    }
+
+    // @note: Whenever some move keeps the snake alive, the one the computer
+    //  picks has to as well
+    #[test]
+    fn never_steers_into_a_wall_or_a_body() {
+        for (strategy, mode) in STRATEGIES.iter().flat_map(|strategy| [(*strategy, GameMode::SOLO), (*strategy, GameMode::VERSUS)]) {
+            for (seed, (columns, rows)) in BOARD_SIZES.iter().enumerate() {
+                let area = game::Bounds { x: 20.0, y: 40.0, width: *columns as f32 * CELL_SIZE, height: *rows as f32 * CELL_SIZE };
+                let walls = (3..8).map(|row| (area.x + 10.0 * CELL_SIZE, area.y + row as f32 * CELL_SIZE)).collect();
+                let mut game = Game::with_walls(mode, area, walls, seed as u64);
+
+                for _ in 0..2000 {
+                    if game.round_over || !game.players[0].alive {
+                        break;
+                    }
+                    let chosen = choose_direction(&game, 0, strategy);
+                    let current = game.players[0].snake.head().direction;
+                    let any_safe = DIRECTIONS.iter().any(|direction| *direction != current.opposite() && survives(&game, *direction));
+                    if any_safe {
+                        assert!(survives(&game, chosen), "{} on {}x{} picked {:?} at tick {}", strategy_name(strategy), columns, rows, chosen, game.tick);
+                    }
+                    game::steer_snake(&mut game.players[0].snake, chosen);
+                    // The other snake, if there is one, plays greedy
+                    steer_ai_players(&mut game, &[None, Some(Strategy::GREEDY)]);
+                    game.step();
+                }
+            }
+        }
+    }
+}
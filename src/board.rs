@@ -0,0 +1,191 @@
+use raylib::prelude::*;
+
+use snake::game::{Bounds, Game, CELL_SIZE};
+
+use crate::settings::{self, BackgroundFit, BoardGrid, Settings};
+
+// @note: The board is drawn in layers, back to front: the background over
+//  the whole screen but darkened, the background again at full brightness
+//  inside the play area, the grid, the frame around the edge that kills and
+//  finally the walls. Everything outside the frame is out of bounds, so it
+//  shouldn't look like somewhere a snake can go.
+
+// How much of the background's brightness is left outside the board
+const OUTSIDE_BRIGHTNESS: f32 = 0.55;
+// Drawn past the edges of the screen so a shaking camera doesn't show the
+//  clear colour behind it
+const OVERSCAN: f32 = 40.0;
+
+const FRAME_WIDTH:     f32   = 4.0;
+const FRAME_COLOR:     Color = Color { r: 62,  g: 48,  b: 36,  a: 255 };
+const FRAME_HIGHLIGHT: Color = Color { r: 255, g: 255, b: 255, a: 50 };
+
+const CHECKER_COLOR: Color = Color { r: 0, g: 0, b: 0, a: 16 };
+const LINE_COLOR:    Color = Color { r: 0, g: 0, b: 0, a: 28 };
+
+const WALL_COLOR:     Color = Color { r: 96,  g: 90,  b: 84,  a: 255 };
+const WALL_HIGHLIGHT: Color = Color { r: 150, g: 144, b: 136, a: 255 };
+const WALL_SHADOW:    Color = Color { r: 52,  g: 48,  b: 44,  a: 255 };
+const WALL_DROP:      Color = Color { r: 0,   g: 0,   b: 0,   a: 60 };
+// Width of a wall's lit and shaded edges
+const WALL_EDGE: f32 = 3.0;
+
+// Which neighbours of a wall are walls too, one bit per side
+const UP:    u8 = 1;
+const RIGHT: u8 = 2;
+const DOWN:  u8 = 4;
+const LEFT:  u8 = 8;
+
+// The background on its own for the screens without a board
+pub fn draw_backdrop(draw_context: &mut impl RaylibDraw, background: &Texture2D, screen: Rectangle) {
+    draw_background(draw_context, background, screen, BackgroundFit::STRETCHED, Color::WHITE);
+}
+
+pub fn draw_board(draw_context: &mut impl RaylibDraw, background: &Texture2D, game: &Game, screen: Rectangle, settings: &Settings) {
+    let tint = settings::theme_background_tint(settings.theme);
+    let board = to_rectangle(game.play_area);
+
+    let outside = Rectangle {
+        x:      screen.x - OVERSCAN,
+        y:      screen.y - OVERSCAN,
+        width:  screen.width  + OVERSCAN * 2.0,
+        height: screen.height + OVERSCAN * 2.0
+    };
+    draw_background(draw_context, background, outside, BackgroundFit::STRETCHED, darken(tint, OUTSIDE_BRIGHTNESS));
+    draw_background(draw_context, background, board, settings.background_fit, tint);
+
+    draw_grid(draw_context, board, settings.grid);
+    draw_frame(draw_context, board);
+    draw_walls(draw_context, game);
+}
+
+fn draw_background(draw_context: &mut impl RaylibDraw, background: &Texture2D, area: Rectangle, fit: BackgroundFit, tint: Color) {
+    let texture_width  = background.width  as f32;
+    let texture_height = background.height as f32;
+
+    match fit {
+        BackgroundFit::STRETCHED => {
+            let source = Rectangle { x: 0.0, y: 0.0, width: texture_width, height: texture_height };
+            draw_context.draw_texture_pro(background, source, area, Vector2::zero(), 0.0, tint);
+        },
+        // Lined up with the board's top left corner, the tiles on the right
+        //  and bottom edges are cut off where the area ends
+        BackgroundFit::TILED => {
+            let mut y = area.y;
+            while y < area.y + area.height {
+                let mut x = area.x;
+                while x < area.x + area.width {
+                    let source = Rectangle {
+                        x:      0.0,
+                        y:      0.0,
+                        width:  texture_width.min(area.x + area.width - x),
+                        height: texture_height.min(area.y + area.height - y)
+                    };
+                    draw_context.draw_texture_rec(background, source, Vector2 { x: x, y: y }, tint);
+                    x += texture_width;
+                }
+                y += texture_height;
+            }
+        },
+    }
+}
+
+fn draw_grid(draw_context: &mut impl RaylibDraw, board: Rectangle, grid: BoardGrid) {
+    let columns = (board.width  / CELL_SIZE) as i32;
+    let rows    = (board.height / CELL_SIZE) as i32;
+
+    match grid {
+        BoardGrid::OFF => {},
+        BoardGrid::CHECKERBOARD => {
+            for row in 0..rows {
+                for column in (row % 2..columns).step_by(2) {
+                    let cell = Rectangle {
+                        x:      board.x + column as f32 * CELL_SIZE,
+                        y:      board.y + row    as f32 * CELL_SIZE,
+                        width:  CELL_SIZE,
+                        height: CELL_SIZE
+                    };
+                    draw_context.draw_rectangle_rec(cell, CHECKER_COLOR);
+                }
+            }
+        },
+        // Only the lines between cells, the frame covers the outside ones
+        BoardGrid::LINES => {
+            let (left, top) = (board.x as i32, board.y as i32);
+            let (right, bottom) = ((board.x + board.width) as i32, (board.y + board.height) as i32);
+            for column in 1..columns {
+                let x = left + column * CELL_SIZE as i32;
+                draw_context.draw_line(x, top, x, bottom, LINE_COLOR);
+            }
+            for row in 1..rows {
+                let y = top + row * CELL_SIZE as i32;
+                draw_context.draw_line(left, y, right, y, LINE_COLOR);
+            }
+        },
+    }
+}
+
+// Sits just outside the play area, so the last cell on every side is still
+//  fully visible
+fn draw_frame(draw_context: &mut impl RaylibDraw, board: Rectangle) {
+    let frame = Rectangle {
+        x:      board.x - FRAME_WIDTH,
+        y:      board.y - FRAME_WIDTH,
+        width:  board.width  + FRAME_WIDTH * 2.0,
+        height: board.height + FRAME_WIDTH * 2.0
+    };
+    draw_context.draw_rectangle_lines_ex(frame, FRAME_WIDTH, FRAME_COLOR);
+    draw_context.draw_rectangle_lines_ex(frame, 1.0, FRAME_HIGHLIGHT);
+}
+
+// @note: Walls that touch are drawn as one block. Each wall looks at its four
+//  neighbours and only puts the lit edge (top and left) or the shaded edge
+//  (bottom and right) on the sides that face open floor, and only casts a
+//  shadow onto the floor below it.
+fn draw_walls(draw_context: &mut impl RaylibDraw, game: &Game) {
+    for (x, y) in &game.walls {
+        let neighbours = wall_neighbours(game, *x, *y);
+        let cell = Rectangle { x: *x, y: *y, width: CELL_SIZE, height: CELL_SIZE };
+        draw_context.draw_rectangle_rec(cell, WALL_COLOR);
+
+        if neighbours & UP == 0 {
+            draw_context.draw_rectangle_rec(Rectangle { height: WALL_EDGE, ..cell }, WALL_HIGHLIGHT);
+        }
+        if neighbours & LEFT == 0 {
+            draw_context.draw_rectangle_rec(Rectangle { width: WALL_EDGE, ..cell }, WALL_HIGHLIGHT);
+        }
+        if neighbours & DOWN == 0 {
+            draw_context.draw_rectangle_rec(Rectangle { y: cell.y + CELL_SIZE - WALL_EDGE, height: WALL_EDGE, ..cell }, WALL_SHADOW);
+
+            let below = *y + CELL_SIZE;
+            if below < game.play_area.y + game.play_area.height {
+                draw_context.draw_rectangle_rec(Rectangle { y: below, height: WALL_EDGE, ..cell }, WALL_DROP);
+            }
+        }
+        if neighbours & RIGHT == 0 {
+            draw_context.draw_rectangle_rec(Rectangle { x: cell.x + CELL_SIZE - WALL_EDGE, width: WALL_EDGE, ..cell }, WALL_SHADOW);
+        }
+    }
+}
+
+fn wall_neighbours(game: &Game, x: f32, y: f32) -> u8 {
+    let mut neighbours = 0;
+    if game.is_wall(x, y - CELL_SIZE) { neighbours |= UP; }
+    if game.is_wall(x + CELL_SIZE, y) { neighbours |= RIGHT; }
+    if game.is_wall(x, y + CELL_SIZE) { neighbours |= DOWN; }
+    if game.is_wall(x - CELL_SIZE, y) { neighbours |= LEFT; }
+    neighbours
+}
+
+fn darken(color: Color, brightness: f32) -> Color {
+    Color {
+        r: (color.r as f32 * brightness) as u8,
+        g: (color.g as f32 * brightness) as u8,
+        b: (color.b as f32 * brightness) as u8,
+        a: color.a
+    }
+}
+
+fn to_rectangle(bounds: Bounds) -> Rectangle {
+    Rectangle { x: bounds.x, y: bounds.y, width: bounds.width, height: bounds.height }
+}
@@ -0,0 +1,433 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cli;
+use crate::food::FoodType;
+use crate::game::{self, Bounds, Direction, Game, GameMode, StepResult, CELL_SIZE};
+
+// @note: External agents. Each bot is a child process that gets one JSON line
+//  on stdin every tick and answers with one line on stdout naming the direction
+//  it wants: `up`, `down`, `left` or `right`. Anything else (or no answer in
+//  time) means "keep going". The answer is steered in like keyboard input, so
+//  the game still enforces every movement and collision rule. A state line
+//  looks like this, cells are in grid coordinates and segments are head first:
+//
+//  {"tick":12,"round":1,"you":0,"done":false,"grid":{"width":30,"height":20},
+//   "food":{"x":9,"y":8,"type":"apple","time_left":4.1,"points":3},
+//   "snakes":[{"player":0,"alive":true,"score":0,"direction":"right",
+//              "segments":[[5,4],[4,4],[3,4]]}]}
+//
+// When a match ends every bot gets a final line with "done":true and stdin is
+//  closed. Whatever a bot writes to stderr goes straight to our stderr.
+//
+// Headless matches wait for every answer (`request_direction`). A match that's
+//  being drawn can't stall the frame, so it never waits (`poll_direction`): a
+//  new state only goes out once the last one was answered or timed out, and
+//  an answer is steered in on the first tick after it arrives.
+
+// How long we wait for an answer before the snake just carries on
+pub const BOT_RESPONSE_TIMEOUT: Duration = Duration::from_millis(250);
+
+// Bots that never crash and never eat would otherwise play a round forever
+const DEFAULT_MAX_TICKS: u64 = 20_000;
+
+pub struct Bot {
+    pub command:    String,
+    child:          Child,
+    stdin:          Option<ChildStdin>,
+    responses:      Receiver<String>,
+    // When the state we're still waiting on an answer for went out
+    waiting_since:  Option<Instant>,
+    pub connected:  bool
+}
+
+impl Bot {
+    // `command` is split on whitespace, e.g. "python3 my_bot.py"
+    pub fn spawn(command: &str) -> Result<Self, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("Empty bot command".to_string())?;
+
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|error| format!("Failed to start bot '{}': {}", command, error))?;
+
+        let stdin  = child.stdin.take();
+        let stdout = child.stdout.take().ok_or(format!("No stdout for bot '{}'", command))?;
+
+        // Reading blocks, so lines are read on their own thread and handed over
+        //  through a channel we can wait on with a timeout.
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() { break; },
+                    Err(_)   => break,
+                }
+            }
+        });
+
+        Ok(Bot {
+            command:        command.to_string(),
+            child:          child,
+            stdin:          stdin,
+            responses:      receiver,
+            waiting_since:  None,
+            connected:      true
+        })
+    }
+
+    fn send_line(self: &mut Self, line: &str) {
+        let written = match self.stdin.as_mut() {
+            Some(stdin) => writeln!(stdin, "{}", line).and_then(|_| stdin.flush()).is_ok(),
+            None        => false,
+        };
+        if !written && self.connected {
+            println!("Bot '{}' stopped listening", self.command);
+            self.connected = false;
+        }
+    }
+
+    // Sends the state and waits for the answer. Late answers from earlier ticks
+    //  are skipped so a slow bot can't fall further and further behind.
+    pub fn request_direction(self: &mut Self, game: &Game, player_index: usize) -> Option<Direction> {
+        if !self.connected {
+            return None;
+        }
+
+        while self.responses.try_recv().is_ok() {}
+        self.waiting_since = None;
+
+        self.send_line(&encode_state(game, player_index, false));
+        if !self.connected {
+            return None;
+        }
+
+        match self.responses.recv_timeout(BOT_RESPONSE_TIMEOUT) {
+            Ok(line) => parse_direction(&line),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => {
+                println!("Bot '{}' exited", self.command);
+                self.connected = false;
+                None
+            },
+        }
+    }
+
+    // Never blocks. Returns the answer to the last state sent if it has come
+    //  in, and sends the current state once the bot is free to answer it.
+    pub fn poll_direction(self: &mut Self, game: &Game, player_index: usize) -> Option<Direction> {
+        if !self.connected {
+            return None;
+        }
+
+        let mut direction = None;
+        loop {
+            match self.responses.try_recv() {
+                // Only the answer to what we asked, anything past that is late
+                Ok(line) => if self.waiting_since.take().is_some() {
+                    direction = parse_direction(&line);
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    println!("Bot '{}' exited", self.command);
+                    self.connected = false;
+                    return None;
+                },
+            }
+        }
+
+        if self.waiting_since.is_some_and(|sent| sent.elapsed() >= BOT_RESPONSE_TIMEOUT) {
+            self.waiting_since = None;
+        }
+        if self.waiting_since.is_none() {
+            self.send_line(&encode_state(game, player_index, false));
+            self.waiting_since = Some(Instant::now());
+        }
+
+        direction
+    }
+
+    pub fn finish(self: &mut Self, game: &Game, player_index: usize) {
+        if self.connected {
+            self.send_line(&encode_state(game, player_index, true));
+        }
+        self.stdin = None;
+    }
+}
+
+impl Drop for Bot {
+    fn drop(self: &mut Self) {
+        self.stdin = None;
+        // Give well behaved bots a moment to exit on their own after stdin closes
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn map_direction_to_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::UP    => "up",
+        Direction::LEFT  => "left",
+        Direction::DOWN  => "down",
+        Direction::RIGHT => "right",
+    }
+}
+
+fn map_food_type_to_name(food_type: FoodType) -> &'static str {
+    match food_type {
+        FoodType::APPLE  => "apple",
+        FoodType::GOLDEN => "golden",
+    }
+}
+
+// Accepts the bare word, optionally quoted, in any case
+pub fn parse_direction(line: &str) -> Option<Direction> {
+    match line.trim().trim_matches('"').to_lowercase().as_str() {
+        "up"    => Some(Direction::UP),
+        "left"  => Some(Direction::LEFT),
+        "down"  => Some(Direction::DOWN),
+        "right" => Some(Direction::RIGHT),
+        _       => None,
+    }
+}
+
+pub fn encode_state(game: &Game, player_index: usize, done: bool) -> String {
+    let area = game.play_area;
+    let to_cell = |x: f32, y: f32| -> (i32, i32) {
+        (((x - area.x) / CELL_SIZE).floor() as i32, ((y - area.y) / CELL_SIZE).floor() as i32)
+    };
+
+    let (food_x, food_y) = to_cell(game.apple.x, game.apple.y);
+    let food = format!(
+        "{{\"x\":{},\"y\":{},\"type\":\"{}\",\"time_left\":{:.3},\"points\":{}}}",
+        food_x, food_y, map_food_type_to_name(game.apple.food_type), game.apple.time_left.max(0.0), game.apple.score_value()
+    );
+
+    let snakes: Vec<String> = game.players.iter().enumerate().map(|(index, player)| {
+        let segments: Vec<String> = player.snake.parts.iter().rev()
+            .map(|part| {
+                let (x, y) = to_cell(part.x, part.y);
+                format!("[{},{}]", x, y)
+            })
+            .collect();
+
+        format!(
+            "{{\"player\":{},\"alive\":{},\"score\":{},\"direction\":\"{}\",\"segments\":[{}]}}",
+            index, player.alive, player.score, map_direction_to_name(player.snake.head().direction), segments.join(",")
+        )
+    }).collect();
+
+    format!(
+        "{{\"tick\":{},\"round\":{},\"you\":{},\"done\":{},\"grid\":{{\"width\":{},\"height\":{}}},\"food\":{},\"snakes\":[{}]}}",
+        game.tick, game.round, player_index, done,
+        (area.width / CELL_SIZE) as i32, (area.height / CELL_SIZE) as i32,
+        food, snakes.join(",")
+    )
+}
+
+// A game where every player is a bot. One bot plays solo, two play versus.
+pub struct BotMatch {
+    pub game:   Game,
+    // Indexed by player
+    bots:       Vec<Bot>
+}
+
+impl BotMatch {
+    pub fn new(commands: &[String], play_area: Bounds, seed: u64) -> Result<Self, String> {
+        let mode = match commands.len() {
+            1 => GameMode::SOLO,
+            2 => GameMode::VERSUS,
+            _ => return Err(format!("Expected one or two bot commands, got {}", commands.len())),
+        };
+
+        let mut bots = Vec::new();
+        for command in commands {
+            bots.push(Bot::spawn(command)?);
+        }
+
+        Ok(BotMatch {
+            game:   Game::new(mode, play_area, seed),
+            bots:   bots
+        })
+    }
+
+    // `wait` blocks until every bot answered or timed out, see the note at the top
+    fn steer_bots(game: &mut Game, bots: &mut [Bot], wait: bool) {
+        for (index, bot) in bots.iter_mut().enumerate() {
+            if index >= game.players.len() || !game.players[index].alive {
+                continue;
+            }
+            let answer = if wait { bot.request_direction(game, index) } else { bot.poll_direction(game, index) };
+            if let Some(direction) = answer {
+                game::steer_snake(&mut game.players[index].snake, direction);
+            }
+        }
+    }
+
+    pub fn step(self: &mut Self) -> StepResult {
+        BotMatch::steer_bots(&mut self.game, &mut self.bots, true);
+        self.game.step()
+    }
+
+    // For the render loop, never waits on the bots
+    pub fn update(self: &mut Self, frame_time: f32) -> StepResult {
+        let bots = &mut self.bots;
+        self.game.update(frame_time, |game| BotMatch::steer_bots(game, bots, false))
+    }
+
+    pub fn bot_names(self: &Self) -> Vec<String> {
+        self.bots.iter().map(|bot| bot.command.clone()).collect()
+    }
+
+    pub fn finish(self: &mut Self) {
+        for (index, bot) in self.bots.iter_mut().enumerate() {
+            bot.finish(&self.game, index);
+        }
+    }
+}
+
+// The `snake bot-headless` subcommand. Plays a whole match as fast as the bots
+//  answer and prints the result.
+pub fn run_headless(args: &[String], play_area: Bounds) -> Result<(), String> {
+    if cli::wants_help(args) {
+        println!("{}", cli::BOT_HEADLESS_USAGE);
+        return Ok(());
+    }
+
+    let mut commands = Vec::new();
+    let mut seed = rand::random();
+    let mut max_ticks = DEFAULT_MAX_TICKS;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" | "--max-ticks" => {
+                let value = args.next().ok_or(format!("Missing value for {}\n\n{}", arg, cli::BOT_HEADLESS_USAGE))?;
+                let number = cli::parse_number(arg, value)?;
+                if arg == "--seed" { seed = number } else { max_ticks = number }
+            },
+            other if other.starts_with("--") => return Err(format!("Unknown option '{}'\n\n{}", other, cli::BOT_HEADLESS_USAGE)),
+            command => commands.push(command.to_string()),
+        }
+    }
+
+    let mut bot_match = BotMatch::new(&commands, play_area, seed)?;
+    println!("Seed {}", seed);
+
+    loop {
+        bot_match.step();
+        if bot_match.game.tick >= max_ticks && !bot_match.game.round_over {
+            println!("Round {}: stopped after {} ticks", bot_match.game.round, bot_match.game.tick);
+            break;
+        }
+        if !bot_match.game.round_over {
+            continue;
+        }
+
+        let game = &bot_match.game;
+        match game.mode {
+            GameMode::SOLO => {
+                println!("Game over after {} ticks, score {}", game.tick, game.players[0].score);
+            },
+            GameMode::VERSUS => match game.round_winner() {
+                Some(winner) => println!("Round {}: player {} wins after {} ticks", game.round, winner + 1, game.tick),
+                None         => println!("Round {}: draw after {} ticks", game.round, game.tick),
+            },
+        }
+
+        if game.is_match_over() {
+            break;
+        }
+        bot_match.game.start_round();
+    }
+
+    for (index, name) in bot_match.bot_names().iter().enumerate() {
+        let player = &bot_match.game.players[index];
+        println!("Player {} ({}): {} points, {} rounds", index + 1, name, player.score, player.round_wins);
+    }
+    bot_match.finish();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::{default_food_settings, Apple};
+
+    // Two snakes a couple of moves in on a 12x10 board that doesn't start at
+    //  the screen's corner, with the apple somewhere known
+    fn small_game() -> Game {
+        let area = Bounds { x: 20.0, y: 40.0, width: 240.0, height: 200.0 };
+        let mut game = Game::new(GameMode::VERSUS, area, 1);
+        game.step();
+        game.step();
+
+        let mut settings = default_food_settings(FoodType::GOLDEN);
+        settings.early_bonus = 0;
+        game.apple = Apple::new(area.x + 2.0 * CELL_SIZE, area.y + 7.0 * CELL_SIZE, FoodType::GOLDEN, settings);
+        game.apple.time_left = 1.25;
+        game.players[1].alive = false;
+        game.players[1].score = 4;
+        game
+    }
+
+    #[test]
+    fn state_lines_are_exact() {
+        let expected = concat!(
+            r#"{"tick":2,"round":1,"you":1,"done":false,"grid":{"width":12,"height":10},"#,
+            r#""food":{"x":2,"y":7,"type":"golden","time_left":1.250,"points":3},"#,
+            r#""snakes":[{"player":0,"alive":true,"score":0,"direction":"right","segments":[[6,4],[5,4],[4,4]]},"#,
+            r#"{"player":1,"alive":false,"score":4,"direction":"left","segments":[[5,5],[6,5],[7,5]]}]}"#
+        );
+        assert_eq!(encode_state(&small_game(), 1, false), expected);
+    }
+
+    #[test]
+    fn the_last_line_says_done() {
+        let mut game = small_game();
+        game.apple.time_left = -0.5;
+        let line = encode_state(&game, 0, true);
+        assert!(line.starts_with(r#"{"tick":2,"round":1,"you":0,"done":true,"#), "{}", line);
+        // An apple that has run out isn't sent with negative time
+        assert!(line.contains(r#""time_left":0.000,"#), "{}", line);
+    }
+
+    #[test]
+    fn headless_option_errors_are_returned() {
+        let area = game::play_area_for_screen(640, 480);
+        let run = |args: &[&str]| run_headless(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>(), area);
+        assert!(run(&["--max-ticks", "soon", "bot"]).unwrap_err().starts_with("Expected a number for --max-ticks"));
+        assert!(run(&["bot", "--seed"]).unwrap_err().starts_with("Missing value for --seed"));
+        assert!(run(&["--fast", "bot"]).unwrap_err().starts_with("Unknown option '--fast'"));
+        assert_eq!(run(&["--seed", "3"]).unwrap_err(), "Expected one or two bot commands, got 0");
+        assert!(run(&["a", "b", "c"]).unwrap_err().starts_with("Expected one or two bot commands"));
+    }
+
+    #[test]
+    fn directions_are_read_loosely() {
+        assert_eq!(parse_direction("up"), Some(Direction::UP));
+        assert_eq!(parse_direction("LEFT\r"), Some(Direction::LEFT));
+        assert_eq!(parse_direction("  \"down\"  "), Some(Direction::DOWN));
+        assert_eq!(parse_direction("Right"), Some(Direction::RIGHT));
+    }
+
+    #[test]
+    fn anything_else_keeps_going() {
+        for line in ["", "u", "upward", "north", "{\"direction\":\"up\"}", "up down", "'up'"] {
+            assert_eq!(parse_direction(line), None, "accepted {:?}", line);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use raylib::prelude::*;
+use rand::*;
+
+use snake::events::{GameEvent, Subscriber};
+use snake::food::FoodType;
+use snake::game::{Game, CELL_SIZE};
+
+use crate::palette::{self, Palette};
+
+// @note: Everything that makes the game feel punchy without changing what
+//  happens in it. The world is drawn through a `Camera2D` so shaking it moves
+//  the board but not the HUD, hit-stop holds the simulation for a moment by
+//  handing the game less frame time, and the head pulse and score popups are
+//  just extra drawing. `MotionLevel` scales all of it down for anyone who gets
+//  motion sick, OFF keeps the popups (they're information) but nothing moves.
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum MotionLevel {
+    FULL,
+    REDUCED,
+    OFF
+}
+
+pub const MOTION_LEVELS: [MotionLevel; 3] = [MotionLevel::FULL, MotionLevel::REDUCED, MotionLevel::OFF];
+
+pub fn motion_level_name(motion: MotionLevel) -> &'static str {
+    match motion {
+        MotionLevel::FULL    => "Full",
+        MotionLevel::REDUCED => "Reduced",
+        MotionLevel::OFF     => "Off",
+    }
+}
+
+// @note: Shake is driven by "trauma" between 0 and 1 that events add to and
+//  that drains over time. The offset grows with trauma squared so small hits
+//  barely move the board and a death really rattles it.
+const DEATH_TRAUMA:         f32 = 0.6;
+const TRAUMA_DECAY:         f32 = 1.5;
+const SHAKE_MAX_OFFSET:     f32 = 10.0;
+const SHAKE_MAX_ROTATION:   f32 = 1.5;
+
+const HIT_STOP_DURATION:    f32 = 0.05;
+
+const PULSE_DURATION:       f32 = 0.25;
+const PULSE_STRENGTH:       f32 = 0.25;
+
+const POPUP_DURATION:       f32 = 0.8;
+const POPUP_RISE:           f32 = 30.0;
+
+pub const UNSCALED: Vector2 = Vector2 { x: 1.0, y: 1.0 };
+
+struct ScorePopup {
+    text:   String,
+    x:      f32,
+    y:      f32,
+    color:  Color,
+    age:    f32
+}
+
+pub struct GameCamera {
+    pub motion:     MotionLevel,
+    pub palette:    Palette,
+    // Shaking rotates around this, the middle of the screen
+    center:         Vector2,
+    trauma:         f32,
+    hit_stop:       f32,
+    // Time left on each player's head pulse
+    pulses:         Vec<f32>,
+    popups:         Vec<ScorePopup>
+}
+
+impl GameCamera {
+    pub fn new(motion: MotionLevel, palette: Palette, center: Vector2) -> Self {
+        GameCamera {
+            motion:     motion,
+            palette:    palette,
+            center:     center,
+            trauma:     0.0,
+            hit_stop:   0.0,
+            pulses:     Vec::new(),
+            popups:     Vec::new()
+        }
+    }
+
+    fn intensity(self: &Self) -> f32 {
+        match self.motion {
+            MotionLevel::FULL    => 1.0,
+            MotionLevel::REDUCED => 0.3,
+            MotionLevel::OFF     => 0.0,
+        }
+    }
+
+    // Drops whatever is still going, e.g. when a new game starts
+    pub fn reset(self: &mut Self) {
+        self.trauma   = 0.0;
+        self.hit_stop = 0.0;
+        self.pulses.clear();
+        self.popups.clear();
+    }
+
+    // Returns how much of the frame the game should get to simulate, which is
+    //  less than all of it while a hit-stop is holding things still.
+    pub fn update(self: &mut Self, frame_time: f32) -> f32 {
+        self.trauma = (self.trauma - TRAUMA_DECAY * frame_time).max(0.0);
+        for pulse in &mut self.pulses {
+            *pulse = (*pulse - frame_time).max(0.0);
+        }
+        for popup in &mut self.popups {
+            popup.age += frame_time;
+        }
+        self.popups.retain(|popup| popup.age < POPUP_DURATION);
+
+        let stopped = self.hit_stop.min(frame_time);
+        self.hit_stop -= stopped;
+        frame_time - stopped
+    }
+
+    pub fn camera(self: &Self) -> Camera2D {
+        let shake = self.trauma * self.trauma * self.intensity();
+        let mut rng = thread_rng();
+
+        Camera2D {
+            offset: Vector2 {
+                x: self.center.x + SHAKE_MAX_OFFSET * shake * rng.gen_range(-1.0..=1.0),
+                y: self.center.y + SHAKE_MAX_OFFSET * shake * rng.gen_range(-1.0..=1.0)
+            },
+            target:     self.center,
+            rotation:   SHAKE_MAX_ROTATION * shake * rng.gen_range(-1.0..=1.0),
+            zoom:       1.0
+        }
+    }
+
+    // @note: Stretches along the sprite first and then squashes back, the
+    //  sine goes through one full wave while the pulse fades out.
+    pub fn head_scale(self: &Self, player_index: usize) -> Vector2 {
+        let time_left = self.pulses.get(player_index).copied().unwrap_or(0.0);
+        if time_left <= 0.0 {
+            return UNSCALED;
+        }
+
+        let progress = 1.0 - time_left / PULSE_DURATION;
+        let amount = PULSE_STRENGTH * self.intensity() * (progress * std::f32::consts::TAU).sin() * (1.0 - progress);
+        Vector2 { x: 1.0 - amount, y: 1.0 + amount }
+    }
+
+    // Draws the world through the camera, with the score popups on top of it
+    pub fn draw<D: RaylibDraw>(self: &Self, draw_context: &mut D, draw_world: impl FnOnce(&mut RaylibMode2D<D>)) {
+        let mut world = draw_context.begin_mode2D(self.camera());
+        draw_world(&mut world);
+
+        for popup in &self.popups {
+            let progress = popup.age / POPUP_DURATION;
+            let rise = if self.motion == MotionLevel::OFF { 0.0 } else { POPUP_RISE * progress };
+            world.draw_text(
+                &popup.text,
+                popup.x as i32,
+                (popup.y - rise - CELL_SIZE) as i32,
+                20,
+                popup.color.fade(1.0 - progress)
+            );
+        }
+    }
+}
+
+impl Subscriber for GameCamera {
+    fn on_event(self: &mut Self, game: &Game, event: &GameEvent) {
+        match *event {
+            GameEvent::EATEN(player_index, apple) => {
+                if self.motion == MotionLevel::FULL {
+                    self.hit_stop = HIT_STOP_DURATION;
+                }
+
+                self.pulses.resize(game.players.len(), 0.0);
+                if let Some(pulse) = self.pulses.get_mut(player_index) {
+                    *pulse = PULSE_DURATION;
+                }
+
+                self.popups.push(ScorePopup {
+                    text:   format!("+{}", apple.score_value()),
+                    x:      apple.x,
+                    y:      apple.y,
+                    color:  if apple.food_type == FoodType::GOLDEN { palette::colors(self.palette).golden } else { palette::colors(self.palette).good },
+                    age:    0.0
+                });
+            },
+            GameEvent::DIED(_, _) => {
+                self.trauma = (self.trauma + DEATH_TRAUMA).min(1.0);
+            },
+            _ => {},
+        }
+    }
+}
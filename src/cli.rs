@@ -0,0 +1,647 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::time::Instant;
+
+use crate::achievements::{Achievements, ACHIEVEMENTS};
+use crate::ai::{self, Strategy};
+use crate::game::{Bounds, Game, GameMode};
+use crate::highscores::HighScores;
+use crate::level::Level;
+use crate::replay::{self, Recording};
+use crate::stats::StatsTracker;
+use crate::tournament;
+
+// @note: The subcommands that script the game and its tools. Each one has a
+//  USAGE text that `--help` prints, and the ones that don't need a window run
+//  from here. `play` and `replay` only have their options parsed here, the
+//  window side of them lives in main.rs. Anything that goes wrong comes back
+//  as an error message for the caller to print and exit non-zero on.
+
+pub const USAGE: &str = "\
+Usage: snake [command] [options]
+
+Commands:
+  play                  Play in a window, the default (see play --help)
+  replay <file>         Watch a recorded game
+  sim                   Play headless games with the computer and report them
+  bench                 Measure how fast the simulation and the AI run
+  validate-level <file> Check a level file can be played
+  export-stats          Write your stats, high scores and achievements out
+  tournament            Compare the AI strategies over many games
+  server [address]      Run a headless versus server
+  client [address]      Join a server
+  host [address]        Run a server and join it
+  spectate [address]    Watch a server or a broadcast game
+  broadcast [address]   Play locally and let others watch
+  bot <cmd> [cmd]       Play one or two external bots in a window
+  bot-headless <cmd>... The same without a window
+
+Anywhere on the command line:
+  --set <key=value>     Override a setting for this run, e.g. --set audio.music_volume=0.5
+  --settings <file>     Read and save settings from this file instead
+
+Run `snake <command> --help` for a command's options.";
+
+pub const PLAY_USAGE: &str = "\
+Usage: snake play [options]
+
+Opens the game. With --mode it skips the title screen.
+
+Options:
+  --mode <mode>         solo, versus, computer, demo or practice
+  --seed <n>            Seed for the first game, later games are random
+  --level <file>        Play on a level (see validate-level --help)
+  --difficulty <level>  easy, normal or hard, how well the computer plays
+  --fullscreen          Start in fullscreen
+  --record <file>       Where to save the replay of each finished game
+                        (default last_replay.txt in the data directory)
+  --help                Show this message";
+
+pub const REPLAY_USAGE: &str = "\
+Usage: snake replay <file> [options]
+
+Plays back a game saved by `snake play`.
+
+Options:
+  --speed <x>           Playback speed, 0.1 to 16 (default 1)
+  --summary             Print how the game ended instead of opening a window
+  --help                Show this message";
+
+pub const SIM_USAGE: &str = "\
+Usage: snake sim [options]
+
+Plays headless games with the computer steering every snake, one line of
+csv per game followed by a summary.
+
+Options:
+  --games <n>           Games to play (default 10)
+  --seed <n>            First seed, games use seed..seed+games (default 0)
+  --mode <solo|versus>  One snake or two against each other (default solo)
+  --strategy <name>     greedy, pathfinding or hamiltonian (default pathfinding)
+  --level <file>        Play on a level
+  --max-ticks <n>       Stop a game that is still going after this many ticks (default 20000)
+  --help                Show this message";
+
+pub const BOT_HEADLESS_USAGE: &str = "\
+Usage: snake bot-headless <command> [command] [options]
+
+Plays one bot solo or two against each other without a window and prints
+how each round went. A command with arguments has to be quoted, e.g.
+\"python3 my_bot.py\".
+
+Options:
+  --seed <n>            Seed of the match (default random, printed first)
+  --max-ticks <n>       Stop a round that is still going after this many ticks (default 20000)
+  --help                Show this message";
+
+pub const BENCH_USAGE: &str = "\
+Usage: snake bench [options]
+
+Runs the simulation as fast as it goes with the computer steering and
+reports ticks per second for each strategy.
+
+Options:
+  --ticks <n>           Ticks per strategy (default 100000)
+  --seed <n>            Seed of the first game (default 0)
+  --strategies <list>   Comma separated (default all)
+  --level <file>        Play on a level
+  --help                Show this message";
+
+pub const VALIDATE_LEVEL_USAGE: &str = "\
+Usage: snake validate-level <file> [file...]
+
+Checks each level is well formed and playable: only '.' and '#' cells,
+rows of the same width, small enough for the screen, both snakes clear
+of walls at the start and no floor walled off. Exits non-zero if any
+level has a problem.
+
+Options:
+  --help                Show this message";
+
+pub const EXPORT_STATS_USAGE: &str = "\
+Usage: snake export-stats [options]
+
+Writes lifetime stats, high scores and achievements from the data
+directory (SNAKE_DATA_DIR overrides where that is).
+
+Options:
+  --format <csv|json>   Output format (default csv)
+  --out <file>          Write here instead of stdout
+  --help                Show this message";
+
+const RECORD_FILE: &str = "last_replay.txt";
+
+// What `snake replay --speed` accepts, inf or NaN would never finish
+const MIN_REPLAY_SPEED: f32 = 0.1;
+const MAX_REPLAY_SPEED: f32 = 16.0;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum PlayMode {
+    SOLO,
+    VERSUS,
+    // Versus against the computer
+    COMPUTER,
+    DEMO,
+    PRACTICE
+}
+
+pub const PLAY_MODES: [PlayMode; 5] = [PlayMode::SOLO, PlayMode::VERSUS, PlayMode::COMPUTER, PlayMode::DEMO, PlayMode::PRACTICE];
+
+pub fn play_mode_name(mode: PlayMode) -> &'static str {
+    match mode {
+        PlayMode::SOLO     => "solo",
+        PlayMode::VERSUS   => "versus",
+        PlayMode::COMPUTER => "computer",
+        PlayMode::DEMO     => "demo",
+        PlayMode::PRACTICE => "practice",
+    }
+}
+
+// @note: Difficulty is how well the computer plays, so it just picks its
+//  strategy
+pub fn parse_difficulty(name: &str) -> Option<Strategy> {
+    match name {
+        "easy"   => Some(Strategy::GREEDY),
+        "normal" => Some(Strategy::PATHFINDING),
+        "hard"   => Some(Strategy::HAMILTONIAN),
+        _        => None,
+    }
+}
+
+pub fn wants_help(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == "--help" || arg == "-h")
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PlayOptions {
+    pub mode:       Option<PlayMode>,
+    pub seed:       Option<u64>,
+    pub level:      Option<String>,
+    pub difficulty: Option<Strategy>,
+    pub fullscreen: bool,
+    pub record:     Option<String>
+}
+
+impl PlayOptions {
+    pub fn record_path(self: &Self) -> std::path::PathBuf {
+        match &self.record {
+            Some(path) => std::path::PathBuf::from(path),
+            None       => crate::storage::data_file(RECORD_FILE),
+        }
+    }
+}
+
+pub fn parse_play_options(args: &[String]) -> Result<PlayOptions, String> {
+    let mut options = PlayOptions::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--fullscreen" {
+            options.fullscreen = true;
+            continue;
+        }
+
+        let known = ["--mode", "--seed", "--level", "--difficulty", "--record"];
+        if !known.contains(&arg.as_str()) {
+            return Err(format!("Unknown option '{}'\n\n{}", arg, PLAY_USAGE));
+        }
+        let value = args.next().ok_or(format!("Missing value for {}\n\n{}", arg, PLAY_USAGE))?;
+
+        match arg.as_str() {
+            "--mode" => {
+                let mode = PLAY_MODES.iter().copied().find(|mode| play_mode_name(*mode) == value);
+                options.mode = Some(mode.ok_or(format!("Unknown mode '{}', expected solo, versus, computer, demo or practice", value))?);
+            },
+            "--seed"       => options.seed = Some(parse_number(arg, value)?),
+            "--level"      => options.level = Some(value.clone()),
+            "--difficulty" => {
+                let strategy = parse_difficulty(value).ok_or(format!("Unknown difficulty '{}', expected easy, normal or hard", value))?;
+                options.difficulty = Some(strategy);
+            },
+            "--record"     => options.record = Some(value.clone()),
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(options)
+}
+
+#[derive(Clone, Debug)]
+pub struct ReplayOptions {
+    pub file:       String,
+    pub speed:      f32,
+    pub summary:    bool
+}
+
+pub fn parse_replay_options(args: &[String]) -> Result<ReplayOptions, String> {
+    let mut file = None;
+    let mut speed = 1.0;
+    let mut summary = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--summary" => summary = true,
+            "--speed"   => {
+                let value = args.next().ok_or(format!("Missing value for --speed\n\n{}", REPLAY_USAGE))?;
+                speed = value.parse::<f32>().ok()
+                    .filter(|speed| (MIN_REPLAY_SPEED..=MAX_REPLAY_SPEED).contains(speed))
+                    .ok_or(format!("Expected a speed from {} to {} for --speed, got '{}'", MIN_REPLAY_SPEED, MAX_REPLAY_SPEED, value))?;
+            },
+            other if other.starts_with("--") => return Err(format!("Unknown option '{}'\n\n{}", other, REPLAY_USAGE)),
+            other => {
+                if file.is_some() {
+                    return Err(format!("Only one replay at a time, got '{}' as well\n\n{}", other, REPLAY_USAGE));
+                }
+                file = Some(other.to_string());
+            },
+        }
+    }
+
+    Ok(ReplayOptions {
+        file:       file.ok_or(format!("Which replay?\n\n{}", REPLAY_USAGE))?,
+        speed:      speed,
+        summary:    summary
+    })
+}
+
+pub fn load_recording(path: &str) -> Result<Recording, String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("Failed to read replay {}: {}", path, error))?;
+    Recording::from_text(&text).map_err(|error| format!("{}: {}", path, error))
+}
+
+// The `snake replay <file> --summary` output
+pub fn print_replay_summary(recording: &Recording) {
+    let game = replay::play_to_end(recording);
+    println!(
+        "{} game, seed {}, {} rounds, {} ticks",
+        replay::map_mode_to_name(recording.mode), recording.seed, recording.rounds.len(), recording.ticks()
+    );
+    for (index, player) in game.players.iter().enumerate() {
+        let death = player.death.map(|death| tournament::map_death_cause_to_name(Some(death.cause))).unwrap_or("alive");
+        println!(
+            "P{}: score {}, length {}, {} round wins, {}",
+            index + 1, player.score, player.snake.parts.len(), player.round_wins, death
+        );
+    }
+}
+
+// The board and its walls for a level, or the whole play area without one
+fn level_board(level: Option<&String>, play_area: Bounds) -> Result<(Bounds, Vec<(f32, f32)>), String> {
+    let Some(path) = level else {
+        return Ok((play_area, Vec::new()));
+    };
+    let level = Level::load(path, play_area).map_err(|problems| format!("{} can't be played:\n{}", path, problems))?;
+    let area = level.play_area(play_area);
+    Ok((area, level.walls_in(area)))
+}
+
+pub fn parse_number(arg: &str, value: &str) -> Result<u64, String> {
+    value.parse::<u64>().map_err(|_| format!("Expected a number for {}, got '{}'", arg, value))
+}
+
+// The seeds of `games` games in a row from `first_seed`, an error if they'd
+//  run past the last one
+pub fn seeds(first_seed: u64, games: u64) -> Result<impl Iterator<Item = u64>, String> {
+    if first_seed.checked_add(games.saturating_sub(1)).is_none() {
+        return Err(format!("{} games from seed {} run past the last seed, {}", games, first_seed, u64::MAX));
+    }
+    Ok((0..games).map(move |offset| first_seed + offset))
+}
+
+// The `snake sim` subcommand
+pub fn run_sim(args: &[String], play_area: Bounds) -> Result<(), String> {
+    if wants_help(args) {
+        println!("{}", SIM_USAGE);
+        return Ok(());
+    }
+
+    let mut games = 10;
+    let mut first_seed = 0;
+    let mut mode = GameMode::SOLO;
+    let mut strategy = Strategy::PATHFINDING;
+    let mut level = None;
+    let mut max_ticks = 20_000;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let known = ["--games", "--seed", "--mode", "--strategy", "--level", "--max-ticks"];
+        if !known.contains(&arg.as_str()) {
+            return Err(format!("Unknown option '{}'\n\n{}", arg, SIM_USAGE));
+        }
+        let value = args.next().ok_or(format!("Missing value for {}\n\n{}", arg, SIM_USAGE))?;
+
+        match arg.as_str() {
+            "--games"     => games      = parse_number(arg, value)?,
+            "--seed"      => first_seed = parse_number(arg, value)?,
+            "--max-ticks" => max_ticks  = parse_number(arg, value)?,
+            "--level"     => level      = Some(value.clone()),
+            "--strategy"  => strategy   = ai::parse_strategy(value).ok_or(format!("Unknown strategy '{}'", value))?,
+            "--mode"      => {
+                mode = match value.as_str() {
+                    "solo"   => GameMode::SOLO,
+                    "versus" => GameMode::VERSUS,
+                    other    => return Err(format!("Unknown mode '{}', expected solo or versus", other)),
+                };
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    let (area, walls) = level_board(level.as_ref(), play_area)?;
+    let strategies = vec![Some(strategy); mode.player_count()];
+    let seeds = seeds(first_seed, games)?;
+
+    let mut stdout = io::stdout().lock();
+    let write_error = |error: io::Error| format!("Failed to write the results: {}", error);
+    writeln!(stdout, "seed,ticks,winner{}", (1..=mode.player_count()).map(|player| format!(",p{0}_score,p{0}_length,p{0}_death", player)).collect::<String>())
+        .map_err(write_error)?;
+
+    let mut total_score = 0u64;
+    let mut total_ticks = 0u64;
+    for seed in seeds {
+        let mut game = Game::with_walls(mode, area, walls.clone(), seed);
+        while !game.round_over && game.tick < max_ticks {
+            ai::steer_ai_players(&mut game, &strategies);
+            game.step();
+        }
+
+        let winner = game.round_winner().map_or("none".to_string(), |winner| format!("p{}", winner + 1));
+        let players: String = game.players.iter().map(|player| {
+            format!(",{},{},{}", player.score, player.snake.parts.len(), tournament::map_death_cause_to_name(player.death.map(|death| death.cause)))
+        }).collect();
+        writeln!(stdout, "{},{},{}{}", seed, game.tick, winner, players).map_err(write_error)?;
+
+        total_score += game.players.iter().map(|player| player.score as u64).sum::<u64>();
+        total_ticks += game.tick;
+    }
+
+    let count = games.max(1) as f64;
+    writeln!(
+        stdout, "\n{} {} games with {}: mean score {:.2} per snake, mean ticks {:.1}",
+        games, replay::map_mode_to_name(mode), ai::strategy_name(strategy),
+        total_score as f64 / count / mode.player_count() as f64, total_ticks as f64 / count
+    ).map_err(write_error)
+}
+
+// The `snake bench` subcommand
+pub fn run_bench(args: &[String], play_area: Bounds) -> Result<(), String> {
+    if wants_help(args) {
+        println!("{}", BENCH_USAGE);
+        return Ok(());
+    }
+
+    let mut ticks = 100_000;
+    let mut seed = 0;
+    let mut strategies = ai::STRATEGIES.to_vec();
+    let mut level = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let known = ["--ticks", "--seed", "--strategies", "--level"];
+        if !known.contains(&arg.as_str()) {
+            return Err(format!("Unknown option '{}'\n\n{}", arg, BENCH_USAGE));
+        }
+        let value = args.next().ok_or(format!("Missing value for {}\n\n{}", arg, BENCH_USAGE))?;
+
+        match arg.as_str() {
+            "--ticks" => ticks = parse_number(arg, value)?.max(1),
+            "--seed"  => seed  = parse_number(arg, value)?,
+            "--level" => level = Some(value.clone()),
+            "--strategies" => {
+                strategies = value.split(',')
+                    .map(|name| ai::parse_strategy(name.trim()).ok_or(format!("Unknown strategy '{}'", name)))
+                    .collect::<Result<Vec<Strategy>, String>>()?;
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    let (area, walls) = level_board(level.as_ref(), play_area)?;
+
+    // @note: A game that ends is replaced with the next seed so every strategy
+    //  runs exactly `ticks` ticks, the time includes setting those games up
+    for strategy in strategies {
+        let mut game_seed = seed;
+        let mut game = Game::with_walls(GameMode::SOLO, area, walls.clone(), game_seed);
+        let mut games = 1;
+
+        let started = Instant::now();
+        for _ in 0..ticks {
+            if game.round_over {
+                game_seed = game_seed.checked_add(1).ok_or(format!("Ran out of seeds after {}", game_seed))?;
+                games += 1;
+                game = Game::with_walls(GameMode::SOLO, area, walls.clone(), game_seed);
+            }
+            ai::steer_ai_players(&mut game, &[Some(strategy)]);
+            game.step();
+        }
+        let seconds = started.elapsed().as_secs_f64();
+
+        println!(
+            "{:<12} {} ticks over {} games in {:.3}s, {:.0} ticks/s, {:.2} us per tick",
+            ai::strategy_name(strategy), ticks, games, seconds, ticks as f64 / seconds.max(f64::EPSILON), seconds * 1_000_000.0 / ticks as f64
+        );
+    }
+    Ok(())
+}
+
+// The `snake validate-level` subcommand
+pub fn run_validate_level(args: &[String], play_area: Bounds) -> Result<(), String> {
+    if wants_help(args) || args.is_empty() {
+        println!("{}", VALIDATE_LEVEL_USAGE);
+        return if args.is_empty() { Err("No level given".to_string()) } else { Ok(()) };
+    }
+
+    let mut failed = 0;
+    for path in args {
+        let problems = match fs::read_to_string(path) {
+            Err(error) => vec![format!("Failed to read it: {}", error)],
+            Ok(text) => match Level::parse(&text) {
+                Err(errors) => errors,
+                Ok(level) => {
+                    let problems = level.problems(play_area);
+                    if problems.is_empty() {
+                        let name = if level.name.is_empty() { "unnamed" } else { level.name.as_str() };
+                        println!("{}: ok, {} ({}x{}, {} walls)", path, name, level.columns, level.rows, level.walls.len());
+                    }
+                    problems
+                },
+            },
+        };
+
+        if !problems.is_empty() {
+            failed += 1;
+            println!("{}:", path);
+            for problem in problems {
+                println!("  {}", problem);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} of {} levels have problems", failed, args.len()));
+    }
+    Ok(())
+}
+
+// The `snake export-stats` subcommand
+pub fn run_export_stats(args: &[String]) -> Result<(), String> {
+    if wants_help(args) {
+        println!("{}", EXPORT_STATS_USAGE);
+        return Ok(());
+    }
+
+    let mut json = false;
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let known = ["--format", "--out"];
+        if !known.contains(&arg.as_str()) {
+            return Err(format!("Unknown option '{}'\n\n{}", arg, EXPORT_STATS_USAGE));
+        }
+        let value = args.next().ok_or(format!("Missing value for {}\n\n{}", arg, EXPORT_STATS_USAGE))?;
+
+        match arg.as_str() {
+            "--out"    => output = Some(value.clone()),
+            "--format" => {
+                json = match value.as_str() {
+                    "csv"  => false,
+                    "json" => true,
+                    other  => return Err(format!("Unknown format '{}', expected csv or json", other)),
+                };
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    let text = if json { export_json() } else { export_csv() };
+    match output {
+        Some(output) => {
+            File::create(&output).and_then(|mut file| file.write_all(text.as_bytes()))
+                .map_err(|error| format!("Failed to write {}: {}", output, error))?;
+            println!("Wrote {}", output);
+        },
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+// The lifetime stats as (key, value), the same keys as the stats file
+fn stat_entries() -> Vec<(String, String)> {
+    StatsTracker::load().lifetime.to_text().lines()
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| *key != "version")
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn export_csv() -> String {
+    let mut text = String::from("stat,value\n");
+    for (key, value) in stat_entries() {
+        text += &format!("{},{}\n", key, value);
+    }
+
+    text += "\nrank,score,length\n";
+    for (rank, entry) in HighScores::load().entries.iter().enumerate() {
+        text += &format!("{},{},{}\n", rank + 1, entry.score, entry.length);
+    }
+
+    let achievements = Achievements::load();
+    text += "\nachievement,name,unlocked\n";
+    for achievement in &ACHIEVEMENTS {
+        text += &format!("{},{},{}\n", achievement.id, achievement.name, achievements.is_unlocked(achievement.id));
+    }
+    text
+}
+
+fn export_json() -> String {
+    let stats: Vec<String> = stat_entries().iter().map(|(key, value)| format!("    \"{}\": {}", key, value)).collect();
+    let high_scores: Vec<String> = HighScores::load().entries.iter()
+        .map(|entry| format!("    {{\"score\":{},\"length\":{}}}", entry.score, entry.length))
+        .collect();
+    let achievements = Achievements::load();
+    let unlocked: Vec<String> = ACHIEVEMENTS.iter()
+        .map(|achievement| format!("    {{\"id\":\"{}\",\"name\":\"{}\",\"unlocked\":{}}}", achievement.id, achievement.name, achievements.is_unlocked(achievement.id)))
+        .collect();
+
+    format!(
+        "{{\n  \"stats\": {{\n{}\n  }},\n  \"high_scores\": {},\n  \"achievements\": {}\n}}\n",
+        stats.join(",\n"), json_list(&high_scores), json_list(&unlocked)
+    )
+}
+
+fn json_list(items: &[String]) -> String {
+    if items.is_empty() {
+        return "[]".to_string();
+    }
+    format!("[\n{}\n  ]", items.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn play_options_are_parsed() {
+        let options = parse_play_options(&args("--mode versus --seed 42 --level maze.txt --difficulty hard --fullscreen --record out.txt")).unwrap();
+        assert_eq!(options.mode, Some(PlayMode::VERSUS));
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.level.as_deref(), Some("maze.txt"));
+        assert_eq!(options.difficulty, Some(Strategy::HAMILTONIAN));
+        assert!(options.fullscreen);
+        assert_eq!(options.record.as_deref(), Some("out.txt"));
+
+        let options = parse_play_options(&[]).unwrap();
+        assert_eq!(options.mode, None);
+        assert_eq!(options.seed, None);
+        assert!(!options.fullscreen);
+    }
+
+    #[test]
+    fn bad_play_options_are_errors() {
+        for bad in ["--mode tag", "--seed -1", "--seed", "--difficulty impossible", "--speed 2", "solo"] {
+            assert!(parse_play_options(&args(bad)).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn replay_options_are_parsed() {
+        let options = parse_replay_options(&args("game.txt --speed 2.5 --summary")).unwrap();
+        assert_eq!(options.file, "game.txt");
+        assert_eq!(options.speed, 2.5);
+        assert!(options.summary);
+
+        let options = parse_replay_options(&args("--speed 16 game.txt")).unwrap();
+        assert_eq!(options.speed, MAX_REPLAY_SPEED);
+        assert!(!options.summary);
+    }
+
+    #[test]
+    fn bad_replay_options_are_errors() {
+        let bad = ["", "--summary", "a.txt b.txt", "a.txt --speed", "a.txt --speed 0", "a.txt --speed 17", "a.txt --speed NaN", "a.txt --speed inf", "a.txt --loop"];
+        for bad in bad {
+            assert!(parse_replay_options(&args(bad)).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn seeds_stop_at_the_last_one() {
+        assert_eq!(seeds(3, 3).unwrap().collect::<Vec<u64>>(), vec![3, 4, 5]);
+        assert_eq!(seeds(u64::MAX, 1).unwrap().collect::<Vec<u64>>(), vec![u64::MAX]);
+        assert_eq!(seeds(u64::MAX, 0).unwrap().count(), 0);
+        assert!(seeds(u64::MAX, 2).is_err());
+        assert!(seeds(u64::MAX - 1, 3).is_err());
+    }
+
+    #[test]
+    fn sims_past_the_last_seed_are_errors() {
+        let play_area = crate::game::play_area_for_screen(640, 480);
+        assert!(run_sim(&args("--seed 18446744073709551615 --games 2"), play_area).is_err());
+    }
+}
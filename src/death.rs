@@ -0,0 +1,116 @@
+use raylib::prelude::*;
+
+use snake::game::{Game, SnakePart, CELL_SIZE};
+
+use crate::effects::ParticleEffects;
+use crate::palette::{self, Palette};
+use crate::particles::ParticleSystem;
+
+// @note: The game itself is done the tick a snake dies, this is only the
+//  window's side of it. Rather than jumping straight to the round over screen
+//  the round goes PLAYING -> DYING -> OVER, and while it's DYING the dead
+//  snakes come apart one segment at a time from the head back, each segment
+//  bursting into particles as it goes. Nothing but the animation moves and no
+//  input is taken until it reaches OVER.
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum RoundPhase {
+    PLAYING,
+    DYING,
+    OVER
+}
+
+// Time between two segments going, long snakes go faster so the whole thing
+//  never takes more than MAX_CRUMBLE_TIME
+const SEGMENT_INTERVAL: f32 = 0.06;
+const MAX_CRUMBLE_TIME: f32 = 1.2;
+// How long the empty board stays up before the round over screen
+const AFTER_CRUMBLE_PAUSE: f32 = 0.4;
+
+struct Crumbling {
+    player_index:   usize,
+    // Head first, the order they go in
+    parts:          Vec<SnakePart>,
+    interval:       f32,
+    // Segments burst so far
+    burst:          usize
+}
+
+pub struct DeathSequence {
+    pub phase:  RoundPhase,
+    snakes:     Vec<Crumbling>,
+    time:       f32
+}
+
+impl DeathSequence {
+    pub fn new() -> Self {
+        DeathSequence {
+            phase:  RoundPhase::PLAYING,
+            snakes: Vec::new(),
+            time:   0.0
+        }
+    }
+
+    pub fn reset(self: &mut Self) {
+        self.phase = RoundPhase::PLAYING;
+        self.snakes.clear();
+        self.time = 0.0;
+    }
+
+    // Called when the round ends. Goes straight to OVER if nobody died, e.g.
+    //  a solo snake that filled the board.
+    pub fn start(self: &mut Self, game: &Game) {
+        self.reset();
+
+        for (index, player) in game.players.iter().enumerate() {
+            if player.death.is_none() || player.snake.parts.is_empty() {
+                continue;
+            }
+            let parts: Vec<SnakePart> = player.snake.parts.iter().rev().copied().collect();
+            self.snakes.push(Crumbling {
+                player_index:   index,
+                interval:       SEGMENT_INTERVAL.min(MAX_CRUMBLE_TIME / parts.len() as f32),
+                parts:          parts,
+                burst:          0
+            });
+        }
+
+        self.phase = if self.snakes.is_empty() { RoundPhase::OVER } else { RoundPhase::DYING };
+    }
+
+    pub fn update(self: &mut Self, frame_time: f32, particle_system: &mut ParticleSystem, particle_effects: &mut ParticleEffects, palette: Palette) {
+        if self.phase != RoundPhase::DYING {
+            return;
+        }
+        self.time += frame_time;
+
+        let mut finished = true;
+        for snake in &mut self.snakes {
+            let due = ((self.time / snake.interval) as usize).min(snake.parts.len());
+            for part in &snake.parts[snake.burst..due] {
+                let position = Vector2 { x: part.x + CELL_SIZE / 2.0, y: part.y + CELL_SIZE / 2.0 };
+                particle_effects.burst(particle_system, "segment_crumble", position, palette::player_tint(palette, snake.player_index));
+            }
+            snake.burst = due;
+
+            let crumble_time = snake.interval * snake.parts.len() as f32;
+            if self.time < crumble_time + AFTER_CRUMBLE_PAUSE {
+                finished = false;
+            }
+        }
+
+        if finished {
+            self.phase = RoundPhase::OVER;
+        }
+    }
+
+    // @note: How many segments of the player's snake are gone, counted from
+    //  the head. The fraction is how far the next one has faded, it bursts
+    //  once it's all the way gone.
+    pub fn crumbled(self: &Self, player_index: usize) -> f32 {
+        match self.snakes.iter().find(|snake| snake.player_index == player_index) {
+            Some(snake) => (self.time / snake.interval).min(snake.parts.len() as f32),
+            None        => 0.0,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use raylib::prelude::*;
+
+// @note: The game is always drawn at the same resolution into `target` and
+//  only scaled up to the window when the frame is shown, so the layout, the
+//  HUD and the mouse all keep working in the same 640x480 coordinates however
+//  big the window is. What doesn't fill the window is left black on the sides
+//  (or the top and bottom) to keep the aspect ratio.
+//
+// With integer scaling on, the frame only grows in whole steps so every 20x20
+//  cell stays exactly N pixels wide. Otherwise it fills as much of the window
+//  as it can and is smoothed to hide the uneven pixels.
+
+// Drawing into the frame, what every draw function takes instead of the
+//  window's draw handle
+pub type Frame<'a, 'b> = RaylibTextureMode<'a, &'b mut RaylibHandle>;
+
+const BORDER_COLOR: Color = Color::BLACK;
+
+// Leaves room for the taskbar and the title bar when picking a window size
+const MONITOR_MARGIN: f32 = 0.9;
+
+pub struct Display {
+    pub target:             RenderTexture2D,
+    pub fullscreen:         bool,
+    pub integer_scaling:    bool,
+    width:                  f32,
+    height:                 f32,
+    // What the window and the texture are set to right now
+    is_fullscreen:          bool,
+    is_smoothed:            bool
+}
+
+impl Display {
+    pub fn new(mut raylib: &mut RaylibHandle, thread: &RaylibThread, width: u32, height: u32) -> Result<Self, String> {
+        let mut target = raylib.load_render_texture(thread, width, height)?;
+        target.set_texture_filter(thread, TextureFilter::TEXTURE_FILTER_POINT);
+        raylib.begin_texture_mode(thread, &mut target).clear_background(BORDER_COLOR);
+
+        raylib.set_window_min_size(width as i32 / 2, height as i32 / 2);
+        fit_window(raylib, width as f32, height as f32);
+
+        Ok(Display {
+            target:             target,
+            fullscreen:         false,
+            integer_scaling:    false,
+            width:              width as f32,
+            height:             height as f32,
+            is_fullscreen:      false,
+            is_smoothed:        false
+        })
+    }
+
+    // Shows the last frame drawn into `target` and polls the input for the
+    //  next one. Call once at the top of every frame, before anything reads
+    //  input or draws.
+    pub fn present(self: &mut Self, raylib: &mut RaylibHandle, thread: &RaylibThread) {
+        if raylib.is_key_pressed(KeyboardKey::KEY_F11) {
+            self.fullscreen = !self.fullscreen;
+        }
+        // @note: Borderless rather than exclusive fullscreen, it keeps the
+        //  desktop resolution and alt-tabs cleanly. The letterboxing takes
+        //  care of whatever aspect ratio the monitor has.
+        if self.fullscreen != self.is_fullscreen {
+            raylib.toggle_borderless_windowed();
+            self.is_fullscreen = self.fullscreen;
+        }
+
+        let view = self.view(raylib.get_screen_width() as f32, raylib.get_screen_height() as f32);
+        let smoothed = view.width % self.width != 0.0 || view.height % self.height != 0.0;
+        if smoothed != self.is_smoothed {
+            let filter = if smoothed { TextureFilter::TEXTURE_FILTER_BILINEAR } else { TextureFilter::TEXTURE_FILTER_POINT };
+            self.target.set_texture_filter(thread, filter);
+            self.is_smoothed = smoothed;
+        }
+
+        // The mouse reads in frame coordinates, same as everything is drawn in
+        raylib.set_mouse_offset(Vector2 { x: -view.x, y: -view.y });
+        raylib.set_mouse_scale(self.width / view.width, self.height / view.height);
+
+        let mut draw_context = raylib.begin_drawing(thread);
+        draw_context.clear_background(BORDER_COLOR);
+        // Render textures are stored upside down
+        let source = Rectangle { x: 0.0, y: 0.0, width: self.width, height: -self.height };
+        draw_context.draw_texture_pro(&self.target, source, view, Vector2::zero(), 0.0, Color::WHITE);
+    }
+
+    // Where the frame goes in a window of the given size
+    fn view(self: &Self, window_width: f32, window_height: f32) -> Rectangle {
+        let mut scale = (window_width / self.width).min(window_height / self.height);
+        // Below 1x there's no whole step to take, shrinking it is all that's left
+        if self.integer_scaling && scale >= 1.0 {
+            scale = scale.floor();
+        }
+
+        let width  = (self.width  * scale).round();
+        let height = (self.height * scale).round();
+        Rectangle {
+            x:      ((window_width  - width)  / 2.0).floor(),
+            y:      ((window_height - height) / 2.0).floor(),
+            width:  width,
+            height: height
+        }
+    }
+}
+
+// @note: On a HiDPI monitor a 640x480 window comes out tiny, so it opens at
+//  the monitor's scale instead, rounded to a whole step and as big as still
+//  fits on the monitor.
+fn fit_window(raylib: &mut RaylibHandle, width: f32, height: f32) {
+    let dpi = raylib.get_window_scale_dpi();
+    let monitor = get_current_monitor();
+    let fits = (get_monitor_width(monitor)  as f32 * MONITOR_MARGIN / width)
+        .min(get_monitor_height(monitor) as f32 * MONITOR_MARGIN / height)
+        .floor();
+
+    let scale = dpi.x.max(dpi.y).round().min(fits).max(1.0);
+    if scale > 1.0 {
+        raylib.set_window_size((width * scale) as i32, (height * scale) as i32);
+    }
+}
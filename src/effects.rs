@@ -0,0 +1,518 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use raylib::prelude::*;
+
+use crate::particles::{EmitterId, EmitterSettings, EmitterShape, ParticleBlend, ParticleLook, ParticleSystem, ATLAS_SPRITES};
+
+// @note: Particle effects are defined by name in a small TOML file, see the
+//  comment at the top of it for what each key means. Only the bits of TOML
+//  that file needs are understood: `[sections]`, `key = value`, numbers,
+//  strings, booleans and (nested) lists.
+//
+// The file that shipped with the game is built in too and whatever the file
+//  on disk defines goes on top of it, so a missing or broken file still leaves
+//  the default effects. While running the file is checked for changes every so
+//  often and reloaded, a version that doesn't parse is reported and the last
+//  good one is kept.
+
+const BUILT_IN_EFFECTS: &str = include_str!("../assets/particles.toml");
+
+const RELOAD_CHECK_INTERVAL: f32 = 0.5;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    NUMBER(f32),
+    TEXT(String),
+    BOOL(bool),
+    LIST(Vec<Value>)
+}
+
+struct Effect {
+    settings:   EmitterSettings,
+    // Indices into `settings.colors` that get the color passed in when triggered
+    tinted:     Vec<usize>
+}
+
+pub struct ParticleEffects {
+    effects:        HashMap<String, Effect>,
+    modified:       Option<SystemTime>,
+    since_check:    f32,
+    // Goes up on every reload, for anyone holding on to a running emitter
+    pub generation: u32,
+    // Names that were asked for but don't exist, so each is only reported once
+    missing:        HashSet<String>
+}
+
+impl ParticleEffects {
+    pub fn load() -> Self {
+        let mut particle_effects = ParticleEffects {
+            effects:        HashMap::new(),
+            modified:       None,
+            since_check:    0.0,
+            generation:     0,
+            missing:        HashSet::new()
+        };
+
+        match parse_effects(BUILT_IN_EFFECTS) {
+            Ok(effects) => particle_effects.effects = effects,
+            Err(error)  => println!("WARN: Built in particle effects don't parse: {}", error),
+        }
+        particle_effects.reload_if_changed();
+        particle_effects
+    }
+
+    // @note: Put together with Path::join rather than written out like the
+    //  other assets, std::fs only takes '\' as a separator on windows
+    fn path() -> PathBuf {
+        Path::new("assets").join("particles.toml")
+    }
+
+    // Call every frame, only actually looks at the file every so often
+    pub fn update(self: &mut Self, frame_time: f32) {
+        self.since_check += frame_time;
+        if self.since_check >= RELOAD_CHECK_INTERVAL {
+            self.since_check = 0.0;
+            self.reload_if_changed();
+        }
+    }
+
+    fn reload_if_changed(self: &mut Self) {
+        let path = ParticleEffects::path();
+        let Ok(modified) = fs::metadata(&path).and_then(|metadata| metadata.modified()) else { return };
+        if self.modified == Some(modified) {
+            return;
+        }
+        self.modified = Some(modified);
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text)   => text,
+            Err(error) => {
+                println!("WARN: Failed to read {}: {}", path.display(), error);
+                return;
+            }
+        };
+
+        match parse_effects(&text) {
+            Ok(effects) => {
+                self.effects = parse_effects(BUILT_IN_EFFECTS).unwrap_or_default();
+                self.effects.extend(effects);
+                self.generation += 1;
+                self.missing.clear();
+            },
+            Err(error) => println!("WARN: {} not loaded: {}", path.display(), error),
+        }
+    }
+
+    // The settings for `name` with "tint" colors filled in
+    pub fn settings(self: &mut Self, name: &str, tint: Color) -> Option<EmitterSettings> {
+        let Some(effect) = self.effects.get(name) else {
+            if self.missing.insert(name.to_string()) {
+                println!("WARN: There is no particle effect called \"{}\"", name);
+            }
+            return None;
+        };
+
+        let mut settings = effect.settings.clone();
+        for index in &effect.tinted {
+            settings.colors[*index] = tint;
+        }
+        Some(settings)
+    }
+
+    pub fn burst(self: &mut Self, particle_system: &mut ParticleSystem, name: &str, position: Vector2, tint: Color) {
+        if let Some(settings) = self.settings(name, tint) {
+            particle_system.burst(&settings, position);
+        }
+    }
+
+    pub fn start(self: &mut Self, particle_system: &mut ParticleSystem, name: &str, position: Vector2, tint: Color) -> Option<EmitterId> {
+        self.settings(name, tint).map(|settings| particle_system.start(&settings, position))
+    }
+}
+
+// A section's name and its keys, each with the line it was on
+type Section = (String, Vec<(String, Value, usize)>);
+
+fn parse_effects(text: &str) -> Result<HashMap<String, Effect>, String> {
+    let mut sections: Vec<Section> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            sections.push((name.trim().to_string(), Vec::new()));
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected `key = value`", line_number));
+        };
+        let Some((_, entries)) = sections.last_mut() else {
+            return Err(format!("line {}: `{}` is outside of any [effect]", line_number, key.trim()));
+        };
+        let value = parse_value(value.trim()).map_err(|error| format!("line {}: {}", line_number, error))?;
+        entries.push((key.trim().to_string(), value, line_number));
+    }
+
+    let mut effects = HashMap::new();
+    for (name, entries) in sections {
+        let effect = build_effect(&entries).map_err(|error| format!("[{}] {}", name, error))?;
+        effects.insert(name, effect);
+    }
+    Ok(effects)
+}
+
+// A '#' starts a comment unless it's inside a string, colors are "#RRGGBB"
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {},
+        }
+    }
+    line
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    let mut characters = text.chars().peekable();
+    let value = parse_value_from(&mut characters)?;
+    if characters.any(|character| !character.is_whitespace()) {
+        return Err(format!("unexpected text after `{}`", text));
+    }
+    Ok(value)
+}
+
+fn parse_value_from(characters: &mut std::iter::Peekable<std::str::Chars>) -> Result<Value, String> {
+    while characters.peek().map(|character| character.is_whitespace()).unwrap_or(false) {
+        characters.next();
+    }
+
+    match characters.peek().copied() {
+        Some('[') => {
+            characters.next();
+            let mut items = Vec::new();
+            loop {
+                while characters.peek().map(|character| character.is_whitespace() || *character == ',').unwrap_or(false) {
+                    characters.next();
+                }
+                match characters.peek() {
+                    Some(']') => {
+                        characters.next();
+                        return Ok(Value::LIST(items));
+                    },
+                    None => return Err("unclosed list".to_string()),
+                    _ => items.push(parse_value_from(characters)?),
+                }
+            }
+        },
+        Some('"') => {
+            characters.next();
+            let mut text = String::new();
+            loop {
+                match characters.next() {
+                    Some('"') => return Ok(Value::TEXT(text)),
+                    Some(character) => text.push(character),
+                    None => return Err("unclosed string".to_string()),
+                }
+            }
+        },
+        Some(_) => {
+            let mut word = String::new();
+            while let Some(character) = characters.peek().copied() {
+                if character == ',' || character == ']' || character.is_whitespace() {
+                    break;
+                }
+                word.push(character);
+                characters.next();
+            }
+            match word.as_str() {
+                "true"  => Ok(Value::BOOL(true)),
+                "false" => Ok(Value::BOOL(false)),
+                _ => word.parse::<f32>().map(Value::NUMBER).map_err(|_| format!("`{}` isn't a number", word)),
+            }
+        },
+        None => Err("missing value".to_string()),
+    }
+}
+
+fn as_number(value: &Value) -> Result<f32, String> {
+    match value {
+        Value::NUMBER(number) => Ok(*number),
+        _ => Err("expected a number".to_string()),
+    }
+}
+
+fn as_pair(value: &Value) -> Result<(f32, f32), String> {
+    match value {
+        Value::LIST(items) if items.len() == 2 => Ok((as_number(&items[0])?, as_number(&items[1])?)),
+        _ => Err("expected [x, y]".to_string()),
+    }
+}
+
+// Either one number or [min, max]
+fn as_range(value: &Value) -> Result<(f32, f32), String> {
+    match value {
+        Value::NUMBER(number) => Ok((*number, *number)),
+        _ => as_pair(value).map_err(|_| "expected a number or [min, max]".to_string()),
+    }
+}
+
+fn as_list(value: &Value) -> Result<&Vec<Value>, String> {
+    match value {
+        Value::LIST(items) => Ok(items),
+        _ => Err("expected a list".to_string()),
+    }
+}
+
+fn as_text(value: &Value) -> Result<&str, String> {
+    match value {
+        Value::TEXT(text) => Ok(text),
+        _ => Err("expected a string".to_string()),
+    }
+}
+
+fn parse_color(text: &str) -> Result<Color, String> {
+    let hex = text.strip_prefix('#').ok_or(format!("`{}` isn't a color like \"#RRGGBB\"", text))?;
+    let channel = |index: usize| -> Result<u8, String> {
+        hex.get(index * 2..index * 2 + 2)
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            .ok_or(format!("`{}` isn't a color like \"#RRGGBB\"", text))
+    };
+
+    match hex.len() {
+        6 => Ok(Color { r: channel(0)?, g: channel(1)?, b: channel(2)?, a: 255 }),
+        8 => Ok(Color { r: channel(0)?, g: channel(1)?, b: channel(2)?, a: channel(3)? }),
+        _ => Err(format!("`{}` isn't a color like \"#RRGGBB\"", text)),
+    }
+}
+
+fn build_effect(entries: &[(String, Value, usize)]) -> Result<Effect, String> {
+    let mut settings = EmitterSettings::default();
+    let mut tinted = Vec::new();
+    let mut shape = "point".to_string();
+    let mut radius = 0.0;
+    let mut end = (0.0, 0.0);
+    let mut size = (0.0, 0.0);
+
+    for (key, value, line_number) in entries {
+        let result: Result<(), String> = (|| {
+            match key.as_str() {
+                "shape"     => shape = as_text(value)?.to_string(),
+                "radius"    => radius = as_number(value)?,
+                "end"       => end = as_pair(value)?,
+                "size"      => size = as_pair(value)?,
+                "burst"     => settings.burst = as_number(value)?.max(0.0) as u32,
+                "rate"      => settings.rate = as_number(value)?.max(0.0),
+                "duration"  => {
+                    settings.duration = match value {
+                        Value::TEXT(text) if text == "forever" => None,
+                        _ => Some(as_number(value).map_err(|_| "expected seconds or \"forever\"".to_string())?),
+                    }
+                },
+                "lifetime"  => settings.lifetime = as_range(value)?,
+                "speed"     => settings.speed = as_range(value)?,
+                "direction" => settings.direction = as_number(value)?,
+                "spread"    => settings.spread = as_number(value)?,
+                "gravity"   => {
+                    let (x, y) = as_pair(value)?;
+                    settings.gravity = Vector2 { x: x, y: y };
+                },
+                "drag"      => settings.drag = as_number(value)?.max(0.0),
+                "colors"    => {
+                    settings.colors.clear();
+                    tinted.clear();
+                    for (index, item) in as_list(value)?.iter().enumerate() {
+                        let text = as_text(item)?;
+                        if text == "tint" {
+                            tinted.push(index);
+                            settings.colors.push(Color::WHITE);
+                        } else {
+                            settings.colors.push(parse_color(text)?);
+                        }
+                    }
+                },
+                "color_over_life" => {
+                    settings.color_over_life.clear();
+                    for key in as_list(value)? {
+                        let items = as_list(key)?;
+                        if items.len() != 2 {
+                            return Err("expected [time, \"#RRGGBB\"]".to_string());
+                        }
+                        settings.color_over_life.push((as_number(&items[0])?, parse_color(as_text(&items[1])?)?));
+                    }
+                },
+                "size_over_life" => {
+                    settings.size_over_life = as_list(value)?.iter().map(as_pair).collect::<Result<_, _>>()?;
+                },
+                "fade" => {
+                    settings.fade = match value {
+                        Value::BOOL(fade) => *fade,
+                        _ => return Err("expected true or false".to_string()),
+                    }
+                },
+                "look" => {
+                    let look = as_text(value)?;
+                    settings.look = match look {
+                        "circle" => ParticleLook::CIRCLE,
+                        "square" => ParticleLook::SQUARE,
+                        "spark"  => ParticleLook::SPARK,
+                        _ => match ATLAS_SPRITES.iter().position(|sprite| *sprite == look) {
+                            Some(index) => ParticleLook::SPRITE(index),
+                            None => return Err(format!("unknown look \"{}\"", look)),
+                        },
+                    }
+                },
+                "blend" => {
+                    settings.blend = match as_text(value)? {
+                        "alpha"    => ParticleBlend::ALPHA,
+                        "additive" => ParticleBlend::ADDITIVE,
+                        _ => return Err("expected \"alpha\" or \"additive\"".to_string()),
+                    }
+                },
+                "rotation"          => settings.rotation = as_range(value)?,
+                "angular_velocity"  => settings.angular_velocity = as_range(value)?,
+                _ => return Err("unknown key".to_string()),
+            }
+            Ok(())
+        })();
+
+        result.map_err(|error| format!("line {}: {}: {}", line_number, key, error))?;
+    }
+
+    settings.shape = match shape.as_str() {
+        "point"     => EmitterShape::POINT,
+        "circle"    => EmitterShape::CIRCLE(radius),
+        "line"      => EmitterShape::LINE(Vector2 { x: end.0, y: end.1 }),
+        "rectangle" => EmitterShape::RECTANGLE(size.0, size.1),
+        _ => return Err(format!("unknown shape \"{}\"", shape)),
+    };
+    if settings.colors.is_empty() {
+        return Err("colors can't be empty".to_string());
+    }
+    settings.color_over_life.sort_by(|a, b| a.0.total_cmp(&b.0));
+    settings.size_over_life.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    Ok(Effect { settings: settings, tinted: tinted })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effect(text: &str) -> Result<Effect, String> {
+        let mut effects = parse_effects(text)?;
+        Ok(effects.remove("test").expect("no [test] effect"))
+    }
+
+    #[test]
+    fn built_in_effects_parse() {
+        let effects = parse_effects(BUILT_IN_EFFECTS).unwrap();
+        assert!(effects.contains_key("apple_eaten"));
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(parse_value("1.5"), Ok(Value::NUMBER(1.5)));
+        assert_eq!(parse_value("-3"), Ok(Value::NUMBER(-3.0)));
+        assert_eq!(parse_value("true"), Ok(Value::BOOL(true)));
+        assert_eq!(parse_value("\"a b, c]\""), Ok(Value::TEXT("a b, c]".to_string())));
+        assert_eq!(parse_value("[]"), Ok(Value::LIST(Vec::new())));
+        assert_eq!(
+            parse_value("[[0, \"#FFFFFF\"], [1,2] ,]"),
+            Ok(Value::LIST(vec![
+                Value::LIST(vec![Value::NUMBER(0.0), Value::TEXT("#FFFFFF".to_string())]),
+                Value::LIST(vec![Value::NUMBER(1.0), Value::NUMBER(2.0)]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn bad_values() {
+        assert!(parse_value("").is_err());
+        assert!(parse_value("fast").is_err());
+        assert!(parse_value("[1, 2").is_err());
+        assert!(parse_value("\"open").is_err());
+        assert!(parse_value("1 2").is_err());
+    }
+
+    #[test]
+    fn comments_stop_outside_strings() {
+        assert_eq!(strip_comment("rate = 4 # per second"), "rate = 4 ");
+        assert_eq!(strip_comment("colors = [\"#FF0000\"] # red"), "colors = [\"#FF0000\"] ");
+        assert_eq!(strip_comment("# all of it"), "");
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#FF8000"), Ok(Color { r: 255, g: 128, b: 0, a: 255 }));
+        assert_eq!(parse_color("#00000080"), Ok(Color { r: 0, g: 0, b: 0, a: 128 }));
+        assert!(parse_color("FF8000").is_err());
+        assert!(parse_color("#FF80").is_err());
+        assert!(parse_color("#GG0000").is_err());
+    }
+
+    #[test]
+    fn defaults_for_missing_keys() {
+        let effect = effect("[test]\nburst = 3\n").unwrap();
+        let defaults = EmitterSettings::default();
+        assert_eq!(effect.settings.burst, 3);
+        assert_eq!(effect.settings.shape, EmitterShape::POINT);
+        assert_eq!(effect.settings.lifetime, defaults.lifetime);
+        assert_eq!(effect.settings.look, ParticleLook::CIRCLE);
+        assert_eq!(effect.settings.blend, ParticleBlend::ALPHA);
+        assert!(effect.tinted.is_empty());
+    }
+
+    #[test]
+    fn every_kind_of_key() {
+        let text = "
+            # leading comment
+            [test]
+            shape = \"rectangle\"
+            size = [10, 4]
+            rate = 30
+            duration = \"forever\"
+            speed = [5, 10]
+            gravity = [0, 98]
+            colors = [\"tint\", \"#102030\", \"tint\"]
+            color_over_life = [[1, \"#000000\"], [0, \"#FFFFFF\"]]
+            look = \"ring\"
+            blend = \"additive\"
+            fade = true
+        ";
+        let effect = effect(text).unwrap();
+        let settings = &effect.settings;
+        assert_eq!(settings.shape, EmitterShape::RECTANGLE(10.0, 4.0));
+        assert_eq!(settings.duration, None);
+        assert_eq!(settings.speed, (5.0, 10.0));
+        assert_eq!(settings.gravity.y, 98.0);
+        assert_eq!(effect.tinted, vec![0, 2]);
+        assert_eq!(settings.colors[1], Color { r: 16, g: 32, b: 48, a: 255 });
+        // Sorted by time
+        assert_eq!(settings.color_over_life[0].0, 0.0);
+        assert_eq!(settings.look, ParticleLook::SPRITE(2));
+        assert_eq!(settings.blend, ParticleBlend::ADDITIVE);
+        assert!(settings.fade);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error = |text: &str| effect(text).err().unwrap();
+        assert_eq!(error("[test]\nburst = 1\noops\n"), "line 3: expected `key = value`");
+        assert_eq!(error("rate = 1\n[test]\n"), "line 1: `rate` is outside of any [effect]");
+        assert_eq!(error("[test]\n\nrate = [1\n"), "line 3: unclosed list");
+        assert_eq!(error("[test]\nburst = 1\nwobble = 2\n"), "[test] line 3: wobble: unknown key");
+        assert_eq!(error("[test]\nfade = 1\n"), "[test] line 2: fade: expected true or false");
+        assert_eq!(error("[test]\nshape = \"blob\"\n"), "[test] unknown shape \"blob\"");
+        assert_eq!(error("[test]\ncolors = []\n"), "[test] colors can't be empty");
+    }
+}
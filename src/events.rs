@@ -0,0 +1,70 @@
+use crate::food::Apple;
+use crate::game::{Death, Direction, Game};
+
+// @note: Everything the simulation wants the rest of the game to know about
+//  comes out of `Game::step` as a list of events, in the order it happened.
+//  Sounds, particles, stats and achievements are all subscribers, so something
+//  new that reacts to gameplay is a new subscriber rather than another branch
+//  in every loop in main.rs.
+//
+// Subscribers are handed the game as it is after the whole batch of steps, not
+//  as it was when each event happened. Anything that needs the exact moment
+//  should be carried in the event itself.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    // A fixed step ran, always the first event of its step
+    TICKED,
+    // (player, the direction the snake moved in), once per move that changed direction
+    TURNED(usize, Direction),
+    // (player), once per tick spent boosting
+    BOOSTED(usize),
+    // (player, the apple that was eaten)
+    EATEN(usize, Apple),
+    // (player, new length) once the snake has actually grown
+    GREW(usize, usize),
+    // (the apple that ran out), a new one is already placed
+    EXPIRED(Apple),
+    DIED(usize, Death),
+    // (player) ate the last apple there was room for
+    FILLED(usize),
+    // The round is over, with the winner if there is one
+    DECIDED(Option<usize>)
+}
+
+pub trait Subscriber {
+    fn on_event(self: &mut Self, game: &Game, event: &GameEvent);
+}
+
+#[derive(Debug, Default)]
+pub struct EventBus {
+    queue: Vec<GameEvent>
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus { queue: Vec::new() }
+    }
+
+    pub fn emit(self: &mut Self, event: GameEvent) {
+        self.queue.push(event);
+    }
+
+    pub fn emit_all(self: &mut Self, events: impl IntoIterator<Item = GameEvent>) {
+        self.queue.extend(events);
+    }
+
+    pub fn is_empty(self: &Self) -> bool {
+        self.queue.is_empty()
+    }
+
+    // Hands every queued event to each subscriber in turn, in the order given,
+    //  and empties the queue.
+    pub fn dispatch(self: &mut Self, game: &Game, subscribers: &mut [&mut dyn Subscriber]) {
+        for event in self.queue.drain(..) {
+            for subscriber in subscribers.iter_mut() {
+                subscriber.on_event(game, &event);
+            }
+        }
+    }
+}
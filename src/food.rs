@@ -0,0 +1,367 @@
+// @note: Everything that decides how a piece of food behaves on the board lives
+//  here. The visuals (fade, blink, timer ring) and the scoring are both driven
+//  from the per type settings table so a new food only needs a new entry.
+//
+// The table a game uses can be changed, it's read from `gameplay.apple.*` and
+//  `gameplay.golden.*` in the settings file (`FoodTable::set` takes the part
+//  after `gameplay.`). Every apple keeps a copy of the settings it was placed
+//  with, so changing the table never changes food already on the board.
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum FoodType {
+    APPLE,
+    GOLDEN
+}
+
+pub const FOOD_TYPES: [FoodType; 2] = [FoodType::APPLE, FoodType::GOLDEN];
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct FoodSettings {
+    // How long the food stays on the board before it expires, in seconds
+    pub duration:           f32,
+    // Over the final `fade_time` seconds the food fades down to `min_alpha`
+    pub fade_time:          f32,
+    pub min_alpha:          f32,
+    // Over the final `blink_time` seconds the food also blinks
+    pub blink_time:         f32,
+    pub blink_frequency:    f32,
+    pub show_timer_ring:    bool,
+    // Points for eating it, plus a bonus that decays linearly to zero over
+    //  the first `early_bonus_time` seconds after it spawns
+    pub points:             u16,
+    pub early_bonus:        u16,
+    pub early_bonus_time:   f32,
+    // Relative chance of this food being picked when a new one is placed
+    pub spawn_weight:       u32
+}
+
+pub fn food_type_key(food_type: FoodType) -> &'static str {
+    match food_type {
+        FoodType::APPLE  => "apple",
+        FoodType::GOLDEN => "golden",
+    }
+}
+
+// What it's called on screen
+pub fn food_type_name(food_type: FoodType) -> &'static str {
+    match food_type {
+        FoodType::APPLE  => "Apple",
+        FoodType::GOLDEN => "Golden apple",
+    }
+}
+
+// The settings every game starts with
+pub fn default_food_settings(food_type: FoodType) -> FoodSettings {
+    match food_type {
+        FoodType::APPLE => FoodSettings {
+            duration:           5.0,
+            fade_time:          2.0,
+            min_alpha:          0.2,
+            blink_time:         1.0,
+            blink_frequency:    4.0,
+            show_timer_ring:    true,
+            points:             1,
+            early_bonus:        2,
+            early_bonus_time:   2.0,
+            spawn_weight:       9
+        },
+        FoodType::GOLDEN => FoodSettings {
+            duration:           3.0,
+            fade_time:          1.5,
+            min_alpha:          0.2,
+            blink_time:         1.0,
+            blink_frequency:    6.0,
+            show_timer_ring:    true,
+            points:             3,
+            early_bonus:        5,
+            early_bonus_time:   1.5,
+            spawn_weight:       1
+        }
+    }
+}
+
+// The settings for every food type, in the order of FOOD_TYPES
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct FoodTable {
+    settings: [FoodSettings; 2]
+}
+
+impl Default for FoodTable {
+    fn default() -> Self {
+        FoodTable { settings: FOOD_TYPES.map(default_food_settings) }
+    }
+}
+
+impl FoodTable {
+    pub fn get(self: &Self, food_type: FoodType) -> FoodSettings {
+        self.settings[food_index(food_type)]
+    }
+
+    pub fn set_settings(self: &mut Self, food_type: FoodType, settings: FoodSettings) {
+        self.settings[food_index(food_type)] = settings;
+    }
+
+    pub fn total_spawn_weight(self: &Self) -> u32 {
+        self.settings.iter().map(|settings| settings.spawn_weight).sum()
+    }
+
+    // @note: `roll` is expected to be in 0..total_spawn_weight(), anything
+    //  outside of that is wrapped back into range.
+    pub fn pick(self: &Self, roll: u32) -> FoodType {
+        let total = self.total_spawn_weight();
+        if total == 0 {
+            return FoodType::APPLE;
+        }
+
+        let mut roll = roll % total;
+        for food_type in FOOD_TYPES {
+            let weight = self.get(food_type).spawn_weight;
+            if roll < weight {
+                return food_type;
+            }
+            roll -= weight;
+        }
+
+        FoodType::APPLE
+    }
+
+    // Every setting as `type.name` and its value, in the order they're declared
+    pub fn entries(self: &Self) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        for food_type in FOOD_TYPES {
+            let settings = self.get(food_type);
+            let values = [
+                ("duration",         format!("{:.2}", settings.duration)),
+                ("fade_time",        format!("{:.2}", settings.fade_time)),
+                ("min_alpha",        format!("{:.2}", settings.min_alpha)),
+                ("blink_time",       format!("{:.2}", settings.blink_time)),
+                ("blink_frequency",  format!("{:.2}", settings.blink_frequency)),
+                ("show_timer_ring",  settings.show_timer_ring.to_string()),
+                ("points",           settings.points.to_string()),
+                ("early_bonus",      settings.early_bonus.to_string()),
+                ("early_bonus_time", format!("{:.2}", settings.early_bonus_time)),
+                ("spawn_weight",     settings.spawn_weight.to_string()),
+            ];
+            for (name, value) in values {
+                entries.push((format!("{}.{}", food_type_key(food_type), name), value));
+            }
+        }
+        entries
+    }
+
+    // Sets one setting from a `type.name` key like `apple.duration`. Times
+    //  can't be negative and alpha has to be in 0..1, the setting is left as
+    //  it was when the value doesn't fit.
+    pub fn set(self: &mut Self, key: &str, value: &str) -> Result<(), String> {
+        let (type_key, name) = key.split_once('.').ok_or(format!("Unknown food setting '{}'", key))?;
+        let food_type = FOOD_TYPES.into_iter()
+            .find(|food_type| food_type_key(*food_type) == type_key)
+            .ok_or(format!("Unknown food type '{}' in {}", type_key, key))?;
+        let settings = &mut self.settings[food_index(food_type)];
+
+        let value = value.trim();
+        let time = |value: &str| -> Result<f32, String> {
+            let number = value.parse::<f32>().map_err(|_| format!("Expected a number for {}, got '{}'", key, value))?;
+            if !number.is_finite() || number < 0.0 {
+                return Err(format!("{} can't be negative, got '{}'", key, value));
+            }
+            Ok(number)
+        };
+        let whole = |value: &str| value.parse::<u32>().map_err(|_| format!("Expected a whole number for {}, got '{}'", key, value));
+
+        match name {
+            "duration"         => settings.duration = time(value)?,
+            "fade_time"        => settings.fade_time = time(value)?,
+            "min_alpha"        => {
+                let alpha = time(value)?;
+                if alpha > 1.0 {
+                    return Err(format!("{} has to be between 0 and 1, got '{}'", key, value));
+                }
+                settings.min_alpha = alpha;
+            },
+            "blink_time"       => settings.blink_time = time(value)?,
+            "blink_frequency"  => settings.blink_frequency = time(value)?,
+            "show_timer_ring"  => {
+                settings.show_timer_ring = value.parse::<bool>()
+                    .map_err(|_| format!("Expected true or false for {}, got '{}'", key, value))?;
+            },
+            "points"           => settings.points = whole(value)?.min(u16::MAX as u32) as u16,
+            "early_bonus"      => settings.early_bonus = whole(value)?.min(u16::MAX as u32) as u16,
+            "early_bonus_time" => settings.early_bonus_time = time(value)?,
+            "spawn_weight"     => settings.spawn_weight = whole(value)?,
+            _ => return Err(format!("Unknown food setting '{}'", key)),
+        }
+        Ok(())
+    }
+}
+
+fn food_index(food_type: FoodType) -> usize {
+    FOOD_TYPES.iter().position(|other| *other == food_type).unwrap_or(0)
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Apple {
+    pub x:          f32,
+    pub y:          f32,
+    pub time_left:  f32,
+    pub food_type:  FoodType,
+    // What it was placed with, see FoodTable
+    pub settings:   FoodSettings
+}
+
+impl Apple {
+    // A fresh one with all of its time left
+    pub fn new(x: f32, y: f32, food_type: FoodType, settings: FoodSettings) -> Self {
+        Apple { x: x, y: y, time_left: settings.duration, food_type: food_type, settings: settings }
+    }
+
+    // 1.0 when freshly placed, 0.0 when it is about to expire
+    pub fn fraction_left(self: &Self) -> f32 {
+        let duration = self.settings.duration;
+        if duration <= 0.0 {
+            return 0.0;
+        }
+        (self.time_left / duration).clamp(0.0, 1.0)
+    }
+
+    pub fn time_alive(self: &Self) -> f32 {
+        (self.settings.duration - self.time_left).max(0.0)
+    }
+
+    // @note: Alpha in the range 0.0 to 1.0. Fully opaque until the fade window
+    //  starts, then fades towards `min_alpha`. Blinking dims the food on every
+    //  other half period instead of hiding it so it never fully disappears.
+    pub fn alpha(self: &Self) -> f32 {
+        let settings = self.settings;
+
+        let mut alpha = 1.0;
+        if settings.fade_time > 0.0 && self.time_left < settings.fade_time {
+            let fade_amount = (self.time_left / settings.fade_time).clamp(0.0, 1.0);
+            alpha = settings.min_alpha + (1.0 - settings.min_alpha) * fade_amount;
+        }
+
+        if self.time_left < settings.blink_time {
+            let phase = (self.time_left.max(0.0) * settings.blink_frequency).fract();
+            if phase < 0.5 {
+                alpha *= 0.35;
+            }
+        }
+
+        alpha
+    }
+
+    pub fn early_bonus(self: &Self) -> u16 {
+        let settings = self.settings;
+        if settings.early_bonus_time <= 0.0 {
+            return 0;
+        }
+
+        let remaining = 1.0 - (self.time_alive() / settings.early_bonus_time);
+        if remaining <= 0.0 {
+            return 0;
+        }
+
+        (settings.early_bonus as f32 * remaining).ceil() as u16
+    }
+
+    pub fn score_value(self: &Self) -> u16 {
+        self.settings.points + self.early_bonus()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apple_with(time_left: f32) -> Apple {
+        let mut apple = Apple::new(0.0, 0.0, FoodType::APPLE, default_food_settings(FoodType::APPLE));
+        apple.time_left = time_left;
+        apple
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.001
+    }
+
+    #[test]
+    fn fresh_apple_is_opaque_and_full() {
+        let apple = apple_with(5.0);
+        assert_eq!(apple.fraction_left(), 1.0);
+        assert_eq!(apple.alpha(), 1.0);
+    }
+
+    #[test]
+    fn fades_over_the_fade_time() {
+        // Not fading yet at exactly the fade time
+        assert_eq!(apple_with(2.0).alpha(), 1.0);
+        // A quarter of the way into the fade window
+        assert!(close(apple_with(1.5).alpha(), 0.8));
+    }
+
+    #[test]
+    fn blinks_dim_on_every_other_half_period() {
+        // 0.6 * 4 = 2.4 is in the dim half, 0.7 * 4 = 2.8 is not
+        assert!(close(apple_with(0.6).alpha(), (0.2 + 0.8 * 0.3) * 0.35));
+        assert!(close(apple_with(0.7).alpha(), 0.2 + 0.8 * 0.35));
+        // Never fully gone
+        assert!(apple_with(0.0).alpha() > 0.0);
+    }
+
+    #[test]
+    fn early_bonus_decays_to_zero() {
+        assert_eq!(apple_with(5.0).early_bonus(), 2);
+        assert_eq!(apple_with(5.0).score_value(), 3);
+        assert_eq!(apple_with(4.0).early_bonus(), 1);
+        assert_eq!(apple_with(3.0).early_bonus(), 0);
+        assert_eq!(apple_with(1.0).score_value(), 1);
+    }
+
+    #[test]
+    fn picks_by_spawn_weight() {
+        let table = FoodTable::default();
+        assert_eq!(table.total_spawn_weight(), 10);
+        assert_eq!(table.pick(0), FoodType::APPLE);
+        assert_eq!(table.pick(8), FoodType::APPLE);
+        assert_eq!(table.pick(9), FoodType::GOLDEN);
+        // Wrapped back into range
+        assert_eq!(table.pick(10), FoodType::APPLE);
+    }
+
+    #[test]
+    fn set_changes_one_setting() {
+        let mut table = FoodTable::default();
+        table.set("golden.duration", "4.5").unwrap();
+        table.set("apple.show_timer_ring", "false").unwrap();
+        table.set("golden.spawn_weight", "0").unwrap();
+        assert_eq!(table.get(FoodType::GOLDEN).duration, 4.5);
+        assert!(!table.get(FoodType::APPLE).show_timer_ring);
+        assert_eq!(table.pick(9), FoodType::APPLE);
+        assert_eq!(table.get(FoodType::APPLE).duration, 5.0);
+    }
+
+    #[test]
+    fn set_rejects_bad_values() {
+        let mut table = FoodTable::default();
+        assert!(table.set("apple.duration", "-1").is_err());
+        assert!(table.set("apple.duration", "inf").is_err());
+        assert!(table.set("apple.min_alpha", "1.5").is_err());
+        assert!(table.set("apple.points", "lots").is_err());
+        assert!(table.set("banana.duration", "1").is_err());
+        assert!(table.set("apple.colour", "1").is_err());
+        assert!(table.set("duration", "1").is_err());
+        assert_eq!(table, FoodTable::default());
+    }
+
+    #[test]
+    fn entries_read_back_the_same() {
+        let mut table = FoodTable::default();
+        table.set("apple.early_bonus_time", "3.25").unwrap();
+        table.set("golden.points", "7").unwrap();
+
+        let mut read = FoodTable::default();
+        for (key, value) in table.entries() {
+            read.set(&key, &value).unwrap();
+        }
+        assert_eq!(read, table);
+    }
+}
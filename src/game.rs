@@ -0,0 +1,735 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::events::GameEvent;
+use crate::food::{Apple, FoodTable, FoodType};
+
+// @note: This module is the whole simulation and deliberately knows nothing about
+//  raylib. Rendering, input and audio all live in main.rs and only ever read the
+//  state in here or feed directions into it.
+
+pub const CELL_SIZE: f32 = 20.0;
+
+pub const STARTING_SNAKE_LENGTH: u32 = 3;
+
+// @note: The simulation runs on a fixed tick. A snake normally moves every
+//  TICKS_PER_MOVE ticks and every tick while boosting, which keeps the timing
+//  the same as the old TIME_TO_MOVE based loop.
+pub const TIME_TO_MOVE:   f32 = 0.15;
+pub const TICKS_PER_MOVE: u32 = 2;
+pub const TICK_DURATION:  f32 = TIME_TO_MOVE / TICKS_PER_MOVE as f32;
+
+pub const ROUNDS_TO_WIN: u32 = 3;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Direction {
+    UP,
+    LEFT,
+    DOWN,
+    RIGHT
+}
+
+impl Direction {
+    pub fn opposite(self: &Self) -> Direction {
+        match self {
+            Direction::UP    => Direction::DOWN,
+            Direction::LEFT  => Direction::RIGHT,
+            Direction::DOWN  => Direction::UP,
+            Direction::RIGHT => Direction::LEFT,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct SnakePart {
+    pub x: f32,
+    pub y: f32,
+    pub direction: Direction
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Snake {
+    pub parts: Vec<SnakePart>,
+    pub head_direction: Direction
+}
+
+impl Snake {
+    pub fn head(self: &Self) -> SnakePart {
+        *self.parts.last().unwrap()
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Bounds {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32
+}
+
+impl Bounds {
+    // @note: Same semantics as raylib's CheckCollisionPointRec, the far edges
+    //  are exclusive.
+    pub fn contains(self: &Self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+    // Which edge a point outside the bounds went over, assuming it only just
+    //  left them (i.e. a snake head that moved one cell too far).
+    pub fn side_crossed(self: &Self, x: f32, y: f32) -> WallSide {
+        if x < self.x {
+            WallSide::LEFT
+        } else if x >= self.x + self.width {
+            WallSide::RIGHT
+        } else if y < self.y {
+            WallSide::TOP
+        } else {
+            WallSide::BOTTOM
+        }
+    }
+}
+
+pub fn play_area_for_screen(screen_width: u32, screen_height: u32) -> Bounds {
+    Bounds {
+        x: 20.0,
+        y: 40.0,
+        width:  screen_width  as f32 - 40.0,
+        height: screen_height as f32 - 80.0
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum GameMode {
+    SOLO,
+    VERSUS
+}
+
+impl GameMode {
+    pub fn player_count(self: &Self) -> usize {
+        match self {
+            GameMode::SOLO   => 1,
+            GameMode::VERSUS => 2,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum WallSide {
+    TOP,
+    LEFT,
+    BOTTOM,
+    RIGHT
+}
+
+pub const WALL_SIDES: [WallSide; 4] = [WallSide::TOP, WallSide::LEFT, WallSide::BOTTOM, WallSide::RIGHT];
+
+// @note: Segments are counted from the head, so 1 is the part right behind it.
+//  Running into another snake's segment 0 means a head on collision.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum DeathCause {
+    WALL(WallSide),
+    // The segment of our own snake that was hit
+    SELF(usize),
+    // (player index, segment of their snake that was hit)
+    SNAKE(usize, usize),
+    // One of the level's walls inside the play area
+    OBSTACLE
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Death {
+    pub cause:      DeathCause,
+    // Top left corner of the cell the head moved into, which can be just
+    //  outside the play area for wall deaths
+    pub x:          f32,
+    pub y:          f32,
+    pub tick:       u64,
+    pub boosting:   bool,
+    pub length:     usize
+}
+
+#[derive(Clone, Debug)]
+pub struct Player {
+    pub snake:          Snake,
+    pub score:          u16,
+    pub round_wins:     u32,
+    pub alive:          bool,
+    pub speed_up:       bool,
+    pub enlarge_snake:  bool,
+    pub move_progress:  u32,
+    // How the snake died this round, None while it is alive
+    pub death:          Option<Death>
+}
+
+// Everything that happened during one or more steps, see events.rs
+#[derive(Clone, Debug, Default)]
+pub struct StepResult {
+    pub events: Vec<GameEvent>
+}
+
+impl StepResult {
+    pub fn append(self: &mut Self, mut other: StepResult) {
+        self.events.append(&mut other.events);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub mode:               GameMode,
+    pub players:            Vec<Player>,
+    pub apple:              Apple,
+    pub play_area:          Bounds,
+    // Top left corners of the cells a level walls off, empty without a level
+    pub walls:              Vec<(f32, f32)>,
+    // How every type of food behaves, see set_food
+    pub food:               FoodTable,
+    pub round:              u32,
+    pub round_over:         bool,
+    pub tick:               u64,
+    pub time_accumulator:   f32,
+    rng:                    StdRng
+}
+
+impl Game {
+    pub fn new(mode: GameMode, play_area: Bounds, seed: u64) -> Self {
+        Game::with_walls(mode, play_area, Vec::new(), seed)
+    }
+
+    pub fn with_walls(mode: GameMode, play_area: Bounds, walls: Vec<(f32, f32)>, seed: u64) -> Self {
+        let mut game = Game {
+            mode:               mode,
+            players:            Vec::new(),
+            apple:              Apple::new(200.0, 200.0, FoodType::APPLE, FoodTable::default().get(FoodType::APPLE)),
+            play_area:          play_area,
+            walls:              walls,
+            food:               FoodTable::default(),
+            round:              0,
+            round_over:         false,
+            tick:               0,
+            time_accumulator:   0.0,
+            rng:                StdRng::seed_from_u64(seed)
+        };
+
+        for _ in 0..mode.player_count() {
+            game.players.push(Player {
+                snake:          Snake { parts: Vec::new(), head_direction: Direction::RIGHT },
+                score:          0,
+                round_wins:     0,
+                alive:          true,
+                speed_up:       false,
+                enlarge_snake:  false,
+                move_progress:  0,
+                death:          None
+            });
+        }
+
+        game.start_round();
+        game
+    }
+
+    // Resets every snake and the apple but keeps scores and round wins.
+    pub fn start_round(self: &mut Self) {
+        self.round           += 1;
+        self.round_over       = false;
+        self.tick             = 0;
+        self.time_accumulator = 0.0;
+
+        let player_count = self.players.len();
+        for (index, player) in self.players.iter_mut().enumerate() {
+            player.snake         = create_starting_snake(index, player_count, self.play_area);
+            player.alive         = true;
+            player.speed_up      = false;
+            player.enlarge_snake = false;
+            player.move_progress = 0;
+            player.death         = None;
+        }
+
+        // @note: The first apple always starts at the same spot, like it always has,
+        //  unless a level or a small board puts that spot out of reach
+        self.apple = Apple::new(200.0, 200.0, FoodType::APPLE, self.food.get(FoodType::APPLE));
+        if !self.play_area.contains(self.apple.x, self.apple.y) || self.is_wall(self.apple.x, self.apple.y) {
+            self.place_apple();
+        }
+    }
+
+    // @note: Meant to be called right after the game is created, the apple
+    //  that's already on the board is given the new settings and a full timer.
+    //  The spawn weights only change which food comes next.
+    pub fn set_food(self: &mut Self, food: FoodTable) {
+        self.food = food;
+        self.apple = Apple::new(self.apple.x, self.apple.y, self.apple.food_type, food.get(self.apple.food_type));
+    }
+
+    // Is the point inside one of the level's walls?
+    pub fn is_wall(self: &Self, x: f32, y: f32) -> bool {
+        self.walls.iter().any(|(wall_x, wall_y)| cell_contains(*wall_x, *wall_y, x, y))
+    }
+
+    // Runs as many fixed steps as fit into the elapsed frame time. `before_step`
+    //  is called ahead of every step, which is how computer players get to
+    //  steer once per tick.
+    pub fn update(self: &mut Self, frame_time: f32, mut before_step: impl FnMut(&mut Game)) -> StepResult {
+        let mut result = StepResult::default();
+        if self.round_over {
+            return result;
+        }
+
+        self.time_accumulator += frame_time;
+        while self.time_accumulator >= TICK_DURATION && !self.round_over {
+            self.time_accumulator -= TICK_DURATION;
+            before_step(self);
+            result.append(self.step());
+        }
+
+        result
+    }
+
+    pub fn step(self: &mut Self) -> StepResult {
+        let mut result = StepResult::default();
+        if self.round_over {
+            return result;
+        }
+
+        self.tick += 1;
+        result.events.push(GameEvent::TICKED);
+
+        self.apple.time_left -= TICK_DURATION;
+        if self.apple.time_left <= 0.0 {
+            let expired = self.apple;
+            self.place_apple();
+            result.events.push(GameEvent::EXPIRED(expired));
+        }
+
+        for (index, player) in self.players.iter_mut().enumerate() {
+            if !player.alive {
+                continue;
+            }
+
+            if player.speed_up {
+                result.events.push(GameEvent::BOOSTED(index));
+            }
+
+            player.move_progress += if player.speed_up { TICKS_PER_MOVE } else { 1 };
+            if player.move_progress >= TICKS_PER_MOVE {
+                if player.snake.head_direction != player.snake.head().direction {
+                    result.events.push(GameEvent::TURNED(index, player.snake.head_direction));
+                }
+                propogate_snake_movement(&mut player.snake, player.enlarge_snake);
+                if player.enlarge_snake {
+                    result.events.push(GameEvent::GREW(index, player.snake.parts.len()));
+                }
+                player.enlarge_snake = false;
+                player.move_progress = 0;
+            }
+        }
+
+        for (index, cause) in self.check_collisions() {
+            let player = &mut self.players[index];
+            let head = player.snake.head();
+            let death = Death {
+                cause:      cause,
+                x:          head.x,
+                y:          head.y,
+                tick:       self.tick,
+                boosting:   player.speed_up,
+                length:     player.snake.parts.len()
+            };
+            player.alive = false;
+            player.death = Some(death);
+            result.events.push(GameEvent::DIED(index, death));
+        }
+
+        for index in 0..self.players.len() {
+            if !self.players[index].alive {
+                continue;
+            }
+
+            let head = self.players[index].snake.head();
+            if cell_contains(self.apple.x, self.apple.y, head.x, head.y) {
+                let eaten = self.apple;
+                self.players[index].enlarge_snake = true;
+                self.players[index].score += eaten.score_value();
+                result.events.push(GameEvent::EATEN(index, eaten));
+                if !self.place_apple() {
+                    result.events.push(GameEvent::FILLED(index));
+                }
+            }
+        }
+
+        if self.is_round_decided() {
+            self.round_over = true;
+            let winner = self.round_winner();
+            if let Some(winner) = winner {
+                self.players[winner].round_wins += 1;
+            }
+            result.events.push(GameEvent::DECIDED(winner));
+        }
+
+        result
+    }
+
+    // @note: All checks are done against the positions after everyone has moved,
+    //  so two snakes that run into each other on the same step both die.
+    fn check_collisions(self: &Self) -> Vec<(usize, DeathCause)> {
+        let mut deaths: Vec<(usize, DeathCause)> = Vec::new();
+
+        for (index, player) in self.players.iter().enumerate() {
+            if !player.alive {
+                continue;
+            }
+
+            let head = player.snake.head();
+            let mut cause = if !self.play_area.contains(head.x, head.y) {
+                Some(DeathCause::WALL(self.play_area.side_crossed(head.x, head.y)))
+            } else if self.is_wall(head.x, head.y) {
+                Some(DeathCause::OBSTACLE)
+            } else if does_snake_self_intersect(&player.snake) {
+                find_segment_hit(&player.snake.parts[..player.snake.parts.len() - 1], head.x, head.y)
+                    .map(|segment| DeathCause::SELF(segment + 1))
+            } else {
+                None
+            };
+
+            for (other_index, other) in self.players.iter().enumerate() {
+                if cause.is_some() {
+                    break;
+                }
+                if other_index == index || !other.alive {
+                    continue;
+                }
+
+                // Head to head and head to body both count, the other snake's
+                //  head is the last part so this covers both cases.
+                if let Some(segment) = find_segment_hit(&other.snake.parts, head.x, head.y) {
+                    cause = Some(DeathCause::SNAKE(other_index, segment));
+                }
+            }
+
+            if let Some(cause) = cause {
+                deaths.push((index, cause));
+            }
+        }
+
+        deaths
+    }
+
+    fn is_round_decided(self: &Self) -> bool {
+        let alive = self.players.iter().filter(|player| player.alive).count();
+        match self.mode {
+            GameMode::SOLO   => alive == 0,
+            GameMode::VERSUS => alive <= 1,
+        }
+    }
+
+    // The last snake standing in versus, None for a draw or in solo.
+    pub fn round_winner(self: &Self) -> Option<usize> {
+        if self.mode == GameMode::SOLO || !self.round_over {
+            return None;
+        }
+
+        let alive: Vec<usize> = (0..self.players.len()).filter(|index| self.players[*index].alive).collect();
+        if alive.len() == 1 {
+            return Some(alive[0]);
+        }
+        None
+    }
+
+    pub fn match_winner(self: &Self) -> Option<usize> {
+        if self.mode == GameMode::SOLO {
+            return None;
+        }
+        self.players.iter().position(|player| player.round_wins >= ROUNDS_TO_WIN)
+    }
+
+    pub fn is_match_over(self: &Self) -> bool {
+        match self.mode {
+            GameMode::SOLO   => self.round_over,
+            GameMode::VERSUS => self.match_winner().is_some(),
+        }
+    }
+
+    // @note: Returns false if there is nowhere left to put the apple, the old
+    //  apple is then left where it is.
+    pub fn place_apple(self: &mut Self) -> bool {
+        let food_type = self.food.pick(self.rng.gen_range(0..self.food.total_spawn_weight().max(1)));
+
+        let columns = (self.play_area.width  / CELL_SIZE) as i32;
+        let rows    = (self.play_area.height / CELL_SIZE) as i32;
+
+        let mut free_cells: Vec<(f32, f32)> = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let x = self.play_area.x + column as f32 * CELL_SIZE;
+                let y = self.play_area.y + row    as f32 * CELL_SIZE;
+                let is_snake_where_apple_is = self.players.iter().any(|player| {
+                    player.snake.parts.iter().any(|part| cell_contains(part.x, part.y, x, y))
+                });
+                if !is_snake_where_apple_is && !self.is_wall(x, y) {
+                    free_cells.push((x, y));
+                }
+            }
+        }
+
+        if free_cells.is_empty() {
+            return false;
+        }
+
+        let (x, y) = free_cells[self.rng.gen_range(0..free_cells.len())];
+        self.apple = Apple::new(x, y, food_type, self.food.get(food_type));
+
+        true
+    }
+}
+
+// Does the cell with its top left corner at (cell_x, cell_y) contain the point?
+pub fn cell_contains(cell_x: f32, cell_y: f32, x: f32, y: f32) -> bool {
+    x >= cell_x && x < cell_x + CELL_SIZE && y >= cell_y && y < cell_y + CELL_SIZE
+}
+
+// Which segment of `parts` (tail first, like `Snake::parts`) covers the point,
+//  counted from the last part. The nearest one to the head wins if several do.
+fn find_segment_hit(parts: &[SnakePart], x: f32, y: f32) -> Option<usize> {
+    parts.iter().rev().position(|part| cell_contains(part.x, part.y, x, y))
+}
+
+// Turns the snake unless that would send it straight back into itself. The
+//  check is against the direction it last moved in, so two quick turns within
+//  one move can't reverse it either.
+pub fn steer_snake(snake: &mut Snake, direction: Direction) {
+    if snake.head().direction != direction.opposite() {
+        snake.head_direction = direction;
+    }
+}
+
+// @note: Player 0 starts where the single player snake always has, player 1
+//  starts in the opposite corner heading the other way.
+pub fn create_starting_snake(player_index: usize, player_count: usize, play_area: Bounds) -> Snake {
+    // @note: Counted in cells and kept on the board, with room for the tail
+    //  behind the head, so narrow boards and levels work. On the standard
+    //  boards these are the same spots the snakes always started on.
+    let columns = ((play_area.width  / CELL_SIZE) as i32).max(2);
+    let rows    = ((play_area.height / CELL_SIZE) as i32).max(1);
+    let (column, row, direction) = if player_index == 0 || player_count == 1 {
+        (5.min(columns - 1), 4.min(rows - 1), Direction::RIGHT)
+    } else {
+        ((columns - 6).clamp(0, columns - 2), (rows - 5).clamp(0, rows - 1), Direction::LEFT)
+    };
+    let head_x = play_area.x + column as f32 * CELL_SIZE;
+    let head_y = play_area.y + row    as f32 * CELL_SIZE;
+
+    let (tail_x, tail_y) = match direction {
+        Direction::UP    => (head_x, head_y + CELL_SIZE),
+        Direction::LEFT  => (head_x + CELL_SIZE, head_y),
+        Direction::DOWN  => (head_x, head_y - CELL_SIZE),
+        Direction::RIGHT => (head_x - CELL_SIZE, head_y),
+    };
+
+    let mut snake_parts: Vec<SnakePart> = Vec::new();
+    for _ in 0..STARTING_SNAKE_LENGTH-1 {
+        snake_parts.push(SnakePart {
+            x: tail_x,
+            y: tail_y,
+            direction: direction
+        });
+    }
+    snake_parts.push(SnakePart {
+        x: head_x,
+        y: head_y,
+        direction: direction
+    });
+
+    Snake {
+        parts: snake_parts,
+        head_direction: direction
+    }
+}
+
+pub fn propogate_snake_movement(snake: &mut Snake, enlarge_snake: bool) {
+    let mut new_head = snake.head();
+    match snake.head_direction {
+        Direction::UP => {
+            new_head.y -= CELL_SIZE;
+        },
+        Direction::LEFT => {
+            new_head.x -= CELL_SIZE;
+        },
+        Direction::DOWN => {
+            new_head.y += CELL_SIZE;
+        },
+        Direction::RIGHT => {
+            new_head.x += CELL_SIZE;
+        }
+    }
+    new_head.direction = snake.head_direction;
+    if !enlarge_snake {
+        snake.parts.remove(0);
+    }
+
+    snake.parts.push(new_head);
+}
+
+pub fn does_snake_self_intersect(snake: &Snake) -> bool {
+    let head = snake.head();
+    for index in 0..snake.parts.len()-1 {
+        let snake_part = snake.parts[index];
+        if cell_contains(snake_part.x, snake_part.y, head.x, head.y) {
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(columns: u32, rows: u32) -> Bounds {
+        Bounds { x: 20.0, y: 40.0, width: columns as f32 * CELL_SIZE, height: rows as f32 * CELL_SIZE }
+    }
+
+    #[test]
+    fn starting_snakes_keep_their_usual_spots() {
+        let play_area = play_area_for_screen(640, 480);
+        let first  = create_starting_snake(0, 2, play_area).head();
+        let second = create_starting_snake(1, 2, play_area).head();
+        assert_eq!((first.x, first.y), (play_area.x + 100.0, play_area.y + 80.0));
+        assert_eq!((second.x, second.y), (play_area.x + play_area.width - 120.0, play_area.y + play_area.height - 100.0));
+    }
+
+    #[test]
+    fn starting_snakes_fit_on_narrow_boards() {
+        for (columns, rows) in [(4, 3), (6, 4), (8, 2), (16, 10)] {
+            let play_area = area(columns, rows);
+            let snakes = [create_starting_snake(0, 2, play_area), create_starting_snake(1, 2, play_area)];
+            for (player_index, snake) in snakes.iter().enumerate() {
+                for part in &snake.parts {
+                    assert!(play_area.contains(part.x, part.y), "{}x{} player {} at {:?}", columns, rows, player_index, part);
+                }
+            }
+            for part in &snakes[0].parts {
+                assert!(find_segment_hit(&snakes[1].parts, part.x, part.y).is_none(), "{}x{} snakes overlap at {:?}", columns, rows, part);
+            }
+        }
+    }
+
+    // A versus game on a 16x10 board with the apple out of the way in the
+    //  bottom right corner
+    fn versus_game() -> Game {
+        let mut game = Game::new(GameMode::VERSUS, area(16, 10), 1);
+        game.apple.x = game.play_area.x + 15.0 * CELL_SIZE;
+        game.apple.y = game.play_area.y + 9.0 * CELL_SIZE;
+        game
+    }
+
+    // Puts a player's snake on the given cells, tail first, about to move
+    fn place_snake(game: &mut Game, player_index: usize, cells: &[(i32, i32)], direction: Direction) {
+        let play_area = game.play_area;
+        let player = &mut game.players[player_index];
+        player.snake = Snake {
+            parts: cells.iter().map(|(column, row)| SnakePart {
+                x: play_area.x + *column as f32 * CELL_SIZE,
+                y: play_area.y + *row    as f32 * CELL_SIZE,
+                direction: direction
+            }).collect(),
+            head_direction: direction
+        };
+        player.move_progress = TICKS_PER_MOVE - 1;
+    }
+
+    #[test]
+    fn head_to_head_kills_both_snakes_and_draws() {
+        let mut game = versus_game();
+        place_snake(&mut game, 0, &[(2, 2), (3, 2), (4, 2)], Direction::RIGHT);
+        place_snake(&mut game, 1, &[(8, 2), (7, 2), (6, 2)], Direction::LEFT);
+
+        let result = game.step();
+        assert!(!game.players[0].alive && !game.players[1].alive);
+        assert_eq!(game.players[0].death.unwrap().cause, DeathCause::SNAKE(1, 0));
+        assert_eq!(game.players[1].death.unwrap().cause, DeathCause::SNAKE(0, 0));
+        assert!(game.round_over);
+        assert_eq!(game.round_winner(), None);
+        assert_eq!(result.events.last(), Some(&GameEvent::DECIDED(None)));
+        assert!(game.players.iter().all(|player| player.round_wins == 0));
+    }
+
+    #[test]
+    fn head_to_body_only_kills_the_attacker() {
+        let mut game = versus_game();
+        place_snake(&mut game, 0, &[(2, 2), (3, 2), (4, 2)], Direction::RIGHT);
+        place_snake(&mut game, 1, &[(5, 3), (5, 2), (5, 1)], Direction::UP);
+
+        game.step();
+        assert!(!game.players[0].alive);
+        assert_eq!(game.players[0].death.unwrap().cause, DeathCause::SNAKE(1, 2));
+        assert!(game.players[1].alive);
+        assert_eq!(game.round_winner(), Some(1));
+        assert_eq!(game.players[1].round_wins, 1);
+    }
+
+    #[test]
+    fn dead_snakes_dont_block() {
+        let mut game = versus_game();
+        place_snake(&mut game, 0, &[(2, 2), (3, 2), (4, 2)], Direction::RIGHT);
+        place_snake(&mut game, 1, &[(7, 2), (6, 2), (5, 2)], Direction::LEFT);
+        game.players[1].alive = false;
+
+        game.step();
+        assert!(game.players[0].alive);
+        assert_eq!(game.players[0].snake.head().x, game.play_area.x + 5.0 * CELL_SIZE);
+        assert_eq!(game.round_winner(), Some(0));
+    }
+
+    #[test]
+    fn the_match_ends_at_rounds_to_win() {
+        let mut game = versus_game();
+        game.players[0].round_wins = ROUNDS_TO_WIN - 1;
+        game.players[1].round_wins = ROUNDS_TO_WIN - 1;
+        assert_eq!(game.match_winner(), None);
+        assert!(!game.is_match_over());
+
+        place_snake(&mut game, 0, &[(5, 3), (5, 2), (5, 1)], Direction::UP);
+        place_snake(&mut game, 1, &[(2, 2), (3, 2), (4, 2)], Direction::RIGHT);
+        game.step();
+        assert_eq!(game.players[0].round_wins, ROUNDS_TO_WIN);
+        assert_eq!(game.match_winner(), Some(0));
+        assert!(game.is_match_over());
+    }
+
+    #[test]
+    fn step_events_come_in_order() {
+        let mut game = versus_game();
+        place_snake(&mut game, 0, &[(2, 2), (3, 2), (4, 2)], Direction::RIGHT);
+        place_snake(&mut game, 1, &[(10, 3), (10, 2), (10, 1)], Direction::UP);
+        game.apple.x = game.play_area.x + 5.0 * CELL_SIZE;
+        game.apple.y = game.play_area.y + 2.0 * CELL_SIZE;
+        // Events carry the apple as it was that tick, its timer already down
+        let mut eaten = game.apple;
+        eaten.time_left -= TICK_DURATION;
+
+        let result = game.step();
+        assert_eq!(result.events, vec![GameEvent::TICKED, GameEvent::EATEN(0, eaten)]);
+        assert_eq!(game.players[0].score, eaten.score_value());
+
+        // The apple runs out, player 1 grows from the last one while boosting
+        //  round a corner and player 2 runs into the top wall
+        game.apple.time_left = TICK_DURATION / 2.0;
+        let mut expired = game.apple;
+        expired.time_left -= TICK_DURATION;
+        game.players[0].speed_up = true;
+        steer_snake(&mut game.players[0].snake, Direction::DOWN);
+        game.players[1].move_progress = TICKS_PER_MOVE - 1;
+
+        let result = game.step();
+        let death = game.players[1].death.unwrap();
+        assert_eq!(death.cause, DeathCause::WALL(WallSide::TOP));
+        assert_eq!(result.events, vec![
+            GameEvent::TICKED,
+            GameEvent::EXPIRED(expired),
+            GameEvent::BOOSTED(0),
+            GameEvent::TURNED(0, Direction::DOWN),
+            GameEvent::GREW(0, 4),
+            GameEvent::DIED(1, death),
+            GameEvent::DECIDED(Some(0))
+        ]);
+    }
+}
@@ -0,0 +1,506 @@
+use crate::ai::{self, Strategy};
+use crate::events::GameEvent;
+use crate::game::{self, Bounds, Direction, Game, GameMode, CELL_SIZE, TICKS_PER_MOVE};
+
+// @note: A reinforcement learning style wrapper around the simulation. One call
+//  to `step` is one move of the agent's snake (TICKS_PER_MOVE ticks), so food
+//  timers run at the same pace relative to movement as in the real game. The
+//  agent is always player 0, and can optionally play versus a computer snake.
+//
+// Actions are relative to the way the snake is heading, which means every
+//  action is always legal. Observations are flat `f32` buffers with a `shape`,
+//  ready to be handed to whatever tensor library the trainer uses.
+
+// The grid has to fit both starting snakes and the first apple
+pub const MIN_GRID_SIZE: u32 = 10;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Action {
+    STRAIGHT,
+    LEFT,
+    RIGHT
+}
+
+pub const ACTIONS: [Action; 3] = [Action::STRAIGHT, Action::LEFT, Action::RIGHT];
+
+impl Action {
+    pub fn from_index(index: usize) -> Option<Action> {
+        ACTIONS.get(index).copied()
+    }
+
+    pub fn apply(self: &Self, heading: Direction) -> Direction {
+        match self {
+            Action::STRAIGHT => heading,
+            Action::LEFT     => turn_left(heading),
+            Action::RIGHT    => turn_left(heading).opposite(),
+        }
+    }
+}
+
+fn turn_left(direction: Direction) -> Direction {
+    match direction {
+        Direction::UP    => Direction::LEFT,
+        Direction::LEFT  => Direction::DOWN,
+        Direction::DOWN  => Direction::RIGHT,
+        Direction::RIGHT => Direction::UP,
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ObservationKind {
+    // Channels x rows x columns, see the GRID_CHANNEL_* constants
+    GRID,
+    // A short vector of hand picked features, see `observe_features`
+    FEATURES
+}
+
+pub const GRID_CHANNEL_BODY:     usize = 0;
+pub const GRID_CHANNEL_HEAD:     usize = 1;
+pub const GRID_CHANNEL_OPPONENT: usize = 2;
+// The food cell holds how much of the food's time is left, 1.0 down to 0.0
+pub const GRID_CHANNEL_FOOD:     usize = 3;
+pub const GRID_CHANNELS:         usize = 4;
+
+pub const FEATURE_COUNT: usize = 13;
+
+#[derive(Clone, Debug)]
+pub struct Observation {
+    // Row major, laid out as described by `shape`
+    pub data:   Vec<f32>,
+    pub shape:  Vec<usize>
+}
+
+// Every field is added to the reward whenever the thing it describes happens.
+#[derive(Clone, Copy, Debug)]
+pub struct RewardConfig {
+    pub apple:      f32,
+    // Per point scored, so golden apples and early bonuses are worth more
+    pub points:     f32,
+    pub death:      f32,
+    // Every step, usually a small negative number to discourage stalling
+    pub step:       f32,
+    // For moving one cell closer to the food, the same negative for moving away
+    pub approach:   f32
+}
+
+impl Default for RewardConfig {
+    fn default() -> Self {
+        RewardConfig {
+            apple:      1.0,
+            points:     0.0,
+            death:      -1.0,
+            step:       -0.01,
+            approach:   0.0
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct EnvConfig {
+    pub columns:                u32,
+    pub rows:                   u32,
+    pub observation:            ObservationKind,
+    pub rewards:                RewardConfig,
+    // Adds a computer snake as player 1 when set
+    pub opponent:               Option<Strategy>,
+    // Ends an episode that goes this many steps without eating, 0 for never
+    pub max_steps_without_food: u32
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            columns:                30,
+            rows:                   20,
+            observation:            ObservationKind::FEATURES,
+            rewards:                RewardConfig::default(),
+            opponent:               None,
+            max_steps_without_food: 1000
+        }
+    }
+}
+
+impl EnvConfig {
+    pub fn observation_shape(self: &Self) -> Vec<usize> {
+        match self.observation {
+            ObservationKind::GRID     => vec![GRID_CHANNELS, self.rows as usize, self.columns as usize],
+            ObservationKind::FEATURES => vec![FEATURE_COUNT],
+        }
+    }
+
+    // Same origin as the real play area so the fixed starting positions line up
+    fn play_area(self: &Self) -> Bounds {
+        let width  = self.columns.max(MIN_GRID_SIZE) as f32 * CELL_SIZE;
+        let height = self.rows.max(MIN_GRID_SIZE)    as f32 * CELL_SIZE;
+        game::play_area_for_screen((width + 2.0 * CELL_SIZE) as u32, (height + 4.0 * CELL_SIZE) as u32)
+    }
+}
+
+pub struct SnakeEnv {
+    pub config:         EnvConfig,
+    game:               Game,
+    steps_without_food: u32,
+    done:               bool
+}
+
+impl SnakeEnv {
+    pub fn new(mut config: EnvConfig) -> Self {
+        config.columns = config.columns.max(MIN_GRID_SIZE);
+        config.rows    = config.rows.max(MIN_GRID_SIZE);
+
+        let mode = if config.opponent.is_some() { GameMode::VERSUS } else { GameMode::SOLO };
+        SnakeEnv {
+            game:               Game::new(mode, config.play_area(), 0),
+            config:             config,
+            steps_without_food: 0,
+            done:               false
+        }
+    }
+
+    pub fn game(self: &Self) -> &Game {
+        &self.game
+    }
+
+    pub fn is_done(self: &Self) -> bool {
+        self.done
+    }
+
+    pub fn reset(self: &mut Self, seed: u64) -> Observation {
+        self.game = Game::new(self.game.mode, self.config.play_area(), seed);
+        self.steps_without_food = 0;
+        self.done = false;
+        self.observe()
+    }
+
+    // Stepping a finished episode does nothing until the next `reset`
+    pub fn step(self: &mut Self, action: Action) -> (Observation, f32, bool) {
+        if self.done {
+            return (self.observe(), 0.0, true);
+        }
+
+        let rewards = self.config.rewards;
+        let mut reward = rewards.step;
+        let distance_before = self.food_distance();
+
+        let heading = self.game.players[0].snake.head().direction;
+        game::steer_snake(&mut self.game.players[0].snake, action.apply(heading));
+
+        let opponent = self.config.opponent;
+        let mut ate = false;
+        for _ in 0..TICKS_PER_MOVE {
+            if let Some(strategy) = opponent {
+                ai::steer_ai_players(&mut self.game, &[None, Some(strategy)]);
+            }
+
+            for event in self.game.step().events {
+                match event {
+                    GameEvent::EATEN(0, apple) => {
+                        reward += rewards.apple + rewards.points * apple.score_value() as f32;
+                        ate = true;
+                    },
+                    GameEvent::DIED(0, _) => reward += rewards.death,
+                    _ => {},
+                }
+            }
+            if self.game.round_over {
+                break;
+            }
+        }
+
+        if !ate && self.game.players[0].alive {
+            let distance_after = self.food_distance();
+            if distance_after < distance_before {
+                reward += rewards.approach;
+            } else if distance_after > distance_before {
+                reward -= rewards.approach;
+            }
+        }
+
+        self.steps_without_food = if ate { 0 } else { self.steps_without_food + 1 };
+
+        let max_steps = self.config.max_steps_without_food;
+        self.done = self.game.round_over
+            || !self.game.players[0].alive
+            || (max_steps > 0 && self.steps_without_food >= max_steps);
+
+        (self.observe(), reward, self.done)
+    }
+
+    pub fn observe(self: &Self) -> Observation {
+        let data = match self.config.observation {
+            ObservationKind::GRID     => self.observe_grid(),
+            ObservationKind::FEATURES => self.observe_features(),
+        };
+        Observation { data: data, shape: self.config.observation_shape() }
+    }
+
+    fn to_cell(self: &Self, x: f32, y: f32) -> (i32, i32) {
+        let area = self.game.play_area;
+        (((x - area.x) / CELL_SIZE).floor() as i32, ((y - area.y) / CELL_SIZE).floor() as i32)
+    }
+
+    fn in_bounds(self: &Self, cell: (i32, i32)) -> bool {
+        cell.0 >= 0 && cell.1 >= 0 && cell.0 < self.config.columns as i32 && cell.1 < self.config.rows as i32
+    }
+
+    fn food_distance(self: &Self) -> i32 {
+        let head = self.game.players[0].snake.head();
+        let (head_x, head_y) = self.to_cell(head.x, head.y);
+        let (food_x, food_y) = self.to_cell(self.game.apple.x, self.game.apple.y);
+        (head_x - food_x).abs() + (head_y - food_y).abs()
+    }
+
+    fn observe_grid(self: &Self) -> Vec<f32> {
+        let columns = self.config.columns as usize;
+        let rows    = self.config.rows as usize;
+        let mut data = vec![0.0; GRID_CHANNELS * rows * columns];
+
+        let mut set = |channel: usize, cell: (i32, i32), value: f32| {
+            if self.in_bounds(cell) {
+                data[channel * rows * columns + cell.1 as usize * columns + cell.0 as usize] = value;
+            }
+        };
+
+        for (index, player) in self.game.players.iter().enumerate() {
+            if !player.alive && index != 0 {
+                continue;
+            }
+            for part in &player.snake.parts {
+                let channel = if index == 0 { GRID_CHANNEL_BODY } else { GRID_CHANNEL_OPPONENT };
+                set(channel, self.to_cell(part.x, part.y), 1.0);
+            }
+            if index == 0 {
+                let head = player.snake.head();
+                set(GRID_CHANNEL_HEAD, self.to_cell(head.x, head.y), 1.0);
+            }
+        }
+
+        let apple = &self.game.apple;
+        set(GRID_CHANNEL_FOOD, self.to_cell(apple.x, apple.y), apple.fraction_left());
+
+        data
+    }
+
+    // Would moving into `cell` next move kill the snake? Our own tail moves out
+    //  of the way unless we're about to grow.
+    fn is_danger(self: &Self, cell: (i32, i32)) -> bool {
+        if !self.in_bounds(cell) {
+            return true;
+        }
+        if self.game.walls.iter().any(|(x, y)| self.to_cell(*x, *y) == cell) {
+            return true;
+        }
+
+        self.game.players.iter().enumerate().any(|(index, player)| {
+            if !player.alive {
+                return false;
+            }
+            let skip_tail = index == 0 && !player.enlarge_snake;
+            player.snake.parts.iter()
+                .skip(if skip_tail { 1 } else { 0 })
+                .any(|part| self.to_cell(part.x, part.y) == cell)
+        })
+    }
+
+    // [danger straight, danger left, danger right,
+    //  heading up, left, down, right,
+    //  food is up, left, down, right of the head,
+    //  food time left, snake length as a fraction of the board]
+    fn observe_features(self: &Self) -> Vec<f32> {
+        let snake = &self.game.players[0].snake;
+        let head = snake.head();
+        let head_cell = self.to_cell(head.x, head.y);
+        let heading = head.direction;
+        let (food_x, food_y) = self.to_cell(self.game.apple.x, self.game.apple.y);
+
+        let step = |direction: Direction| -> (i32, i32) {
+            match direction {
+                Direction::UP    => (head_cell.0, head_cell.1 - 1),
+                Direction::LEFT  => (head_cell.0 - 1, head_cell.1),
+                Direction::DOWN  => (head_cell.0, head_cell.1 + 1),
+                Direction::RIGHT => (head_cell.0 + 1, head_cell.1),
+            }
+        };
+        let flag = |value: bool| if value { 1.0 } else { 0.0 };
+
+        let cell_count = (self.config.columns * self.config.rows) as f32;
+        vec![
+            flag(self.is_danger(step(Action::STRAIGHT.apply(heading)))),
+            flag(self.is_danger(step(Action::LEFT.apply(heading)))),
+            flag(self.is_danger(step(Action::RIGHT.apply(heading)))),
+            flag(heading == Direction::UP),
+            flag(heading == Direction::LEFT),
+            flag(heading == Direction::DOWN),
+            flag(heading == Direction::RIGHT),
+            flag(food_y < head_cell.1),
+            flag(food_x < head_cell.0),
+            flag(food_y > head_cell.1),
+            flag(food_x > head_cell.0),
+            self.game.apple.fraction_left(),
+            snake.parts.len() as f32 / cell_count,
+        ]
+    }
+}
+
+// @note: A batch of environments stepped together. Finished environments are
+//  reset straight away with the next seed, so the observation returned
+//  alongside done == true is already the first one of the new episode.
+pub struct VecEnv {
+    pub envs:   Vec<SnakeEnv>,
+    next_seed:  u64
+}
+
+impl VecEnv {
+    pub fn new(config: EnvConfig, count: usize) -> Self {
+        VecEnv {
+            envs:       (0..count).map(|_| SnakeEnv::new(config)).collect(),
+            next_seed:  0
+        }
+    }
+
+    pub fn len(self: &Self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(self: &Self) -> bool {
+        self.envs.is_empty()
+    }
+
+    // Environment i gets `seed + i`, later resets carry on counting from there
+    pub fn reset(self: &mut Self, seed: u64) -> Vec<Observation> {
+        self.next_seed = seed;
+        let mut observations = Vec::with_capacity(self.envs.len());
+        for env in &mut self.envs {
+            observations.push(env.reset(self.next_seed));
+            self.next_seed += 1;
+        }
+        observations
+    }
+
+    // `actions` must have one action per environment
+    pub fn step(self: &mut Self, actions: &[Action]) -> Vec<(Observation, f32, bool)> {
+        assert_eq!(actions.len(), self.envs.len(), "Expected one action per environment");
+
+        let mut results = Vec::with_capacity(self.envs.len());
+        for (env, action) in self.envs.iter_mut().zip(actions) {
+            let (mut observation, reward, done) = env.step(*action);
+            if done {
+                observation = env.reset(self.next_seed);
+                self.next_seed += 1;
+            }
+            results.push((observation, reward, done));
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::Apple;
+
+    fn config(observation: ObservationKind) -> EnvConfig {
+        EnvConfig { columns: 12, rows: 10, observation: observation, ..EnvConfig::default() }
+    }
+
+    // Steps straight on until the snake hits the right hand wall
+    fn run_into_wall(env: &mut SnakeEnv) -> (Observation, f32, bool) {
+        loop {
+            let result = env.step(Action::STRAIGHT);
+            if result.2 {
+                return result;
+            }
+        }
+    }
+
+    #[test]
+    fn observations_match_their_shape() {
+        let mut env = SnakeEnv::new(config(ObservationKind::GRID));
+        let observation = env.reset(1);
+        assert_eq!(observation.shape, vec![GRID_CHANNELS, 10, 12]);
+        assert_eq!(observation.data.len(), GRID_CHANNELS * 10 * 12);
+        // One head, and the food somewhere
+        let cells = 10 * 12;
+        let head = &observation.data[GRID_CHANNEL_HEAD * cells..(GRID_CHANNEL_HEAD + 1) * cells];
+        assert_eq!(head.iter().filter(|value| **value == 1.0).count(), 1);
+
+        let mut env = SnakeEnv::new(config(ObservationKind::FEATURES));
+        let (observation, _, _) = env.step(Action::LEFT);
+        assert_eq!(observation.shape, vec![FEATURE_COUNT]);
+        assert_eq!(observation.data.len(), FEATURE_COUNT);
+    }
+
+    #[test]
+    fn small_grids_are_grown_to_the_minimum() {
+        let env = SnakeEnv::new(EnvConfig { columns: 3, rows: 4, ..EnvConfig::default() });
+        assert_eq!((env.config.columns, env.config.rows), (MIN_GRID_SIZE, MIN_GRID_SIZE));
+    }
+
+    #[test]
+    fn actions_are_relative_to_the_heading() {
+        assert_eq!(Action::STRAIGHT.apply(Direction::RIGHT), Direction::RIGHT);
+        assert_eq!(Action::LEFT.apply(Direction::RIGHT), Direction::UP);
+        assert_eq!(Action::RIGHT.apply(Direction::RIGHT), Direction::DOWN);
+        assert_eq!(Action::LEFT.apply(Direction::UP), Direction::LEFT);
+        assert_eq!(Action::RIGHT.apply(Direction::UP), Direction::RIGHT);
+        assert_eq!(Action::LEFT.apply(Direction::DOWN), Direction::RIGHT);
+        assert_eq!(Action::RIGHT.apply(Direction::LEFT), Direction::UP);
+        assert_eq!(Action::from_index(2), Some(Action::RIGHT));
+        assert_eq!(Action::from_index(3), None);
+
+        let mut env = SnakeEnv::new(config(ObservationKind::FEATURES));
+        env.step(Action::RIGHT);
+        assert_eq!(env.game().players[0].snake.head().direction, Direction::DOWN);
+    }
+
+    #[test]
+    fn eating_is_rewarded() {
+        let mut env = SnakeEnv::new(config(ObservationKind::FEATURES));
+        let head = env.game.players[0].snake.head();
+        let food_type = env.game.apple.food_type;
+        env.game.apple = Apple::new(head.x + CELL_SIZE, head.y, food_type, env.game.food.get(food_type));
+
+        let (_, reward, done) = env.step(Action::STRAIGHT);
+        let rewards = RewardConfig::default();
+        assert!((reward - (rewards.apple + rewards.step)).abs() < 0.0001);
+        assert!(!done);
+    }
+
+    #[test]
+    fn dying_is_penalised_and_ends_the_episode() {
+        let mut env = SnakeEnv::new(config(ObservationKind::FEATURES));
+        let (_, reward, done) = run_into_wall(&mut env);
+        let rewards = RewardConfig::default();
+        assert!(done);
+        assert!(!env.game().players[0].alive);
+        assert!((reward - (rewards.death + rewards.step)).abs() < 0.0001);
+
+        // Nothing more happens until a reset
+        let (_, reward, done) = env.step(Action::LEFT);
+        assert_eq!((reward, done), (0.0, true));
+        env.reset(2);
+        assert!(!env.is_done());
+    }
+
+    #[test]
+    fn vec_env_resets_finished_environments() {
+        let mut envs = VecEnv::new(config(ObservationKind::GRID), 2);
+        let observations = envs.reset(100);
+        assert_eq!(observations.len(), 2);
+
+        let mut finished = None;
+        for _ in 0..100 {
+            let results = envs.step(&[Action::STRAIGHT, Action::STRAIGHT]);
+            if let Some(index) = results.iter().position(|(_, _, done)| *done) {
+                finished = Some((index, results[index].0.data.clone()));
+                break;
+            }
+        }
+
+        let (index, observation) = finished.expect("an environment should have finished");
+        assert!(!envs.envs[index].is_done());
+        // The first reset handed out seeds 100 and 101
+        let mut fresh = SnakeEnv::new(config(ObservationKind::GRID));
+        assert_eq!(observation, fresh.reset(102).data);
+    }
+}
@@ -0,0 +1,181 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::storage;
+
+// @note: The best solo scores. Only real games go in here, practice runs and
+//  games the computer played never get submitted. The file is plain text, one
+//  "score length" pair per line, best first.
+
+pub const MAX_HIGH_SCORES: usize = 10;
+
+const HIGH_SCORES_FILE: &str = "highscores.txt";
+const HIGH_SCORES_HEADER: &str = "# snake high scores v1";
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct HighScore {
+    pub score:  u16,
+    pub length: usize
+}
+
+pub struct HighScores {
+    pub entries:    Vec<HighScore>,
+    path:           PathBuf
+}
+
+impl HighScores {
+    // A missing or unreadable file just means an empty table
+    pub fn load() -> Self {
+        HighScores::load_from(storage::data_file(HIGH_SCORES_FILE))
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path).map(|text| parse_entries(&text)).unwrap_or_default();
+
+        HighScores {
+            entries:    entries,
+            path:       path
+        }
+    }
+
+    pub fn best(self: &Self) -> Option<u16> {
+        self.entries.first().map(|entry| entry.score)
+    }
+
+    // Adds the score if it makes the table and saves straight away. Returns the
+    //  rank it got, 0 being the best.
+    pub fn submit(self: &mut Self, entry: HighScore) -> Option<usize> {
+        if entry.score == 0 {
+            return None;
+        }
+
+        let rank = self.entries.iter().position(|existing| entry.score > existing.score).unwrap_or(self.entries.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+
+        if let Err(error) = self.save() {
+            println!("WARN: Failed to save high scores to {}: {}", self.path.display(), error);
+        }
+        Some(rank)
+    }
+
+    fn save(self: &Self) -> std::io::Result<()> {
+        storage::write_atomically(&self.path, &format_entries(&self.entries))
+    }
+}
+
+// Best first and at most MAX_HIGH_SCORES of them, whatever order the file was
+//  in. Warnings go to stderr since `snake export-stats` reads this file too.
+fn parse_entries(text: &str) -> Vec<HighScore> {
+    let mut entries: Vec<HighScore> = Vec::new();
+
+    for line in text.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let score  = fields.next().and_then(|field| field.parse::<u16>().ok());
+        let length = fields.next().and_then(|field| field.parse::<usize>().ok());
+        match (score, length, fields.next()) {
+            (Some(score), Some(length), None) => entries.push(HighScore { score: score, length: length }),
+            _ => eprintln!("WARN: Skipping high score line '{}', expected a score and a length", line),
+        }
+    }
+
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+    entries.truncate(MAX_HIGH_SCORES);
+    entries
+}
+
+fn format_entries(entries: &[HighScore]) -> String {
+    let mut contents = String::from(HIGH_SCORES_HEADER);
+    contents.push('\n');
+    for entry in entries {
+        contents += &format!("{} {}\n", entry.score, entry.length);
+    }
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: u16) -> HighScore {
+        HighScore { score: score, length: score as usize + 3 }
+    }
+
+    fn scores(high_scores: &HighScores) -> Vec<u16> {
+        high_scores.entries.iter().map(|entry| entry.score).collect()
+    }
+
+    // A table saving to a file of its own in the temp directory
+    fn table(name: &str, entries: &[HighScore]) -> HighScores {
+        HighScores {
+            entries:    entries.to_vec(),
+            path:       std::env::temp_dir().join(format!("snake-highscores-{}-{}.txt", name, std::process::id()))
+        }
+    }
+
+    #[test]
+    fn scores_are_ranked_best_first() {
+        let mut high_scores = table("ranked", &[]);
+        assert_eq!(high_scores.submit(entry(5)), Some(0));
+        assert_eq!(high_scores.submit(entry(9)), Some(0));
+        assert_eq!(high_scores.submit(entry(7)), Some(1));
+        assert_eq!(scores(&high_scores), vec![9, 7, 5]);
+        assert_eq!(high_scores.best(), Some(9));
+
+        // A tie goes below the score that was already there
+        let tie = HighScore { score: 7, length: 50 };
+        assert_eq!(high_scores.submit(tie), Some(2));
+        assert_eq!(high_scores.entries[1], entry(7));
+        assert_eq!(high_scores.entries[2], tie);
+        let _ = fs::remove_file(&high_scores.path);
+    }
+
+    #[test]
+    fn zero_scores_never_make_it() {
+        let mut high_scores = table("zero", &[]);
+        assert_eq!(high_scores.submit(entry(0)), None);
+        assert!(high_scores.entries.is_empty());
+        assert!(!high_scores.path.exists());
+    }
+
+    #[test]
+    fn the_table_keeps_the_best_ones() {
+        let full: Vec<HighScore> = (0..MAX_HIGH_SCORES as u16).map(|index| entry(100 - index * 10)).collect();
+        let mut high_scores = table("full", &full);
+
+        assert_eq!(high_scores.submit(entry(1)), None);
+        assert_eq!(high_scores.submit(entry(55)), Some(5));
+        assert_eq!(high_scores.entries.len(), MAX_HIGH_SCORES);
+        assert_eq!(high_scores.entries[5], entry(55));
+        assert_eq!(high_scores.entries.last(), Some(&entry(20)));
+
+        let loaded = HighScores::load_from(high_scores.path.clone());
+        assert_eq!(loaded.entries, high_scores.entries);
+        let _ = fs::remove_file(&high_scores.path);
+    }
+
+    #[test]
+    fn bad_lines_are_skipped() {
+        let text = "# snake high scores v1\n12 8\nlots 4\n30\n\n7 5 extra\n-3 4\n40 9\n";
+        assert_eq!(parse_entries(text), vec![HighScore { score: 40, length: 9 }, HighScore { score: 12, length: 8 }]);
+    }
+
+    #[test]
+    fn entries_survive_the_text_format() {
+        let entries = vec![entry(30), entry(20), HighScore { score: 20, length: 2 }];
+        assert_eq!(parse_entries(&format_entries(&entries)), entries);
+
+        let too_many: Vec<HighScore> = (1..=15).map(entry).collect();
+        let parsed = parse_entries(&format_entries(&too_many));
+        assert_eq!(parsed.len(), MAX_HIGH_SCORES);
+        assert_eq!(parsed[0], entry(15));
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+
+use crate::game::{Game, TICK_DURATION};
+
+// @note: Recent game states kept in a fixed size ring buffer, one per tick.
+//  A `Game` is small enough that cloning it every tick is cheap, and whole
+//  states mean playing one back is just drawing it, no re-simulation needed.
+
+pub struct History {
+    states:     VecDeque<Game>,
+    capacity:   usize
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            states:     VecDeque::with_capacity(capacity),
+            capacity:   capacity.max(1)
+        }
+    }
+
+    // Enough room for the last `seconds` of play
+    pub fn with_duration(seconds: f32) -> Self {
+        History::new((seconds / TICK_DURATION).ceil() as usize)
+    }
+
+    // A state from a new round or a new game throws away everything before it
+    pub fn record(self: &mut Self, game: &Game) {
+        if let Some(last) = self.states.back() {
+            if game.round != last.round || game.tick < last.tick {
+                self.states.clear();
+            }
+        }
+
+        if self.states.len() == self.capacity {
+            self.states.pop_front();
+        }
+        self.states.push_back(game.clone());
+    }
+
+    // Forgets everything from `len` onwards, used when play carries on from an
+    //  earlier state so the future we rewound past is gone.
+    pub fn truncate(self: &mut Self, len: usize) {
+        self.states.truncate(len);
+    }
+
+    pub fn clear(self: &mut Self) {
+        self.states.clear();
+    }
+
+    pub fn len(self: &Self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(self: &Self) -> bool {
+        self.states.is_empty()
+    }
+
+    // 0 is the oldest state still kept
+    pub fn get(self: &Self, index: usize) -> Option<&Game> {
+        self.states.get(index)
+    }
+}
+
+// Steps through a `History` at the game's own tick rate, scaled by `speed`.
+pub struct Playback {
+    pub frame:  usize,
+    pub speed:  f32,
+    time:       f32
+}
+
+impl Playback {
+    pub fn new(speed: f32) -> Self {
+        Playback {
+            frame:  0,
+            speed:  speed,
+            time:   0.0
+        }
+    }
+
+    // Returns true once the last frame has been shown for a full tick
+    pub fn update(self: &mut Self, frame_time: f32, frame_count: usize) -> bool {
+        self.time += frame_time * self.speed;
+        while self.time >= TICK_DURATION {
+            self.time -= TICK_DURATION;
+            self.frame += 1;
+        }
+
+        if self.frame >= frame_count {
+            self.frame = frame_count.saturating_sub(1);
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{play_area_for_screen, GameMode};
+
+    fn game() -> Game {
+        Game::new(GameMode::SOLO, play_area_for_screen(640, 480), 1)
+    }
+
+    fn ticks(history: &History) -> Vec<u64> {
+        (0..history.len()).map(|index| history.get(index).unwrap().tick).collect()
+    }
+
+    #[test]
+    fn the_oldest_state_is_dropped_at_capacity() {
+        let mut game = game();
+        let mut history = History::new(3);
+        for _ in 0..5 {
+            game.step();
+            history.record(&game);
+        }
+        assert_eq!(ticks(&history), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn a_new_round_or_an_earlier_tick_starts_over() {
+        let mut game = game();
+        let mut history = History::new(10);
+        for _ in 0..3 {
+            game.step();
+            history.record(&game);
+        }
+
+        let mut earlier = history.get(0).unwrap().clone();
+        history.record(&earlier);
+        assert_eq!(ticks(&history), vec![1]);
+
+        earlier.step();
+        history.record(&earlier);
+        earlier.start_round();
+        earlier.step();
+        history.record(&earlier);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.get(0).unwrap().round, 2);
+    }
+
+    #[test]
+    fn truncate_forgets_the_later_states() {
+        let mut game = game();
+        let mut history = History::new(10);
+        for _ in 0..5 {
+            game.step();
+            history.record(&game);
+        }
+        history.truncate(2);
+        assert_eq!(ticks(&history), vec![1, 2]);
+        history.truncate(4);
+        assert_eq!(ticks(&history), vec![1, 2]);
+    }
+
+    #[test]
+    fn playback_stops_on_the_last_frame() {
+        let mut playback = Playback::new(2.0);
+        assert!(!playback.update(TICK_DURATION, 5));
+        assert_eq!(playback.frame, 2);
+
+        assert!(playback.update(TICK_DURATION * 10.0, 5));
+        assert_eq!(playback.frame, 4);
+        assert!(!playback.update(0.0, 5));
+        assert_eq!(playback.frame, 4);
+
+        let mut playback = Playback::new(1.0);
+        assert!(playback.update(0.0, 0));
+        assert_eq!(playback.frame, 0);
+    }
+}
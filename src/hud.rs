@@ -0,0 +1,284 @@
+use std::cell::Cell;
+
+use raylib::prelude::*;
+
+use snake::food::{self, Apple};
+use snake::game::{Game, GameMode, Player, TICK_DURATION, TIME_TO_MOVE};
+
+use crate::palette::{self, Palette};
+use crate::settings::Settings;
+
+// @note: Everything drawn over the board in screen space. Text is placed
+//  against an `Anchor`, a corner, an edge or the middle of the screen (the
+//  fixed size frame the window shows, see `display`), and it's measured with the
+//  font it's drawn in so centred text actually ends up centred. The widgets
+//  stack downwards from their anchor, each one returns the box it took up.
+
+const HUD_FONT_FILE: &str = r"assets\fonts\DejaVuSans-Bold.ttf";
+
+// The font gets rasterised once at each of these and text is drawn with the
+//  smallest one at least as big as asked for, so it's only scaled down a bit
+const FONT_SIZES: [i32; 2] = [20, 40];
+const LETTER_SPACING: f32 = 1.0;
+
+// Space kept free between the HUD and the edges of the screen
+const MARGIN: f32 = 12.0;
+// Between two widgets stacked on the same anchor
+const WIDGET_GAP: f32 = 2.0;
+
+const SHADOW_OFFSET: Vector2 = Vector2 { x: 2.0, y: 2.0 };
+const SHADOW_COLOR:  Color   = Color { r: 0, g: 0, b: 0, a: 90 };
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Anchor {
+    TOPLEFT,
+    TOP,
+    TOPRIGHT,
+    LEFT,
+    CENTER,
+    BOTTOMLEFT,
+    BOTTOMRIGHT
+}
+
+impl Anchor {
+    // Where the anchor is on a box, (0, 0) is its top left and (1, 1) its bottom right
+    fn fraction(self: &Self) -> Vector2 {
+        let (x, y) = match self {
+            Anchor::TOPLEFT     => (0.0, 0.0),
+            Anchor::TOP         => (0.5, 0.0),
+            Anchor::TOPRIGHT    => (1.0, 0.0),
+            Anchor::LEFT        => (0.0, 0.5),
+            Anchor::CENTER      => (0.5, 0.5),
+            Anchor::BOTTOMLEFT  => (0.0, 1.0),
+            Anchor::BOTTOMRIGHT => (1.0, 1.0),
+        };
+        Vector2 { x: x, y: y }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextStyle {
+    pub size:   f32,
+    pub color:  Color,
+    pub shadow: bool,
+    // Ignores the text size setting, for layouts that don't grow with it
+    pub fixed:  bool
+}
+
+impl TextStyle {
+    pub fn new(size: f32, color: Color) -> Self {
+        TextStyle { size: size, color: color, shadow: false, fixed: false }
+    }
+
+    pub fn shadowed(self: Self) -> Self {
+        TextStyle { shadow: true, ..self }
+    }
+
+    pub fn fixed(self: Self) -> Self {
+        TextStyle { fixed: true, ..self }
+    }
+}
+
+pub struct Hud {
+    // Empty when the TTF couldn't be loaded, raylib's own font is used instead
+    fonts:          Vec<(i32, Font)>,
+    default_font:   WeakFont,
+    // @note: Cells because the menus hold on to the HUD for as long as they
+    //  live, and the settings they change have to reach it right away
+    scale:          Cell<f32>,
+    palette:        Cell<Palette>
+}
+
+impl Hud {
+    pub fn load(raylib: &mut RaylibHandle, render_thread: &RaylibThread) -> Self {
+        let mut fonts = Vec::new();
+        for size in FONT_SIZES {
+            match raylib.load_font_ex(render_thread, HUD_FONT_FILE, size, None) {
+                Ok(font) => fonts.push((size, font)),
+                Err(error) => {
+                    println!("WARN: {} not loaded, falling back to the default font: {}", HUD_FONT_FILE, error);
+                    fonts.clear();
+                    break;
+                }
+            }
+        }
+
+        Hud {
+            fonts:          fonts,
+            default_font:   raylib.get_font_default(),
+            scale:          Cell::new(1.0),
+            palette:        Cell::new(Palette::STANDARD)
+        }
+    }
+
+    pub fn apply_settings(self: &Self, settings: &Settings) {
+        self.scale.set(settings.ui_scale);
+        self.palette.set(settings.palette);
+    }
+
+    // What the text size setting multiplies text by
+    pub fn scale(self: &Self) -> f32 {
+        self.scale.get()
+    }
+
+    pub fn palette(self: &Self) -> Palette {
+        self.palette.get()
+    }
+
+    fn font_for(self: &Self, size: f32) -> Option<&Font> {
+        self.fonts.iter()
+            .find(|(font_size, _)| *font_size as f32 >= size)
+            .or(self.fonts.last())
+            .map(|(_, font)| font)
+    }
+
+    pub fn measure(self: &Self, text: &str, size: f32) -> Vector2 {
+        match self.font_for(size) {
+            Some(font) => measure_text_ex(font, text, size, LETTER_SPACING),
+            None       => measure_text_ex(&self.default_font, text, size, LETTER_SPACING),
+        }
+    }
+
+    // Draws the text so its `anchor` point lands on `position`, e.g. CENTER
+    //  centres it there and TOPRIGHT right aligns it
+    pub fn text_at(self: &Self, draw_context: &mut impl RaylibDraw, text: &str, position: Vector2, anchor: Anchor, style: TextStyle) -> Rectangle {
+        let style = if style.fixed { style } else { TextStyle { size: style.size * self.scale(), ..style } };
+        let size = self.measure(text, style.size);
+        let fraction = anchor.fraction();
+        let top_left = Vector2 {
+            x: (position.x - size.x * fraction.x).round(),
+            y: (position.y - size.y * fraction.y).round()
+        };
+
+        if style.shadow {
+            let shadow_position = Vector2 { x: top_left.x + SHADOW_OFFSET.x, y: top_left.y + SHADOW_OFFSET.y };
+            let shadow_color = SHADOW_COLOR.fade(SHADOW_COLOR.a as f32 / 255.0 * style.color.a as f32 / 255.0);
+            self.draw_text(draw_context, text, shadow_position, style.size, shadow_color);
+        }
+        self.draw_text(draw_context, text, top_left, style.size, style.color);
+
+        Rectangle { x: top_left.x, y: top_left.y, width: size.x, height: size.y }
+    }
+
+    fn draw_text(self: &Self, draw_context: &mut impl RaylibDraw, text: &str, position: Vector2, size: f32, color: Color) {
+        match self.font_for(size) {
+            Some(font) => draw_context.draw_text_ex(font, text, position, size, LETTER_SPACING, color),
+            None       => draw_context.draw_text_ex(&self.default_font, text, position, size, LETTER_SPACING, color),
+        }
+    }
+
+    // Puts the text against the anchor's side of the screen, `offset` moves
+    //  it in from the edges (and along the centred axis as it is)
+    pub fn text(self: &Self, draw_context: &mut impl RaylibDraw, text: &str, anchor: Anchor, offset: Vector2, style: TextStyle) -> Rectangle {
+        let position = anchor_position(anchor, offset);
+        self.text_at(draw_context, text, position, anchor, style)
+    }
+
+    pub fn draw_game_hud(self: &Self, draw_context: &mut impl RaylibDraw, game: &Game, high_score: Option<u16>) {
+        match game.mode {
+            GameMode::SOLO => {
+                let style = TextStyle::new(20.0, Color::BLACK).shadowed();
+                let score = self.text(draw_context, &format!("Score {}", game.players[0].score), Anchor::TOPLEFT, Vector2::zero(), style);
+                if let Some(high_score) = high_score {
+                    let best = high_score.max(game.players[0].score);
+                    self.text(
+                        draw_context, &format!("Best {}", best), Anchor::TOPLEFT, below(score, 0.0),
+                        TextStyle::new(16.0, Color::DARKGRAY)
+                    );
+                }
+
+                self.draw_player_widgets(draw_context, &game.players[0], Anchor::TOPRIGHT, 0.0, palette::player_text_color(self.palette(), 0));
+                self.draw_apple_timer(draw_context, &game.apple, 0.0);
+            },
+            GameMode::VERSUS => {
+                let colors = palette::colors(self.palette()).player_text;
+                for (index, anchor) in [Anchor::TOPLEFT, Anchor::TOPRIGHT].into_iter().enumerate() {
+                    let style = TextStyle::new(20.0, colors[index]).shadowed();
+                    let score = self.text(
+                        draw_context, &format!("P{} {}", index + 1, game.players[index].score), anchor, Vector2::zero(), style
+                    );
+                    self.draw_player_widgets(draw_context, &game.players[index], anchor, score.height + WIDGET_GAP, colors[index]);
+                }
+
+                let round = self.text(
+                    draw_context,
+                    &format!("Round {}  ({} - {})", game.round, game.players[0].round_wins, game.players[1].round_wins),
+                    Anchor::TOP, Vector2::zero(), TextStyle::new(20.0, Color::BLACK).shadowed()
+                );
+                self.draw_apple_timer(draw_context, &game.apple, round.height + WIDGET_GAP);
+            },
+        }
+    }
+
+    // Length, speed and whatever is boosting the player right now, stacked
+    //  down from `top` under the anchor
+    fn draw_player_widgets(self: &Self, draw_context: &mut impl RaylibDraw, player: &Player, anchor: Anchor, top: f32, color: Color) {
+        let style = TextStyle::new(16.0, color);
+
+        let length = self.text(
+            draw_context, &format!("Length {}", player.snake.parts.len()), anchor, Vector2 { x: 0.0, y: top }, style
+        );
+
+        let moves_per_second = if player.speed_up { 1.0 / TICK_DURATION } else { 1.0 / TIME_TO_MOVE };
+        let speed = self.text(
+            draw_context, &format!("Speed {:.1}/s", moves_per_second), anchor, below(length, top), style
+        );
+
+        if player.speed_up {
+            self.text(
+                draw_context, "BOOST", anchor, below(speed, top + length.height + WIDGET_GAP),
+                TextStyle::new(16.0, palette::colors(self.palette()).warning).shadowed()
+            );
+        }
+    }
+
+    // What's on the board and how long it has left, with the early bonus
+    //  while it's still worth grabbing quickly
+    fn draw_apple_timer(self: &Self, draw_context: &mut impl RaylibDraw, apple: &Apple, top: f32) {
+        let colors = palette::colors(self.palette());
+        let color = if apple.time_left < apple.settings.blink_time { colors.danger } else { Color::BLACK };
+        let timer = self.text(
+            draw_context,
+            &format!("{}: {:.1}s", food::food_type_name(apple.food_type), apple.time_left.max(0.0)),
+            Anchor::TOP, Vector2 { x: 0.0, y: top }, TextStyle::new(20.0, color).shadowed()
+        );
+
+        let bonus = apple.early_bonus();
+        if bonus > 0 {
+            self.text(
+                draw_context, &format!("Bonus +{}", bonus), Anchor::TOP, below(timer, top),
+                TextStyle::new(16.0, colors.good)
+            );
+        }
+    }
+}
+
+pub fn screen_center() -> Vector2 {
+    Vector2 {
+        x: crate::SCREEN_WIDTH  as f32 / 2.0,
+        y: crate::SCREEN_HEIGHT as f32 / 2.0
+    }
+}
+
+fn anchor_position(anchor: Anchor, offset: Vector2) -> Vector2 {
+    let fraction = anchor.fraction();
+    let inwards = |fraction: f32, offset: f32| {
+        if fraction < 0.5 {
+            MARGIN + offset
+        } else if fraction > 0.5 {
+            -(MARGIN + offset)
+        } else {
+            offset
+        }
+    };
+
+    Vector2 {
+        x: crate::SCREEN_WIDTH  as f32 * fraction.x + inwards(fraction.x, offset.x),
+        y: crate::SCREEN_HEIGHT as f32 * fraction.y + inwards(fraction.y, offset.y)
+    }
+}
+
+// The offset that puts the next widget under one that started at `top`
+fn below(widget: Rectangle, top: f32) -> Vector2 {
+    Vector2 { x: 0.0, y: top + widget.height + WIDGET_GAP }
+}
@@ -0,0 +1,282 @@
+use std::fs;
+
+use crate::game::{self, Bounds, CELL_SIZE};
+
+// @note: A level is a board with walls in it, drawn as a grid of characters:
+//
+//      // comments start with two slashes
+//      name=Four pillars
+//      ..............
+//      ..##......##..
+//      ..............
+//
+//  `.` is floor and `#` a wall, one character per cell. The grid sets the size
+//  of the board, which is centred on the screen like the smaller board sizes.
+//  Snakes start where they always do, counted from the board's top left.
+
+const FLOOR: char = '.';
+const WALL:  char = '#';
+
+// Room for both snakes to start facing each other with space to turn
+const MIN_COLUMNS: i32 = 12;
+const MIN_ROWS:    i32 = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level {
+    pub name:       String,
+    pub columns:    i32,
+    pub rows:       i32,
+    // (column, row) of every wall cell
+    pub walls:      Vec<(i32, i32)>
+}
+
+impl Level {
+    // Reads and checks the level, `full` is the play area the board has to
+    //  fit in. Every problem found is in the error, one per line.
+    pub fn load(path: &str, full: Bounds) -> Result<Level, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("Failed to read level {}: {}", path, error))?;
+        let level = Level::parse(&text).map_err(|problems| problems.join("\n"))?;
+
+        let problems = level.problems(full);
+        if !problems.is_empty() {
+            return Err(problems.join("\n"));
+        }
+        Ok(level)
+    }
+
+    // Only checks the file is well formed, `problems` checks it's playable
+    pub fn parse(text: &str) -> Result<Level, Vec<String>> {
+        let mut level = Level { name: String::new(), columns: 0, rows: 0, walls: Vec::new() };
+        let mut errors = Vec::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                match key.trim() {
+                    "name" => level.name = value.trim().to_string(),
+                    other  => errors.push(format!("Line {}: unknown setting '{}'", line_number, other)),
+                }
+                continue;
+            }
+
+            let row = level.rows;
+            let columns = line.chars().count() as i32;
+            if level.rows == 0 {
+                level.columns = columns;
+            } else if columns != level.columns {
+                errors.push(format!("Line {}: row is {} cells wide, the first row is {}", line_number, columns, level.columns));
+            }
+
+            for (column, cell) in line.chars().enumerate() {
+                match cell {
+                    FLOOR => {},
+                    WALL  => level.walls.push((column as i32, row)),
+                    other => errors.push(format!("Line {}: unknown cell '{}', expected '{}' or '{}'", line_number, other, FLOOR, WALL)),
+                }
+            }
+            level.rows += 1;
+        }
+
+        if level.rows == 0 {
+            errors.push("The level has no grid".to_string());
+        }
+
+        if errors.is_empty() { Ok(level) } else { Err(errors) }
+    }
+
+    // Everything that keeps the level from being played on a board that has
+    //  to fit in `full`
+    pub fn problems(self: &Self, full: Bounds) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let max_columns = (full.width  / CELL_SIZE) as i32;
+        let max_rows    = (full.height / CELL_SIZE) as i32;
+        if self.columns > max_columns || self.rows > max_rows {
+            problems.push(format!("The board is {}x{}, at most {}x{} fits on the screen", self.columns, self.rows, max_columns, max_rows));
+        }
+        if self.columns < MIN_COLUMNS || self.rows < MIN_ROWS {
+            problems.push(format!("The board is {}x{}, it has to be at least {}x{}", self.columns, self.rows, MIN_COLUMNS, MIN_ROWS));
+        }
+        if !problems.is_empty() {
+            return problems;
+        }
+
+        // Where the snakes start and the cell each one moves into first
+        let area = self.play_area(full);
+        for player_index in 0..2 {
+            let snake = game::create_starting_snake(player_index, 2, area);
+            let head = snake.head();
+            let (ahead_x, ahead_y) = match snake.head_direction {
+                game::Direction::UP    => (head.x, head.y - CELL_SIZE),
+                game::Direction::LEFT  => (head.x - CELL_SIZE, head.y),
+                game::Direction::DOWN  => (head.x, head.y + CELL_SIZE),
+                game::Direction::RIGHT => (head.x + CELL_SIZE, head.y),
+            };
+
+            let cells = snake.parts.iter().map(|part| (part.x, part.y)).chain([(ahead_x, ahead_y)]);
+            for (x, y) in cells {
+                let cell = self.to_cell(area, x, y);
+                if self.walls.contains(&cell) {
+                    problems.push(format!("Player {} starts on or runs straight into the wall at {},{}", player_index + 1, cell.0, cell.1));
+                    break;
+                }
+            }
+        }
+
+        let start_snake = game::create_starting_snake(0, 2, area);
+        let start = self.to_cell(area, start_snake.head().x, start_snake.head().y);
+        let unreachable = self.floor_cells() - self.reachable_from(start);
+        if unreachable > 0 {
+            problems.push(format!("{} floor cells are walled off from where the snakes start", unreachable));
+        }
+
+        problems
+    }
+
+    // The board centred in `full`, kept on its grid
+    pub fn play_area(self: &Self, full: Bounds) -> Bounds {
+        let width  = (self.columns as f32 * CELL_SIZE).min(full.width);
+        let height = (self.rows    as f32 * CELL_SIZE).min(full.height);
+        Bounds {
+            x:      full.x + ((full.width  - width)  / 2.0 / CELL_SIZE).floor() * CELL_SIZE,
+            y:      full.y + ((full.height - height) / 2.0 / CELL_SIZE).floor() * CELL_SIZE,
+            width:  width,
+            height: height
+        }
+    }
+
+    // The walls as `Game::walls` wants them, for a board at `area`
+    pub fn walls_in(self: &Self, area: Bounds) -> Vec<(f32, f32)> {
+        self.walls.iter()
+            .map(|(column, row)| (area.x + *column as f32 * CELL_SIZE, area.y + *row as f32 * CELL_SIZE))
+            .collect()
+    }
+
+    fn to_cell(self: &Self, area: Bounds, x: f32, y: f32) -> (i32, i32) {
+        (((x - area.x) / CELL_SIZE).floor() as i32, ((y - area.y) / CELL_SIZE).floor() as i32)
+    }
+
+    fn floor_cells(self: &Self) -> usize {
+        (self.columns * self.rows) as usize - self.walls.len()
+    }
+
+    fn reachable_from(self: &Self, start: (i32, i32)) -> usize {
+        let in_bounds = |cell: (i32, i32)| cell.0 >= 0 && cell.1 >= 0 && cell.0 < self.columns && cell.1 < self.rows;
+        if !in_bounds(start) || self.walls.contains(&start) {
+            return 0;
+        }
+
+        let mut seen = vec![false; (self.columns * self.rows) as usize];
+        for (column, row) in &self.walls {
+            seen[(row * self.columns + column) as usize] = true;
+        }
+
+        let mut reached = 0;
+        let mut open = vec![start];
+        seen[(start.1 * self.columns + start.0) as usize] = true;
+        while let Some((column, row)) = open.pop() {
+            reached += 1;
+            for next in [(column + 1, row), (column - 1, row), (column, row + 1), (column, row - 1)] {
+                if in_bounds(next) && !seen[(next.1 * self.columns + next.0) as usize] {
+                    seen[(next.1 * self.columns + next.0) as usize] = true;
+                    open.push(next);
+                }
+            }
+        }
+        reached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A `columns` x `rows` grid with walls at the given cells
+    fn grid(columns: i32, rows: i32, walls: &[(i32, i32)]) -> String {
+        let mut text = String::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                text.push(if walls.contains(&(column, row)) { WALL } else { FLOOR });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    fn full() -> Bounds {
+        game::play_area_for_screen(640, 480)
+    }
+
+    #[test]
+    fn parses_the_name_and_walls() {
+        let level = Level::parse("// two pillars\nname = Pillars\n....\n.#..\n...#\n").unwrap();
+        assert_eq!(level.name, "Pillars");
+        assert_eq!((level.columns, level.rows), (4, 3));
+        assert_eq!(level.walls, vec![(1, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn bundled_levels_are_playable() {
+        for name in ["corridors", "pillars"] {
+            let path = format!("assets/levels/{}.txt", name);
+            if let Err(error) = Level::load(&path, full()) {
+                panic!("{}: {}", path, error);
+            }
+        }
+    }
+
+    #[test]
+    fn rows_of_different_widths_are_errors() {
+        let errors = Level::parse("....\n...\n.....\n").unwrap_err();
+        assert_eq!(errors, vec![
+            "Line 2: row is 3 cells wide, the first row is 4".to_string(),
+            "Line 3: row is 5 cells wide, the first row is 4".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn unknown_cells_and_settings_are_errors() {
+        let errors = Level::parse("size=3\n..x.\n").unwrap_err();
+        assert_eq!(errors, vec![
+            "Line 1: unknown setting 'size'".to_string(),
+            "Line 2: unknown cell 'x', expected '.' or '#'".to_string(),
+        ]);
+
+        assert_eq!(Level::parse("name=Empty\n// nothing here\n").unwrap_err(), vec!["The level has no grid".to_string()]);
+    }
+
+    #[test]
+    fn boards_have_to_fit_and_be_big_enough() {
+        let small = Level::parse(&grid(MIN_COLUMNS - 1, MIN_ROWS, &[])).unwrap();
+        assert_eq!(small.problems(full()), vec![format!("The board is 11x10, it has to be at least {}x{}", MIN_COLUMNS, MIN_ROWS)]);
+
+        let large = Level::parse(&grid(31, 20, &[])).unwrap();
+        assert_eq!(large.problems(full()), vec!["The board is 31x20, at most 30x20 fits on the screen".to_string()]);
+
+        let open = Level::parse(&grid(MIN_COLUMNS, MIN_ROWS, &[])).unwrap();
+        assert!(open.problems(full()).is_empty());
+    }
+
+    #[test]
+    fn a_walled_off_floor_is_a_problem() {
+        // A wall right across row 8 cuts off the bottom row
+        let walls: Vec<(i32, i32)> = (0..14).map(|column| (column, 8)).collect();
+        let level = Level::parse(&grid(14, 10, &walls)).unwrap();
+        assert_eq!(level.problems(full()), vec!["14 floor cells are walled off from where the snakes start".to_string()]);
+    }
+
+    #[test]
+    fn a_blocked_start_is_a_problem() {
+        // Player 1 starts at 5,4 heading right and player 2 at 8,5 heading left
+        let level = Level::parse(&grid(14, 10, &[(6, 4)])).unwrap();
+        assert_eq!(level.problems(full()), vec!["Player 1 starts on or runs straight into the wall at 6,4".to_string()]);
+
+        let level = Level::parse(&grid(14, 10, &[(8, 5)])).unwrap();
+        assert_eq!(level.problems(full()), vec!["Player 2 starts on or runs straight into the wall at 8,5".to_string()]);
+    }
+}
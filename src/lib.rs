@@ -0,0 +1,25 @@
+// @note: Everything that doesn't need raylib lives in the library so it can be
+//  built and used on its own, e.g. `cargo build --lib --no-default-features` to
+//  train agents against `gym` on a machine without a graphics stack. The game
+//  itself (main.rs) is the only thing that needs the `render` feature.
+
+// @note: `self: &Self` and spelled out field names are how this code is
+//  written, everything else clippy says is meant to be fixed.
+#![allow(clippy::needless_arbitrary_self_type, clippy::redundant_field_names)]
+
+pub mod food;
+pub mod game;
+pub mod events;
+pub mod ai;
+pub mod bot;
+pub mod net;
+pub mod gym;
+pub mod history;
+pub mod storage;
+pub mod highscores;
+pub mod stats;
+pub mod achievements;
+pub mod tournament;
+pub mod level;
+pub mod replay;
+pub mod cli;
@@ -1,40 +1,78 @@
+// @note: Same as lib.rs, `self: &Self`, spelled out field names and all caps
+//  variants are on purpose (the library's are public, so clippy already
+//  leaves those alone). The screens take every handle they draw with as its
+//  own argument, which runs past clippy's limit.
+#![allow(
+    clippy::needless_arbitrary_self_type,
+    clippy::redundant_field_names,
+    clippy::upper_case_acronyms,
+    clippy::too_many_arguments
+)]
+
 use raylib::prelude::*;
 
+mod board;
+mod camera;
+mod death;
+mod display;
+mod effects;
+mod hud;
+mod palette;
 mod particles;
+mod settings;
+mod ui;
+
+use snake::{ai, bot, cli, game, net, tournament};
+use camera::GameCamera;
+use death::{DeathSequence, RoundPhase};
+use display::{Display, Frame};
+use effects::ParticleEffects;
+use hud::{Anchor, Hud, TextStyle};
+use palette::Palette;
+use particles::{EmitterId, ParticleSystem};
+use settings::{PlayerControls, Settings, SettingsFile, SettingsMenu};
+use ui::Ui;
+use snake::achievements::{self, Achievement, Achievements};
+use snake::events::{EventBus, GameEvent, Subscriber};
+use snake::highscores::{HighScore, HighScores};
+use snake::history::{History, Playback};
+use snake::level::Level;
+use snake::replay::{Recording, Replayer};
+use snake::stats::{Stats, StatsTracker};
+use snake::ai::Strategy;
+use snake::food::{Apple, FoodType};
+use snake::game::{Death, DeathCause, Direction, Game, GameMode, Snake, WallSide, CELL_SIZE, TICK_DURATION};
 
 const SCREEN_WIDTH : u32 = 640;
 const SCREEN_HEIGHT: u32 = 480;
 
-const STARTING_SNAKE_LENGTH: u32 = 3;
+// @note: Sitting on the title screen this long starts the attract mode where
+//  the computer plays by itself, and a finished demo restarts after the delay.
+const ATTRACT_MODE_DELAY: f32 = 10.0;
+const DEMO_RESTART_DELAY: f32 = 2.0;
 
-const MAX_APPLE_DURATION: f32 = 5.0;
+// How much of the end of a round can be watched again, and how fast
+const REWIND_SECONDS:       f32 = 3.0;
+const REWIND_PLAYBACK_SPEED: f32 = 0.5;
 
-#[derive(PartialEq, Clone, Copy, Debug)]
-enum Direction {
-    UP,
-    LEFT,
-    DOWN,
-    RIGHT
-}
+// Practice keeps a longer history and scrubs through it faster than real time
+const PRACTICE_REWIND_SECONDS: f32 = 10.0;
+const PRACTICE_SCRUB_SPEED:    f32 = 2.0;
 
-#[derive(PartialEq, Clone, Copy)]
-struct SnakePart {
-    x: f32,
-    y: f32,
-    direction: Direction
-}
+const TOAST_DURATION: f32 = 3.0;
 
-#[derive(PartialEq, Clone)]
-struct Snake {
-    parts: Vec<SnakePart>,
-    head_direction: Direction
-}
+// The stats table only just fits on screen as it is, bigger text settings
+//  grow it up to this and no further
+const STATS_MAX_SCALE: f32 = 1.15;
 
-impl Snake {
-    fn head(self: &Self) -> SnakePart {
-        return self.parts.last().unwrap().clone();
-    }
-}
+// Particles that can be on screen at once, across every emitter
+const PARTICLE_CAPACITY: usize = 1024;
+
+// High contrast mode puts this around the snakes and the food
+const OUTLINE_COLOR: Color = Color::BLACK;
+const OUTLINE_WIDTH: f32   = 2.0;
+// The polygons food shapes are drawn as, from the middle of the cell
+const FOOD_SHAPE_RADIUS: f32 = 9.0;
 
 struct TextureMap {
     snake_head_straight_texture:             Texture2D, 
@@ -42,23 +80,203 @@ struct TextureMap {
     snake_head_turning_left_texture:        Texture2D, 
     snake_corner_texture:                   Texture2D, 
     snake_middle_texture:                   Texture2D, 
+    // @note: Loaded but not drawn yet, the last middle piece stands in for it
+    #[allow(dead_code)]
     snake_tail_texture:                     Texture2D,
     apple_texture:                          Texture2D,
     background_texture:                     Texture2D,
+    particle_atlas_texture:                 Texture2D,
+}
+
+enum LaunchMode {
+    LOCAL(cli::PlayOptions),
+    REPLAY(cli::ReplayOptions),
+    SERVER(String),
+    CLIENT(String),
+    HOST(String),
+    SPECTATE(String),
+    BROADCAST(String),
+    // The bot commands and whether to run without a window
+    BOT(Vec<String>, bool),
+    TOURNAMENT(Vec<String>),
+    // The headless tools in cli.rs, with the arguments after the command
+    SIM(Vec<String>),
+    BENCH(Vec<String>),
+    VALIDATELEVEL(Vec<String>),
+    EXPORTSTATS(Vec<String>),
+    // Just prints the usage text
+    HELP(&'static str)
+}
+
+// @note: `snake` plays locally, `snake server [bind address]` runs a headless 
+//  versus server, `snake client [server address]` joins one and `snake host 
+//  [bind address]` does both in one process. `snake spectate [address]` watches 
+//  a server or a local game started with `snake broadcast [bind address]`.
+//  `snake bot <command> [command]` plays one or two external bots against each
+//  other and `snake bot-headless` does the same without a window.
+//  `snake tournament --help` lists the options for comparing AI strategies.
+//  `snake --help` lists everything, the rest of the commands are in cli.rs.
+fn parse_launch_mode(args: &[String]) -> Result<LaunchMode, String> {
+    let address = args.get(2).cloned();
+    let command_args = args.get(2..).unwrap_or(&[]).to_vec();
+    let is_network = matches!(args.get(1).map(|arg| arg.as_str()), Some("server" | "client" | "host" | "spectate" | "broadcast"));
+    if is_network && cli::wants_help(&command_args) {
+        return Ok(LaunchMode::HELP(cli::USAGE));
+    }
+
+    let launch_mode = match args.get(1).map(|arg| arg.as_str()) {
+        Some("--help" | "-h" | "help") => LaunchMode::HELP(cli::USAGE),
+        Some("play") if cli::wants_help(&command_args)   => LaunchMode::HELP(cli::PLAY_USAGE),
+        Some("play")   => LaunchMode::LOCAL(cli::parse_play_options(&command_args)?),
+        Some("replay") if cli::wants_help(&command_args) => LaunchMode::HELP(cli::REPLAY_USAGE),
+        Some("replay") => LaunchMode::REPLAY(cli::parse_replay_options(&command_args)?),
+        Some("sim")    => LaunchMode::SIM(command_args),
+        Some("bench")  => LaunchMode::BENCH(command_args),
+        Some("validate-level") => LaunchMode::VALIDATELEVEL(command_args),
+        Some("export-stats")   => LaunchMode::EXPORTSTATS(command_args),
+        Some("server") => LaunchMode::SERVER(address.unwrap_or(net::DEFAULT_BIND_ADDRESS.to_string())),
+        Some("client") => LaunchMode::CLIENT(address.unwrap_or(net::DEFAULT_SERVER_ADDRESS.to_string())),
+        Some("host")   => LaunchMode::HOST(address.unwrap_or(net::DEFAULT_BIND_ADDRESS.to_string())),
+        Some("spectate")  => LaunchMode::SPECTATE(address.unwrap_or(net::DEFAULT_SERVER_ADDRESS.to_string())),
+        Some("broadcast") => LaunchMode::BROADCAST(address.unwrap_or(net::DEFAULT_BIND_ADDRESS.to_string())),
+        Some("bot")          => LaunchMode::BOT(args[2..].to_vec(), false),
+        Some("bot-headless") => LaunchMode::BOT(args[2..].to_vec(), true),
+        Some("tournament")   => LaunchMode::TOURNAMENT(args[2..].to_vec()),
+        Some(other)    => return Err(format!("Unknown command '{}'\n\n{}", other, cli::USAGE)),
+        None => LaunchMode::LOCAL(cli::PlayOptions::default()),
+    };
+    Ok(launch_mode)
 }
 
-#[derive(Clone, Copy, Debug)]
-struct Apple {
-    x: f32,
-    y: f32,
-    time_left: f32
+// @note: `--set key=value` (any number of times) overrides a setting for this
+//  run and `--settings <file>` reads and saves the settings somewhere else.
+//  They can go anywhere on the command line, they're taken out before the
+//  mode and its arguments are looked at.
+fn take_settings_args(args: &mut Vec<String>) -> (Option<std::path::PathBuf>, Vec<String>) {
+    let mut path = None;
+    let mut overrides = Vec::new();
+
+    let mut index = 1;
+    while index < args.len() {
+        if args[index] != "--set" && args[index] != "--settings" {
+            index += 1;
+            continue;
+        }
+        let flag = args.remove(index);
+        if index >= args.len() {
+            eprintln!("WARN: Missing value for {}", flag);
+            break;
+        }
+        let value = args.remove(index);
+        if flag == "--set" {
+            overrides.push(value);
+        } else {
+            path = Some(std::path::PathBuf::from(value));
+        }
+    }
+
+    (path, overrides)
 }
 
 fn main() {
-    let (mut raylib, render_thread) = raylib::init()
+    let play_area = game::play_area_for_screen(SCREEN_WIDTH, SCREEN_HEIGHT);
+
+    let mut args: Vec<String> = std::env::args().collect();
+    let (settings_path, settings_overrides) = take_settings_args(&mut args);
+
+    let launch_mode = match parse_launch_mode(&args) {
+        Ok(launch_mode) => launch_mode,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    if let LaunchMode::HELP(usage) = &launch_mode {
+        println!("{}", usage);
+        return;
+    }
+    if let LaunchMode::SERVER(bind_address) = &launch_mode {
+        net::run_server(bind_address, play_area);
+        return;
+    }
+    let tool_result = match &launch_mode {
+        LaunchMode::BOT(args, true)     => Some(bot::run_headless(args, play_area)),
+        LaunchMode::TOURNAMENT(args)    => Some(tournament::run(args, play_area)),
+        LaunchMode::SIM(args)           => Some(cli::run_sim(args, play_area)),
+        LaunchMode::BENCH(args)         => Some(cli::run_bench(args, play_area)),
+        LaunchMode::VALIDATELEVEL(args) => Some(cli::run_validate_level(args, play_area)),
+        LaunchMode::EXPORTSTATS(args)   => Some(cli::run_export_stats(args)),
+        _ => None,
+    };
+    // Errors go to stderr, the tools' output is usually redirected to a file
+    if let Some(result) = tool_result {
+        if let Err(error) = result {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Everything the window needs from the files it was pointed at is read
+    //  before it opens, so a typo doesn't flash a window up
+    let mut replay_recording: Option<Recording> = None;
+    if let LaunchMode::REPLAY(options) = &launch_mode {
+        match cli::load_recording(&options.file) {
+            Ok(loaded) if options.summary => {
+                cli::print_replay_summary(&loaded);
+                return;
+            },
+            Ok(loaded) => replay_recording = Some(loaded),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut level: Option<Level> = None;
+    if let LaunchMode::LOCAL(cli::PlayOptions { level: Some(path), .. }) = &launch_mode {
+        match Level::load(path, play_area) {
+            Ok(loaded) => level = Some(loaded),
+            Err(problems) => {
+                eprintln!("{} can't be played:\n{}", path, problems);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let (mut raylib_handle, render_thread) = raylib::init()
         .size(SCREEN_WIDTH as i32, SCREEN_HEIGHT as i32)
         .title("Snake!")
+        .resizable()
         .build();
+    let mut raylib = &mut raylib_handle;
+
+    let (mut settings_file, mut settings) = SettingsFile::load(settings_path);
+    settings_file.apply_overrides(&mut settings, &settings_overrides);
+    raylib.set_target_fps(settings.target_fps);
+
+    // Like --set, difficulty only holds for this run
+    let play_options = match &launch_mode {
+        LaunchMode::LOCAL(options) => options.clone(),
+        _ => cli::PlayOptions::default(),
+    };
+    if let Some(strategy) = play_options.difficulty {
+        settings_file.apply_overrides(&mut settings, &[format!("gameplay.computer={}", ai::strategy_name(strategy))]);
+    }
+    if play_options.fullscreen {
+        settings_file.apply_overrides(&mut settings, &["graphics.fullscreen=true".to_string()]);
+    }
+
+    let mut display = match Display::new(raylib, &render_thread, SCREEN_WIDTH, SCREEN_HEIGHT) {
+        Ok(display) => display,
+        Err(error) => {
+            println!("Failed to create the render target: {}", error);
+            return;
+        }
+    };
+    display.fullscreen = settings.fullscreen;
+    display.integer_scaling = settings.integer_scaling;
 
     let audio_device = RaylibAudio::init_audio_device();
     if audio_device.is_err() {
@@ -72,7 +290,14 @@ fn main() {
         println!("Failed to load gulp sound effect. ");
         return;
     }
-    let gulp_sound = gulp_sound.unwrap();
+    let mut gulp_sound = gulp_sound.unwrap();
+
+    let crumble_sound   = audio_device.new_sound(r"assets\sounds\crumble.wav");
+    if  crumble_sound.is_err() {
+        println!("Failed to load crumble sound effect. ");
+        return;
+    }
+    let mut crumble_sound = crumble_sound.unwrap();
 
     let background_music   = audio_device.new_music(r"assets\music\jungle_background.mp3");
     if  background_music.is_err() {
@@ -80,7 +305,10 @@ fn main() {
         return;
     }
     let mut background_music = background_music.unwrap();
-    background_music.set_volume(1.0);
+
+    background_music.set_volume(settings.music_volume);
+    gulp_sound.set_volume(settings.effects_volume);
+    crumble_sound.set_volume(settings.effects_volume);
     background_music.play_stream();
     if !background_music.is_stream_playing() {
         println!("Music isnt playing...");
@@ -95,124 +323,1222 @@ fn main() {
         snake_tail_texture:                 raylib.load_texture(&render_thread, r"assets\sprites\snake_tail_20x20.png").unwrap(),
         apple_texture:                      raylib.load_texture(&render_thread, r"assets\sprites\apple_20x20.png").unwrap(),
         background_texture:                 raylib.load_texture(&render_thread, r"assets\sprites\background.png").unwrap(),
+        particle_atlas_texture:             raylib.load_texture(&render_thread, r"assets\sprites\particles_20x20.png").unwrap(),
     };
 
-    let mut snake = create_starting_snake();
+    let hud = Hud::load(raylib, &render_thread);
+    hud.apply_settings(&settings);
+    let mut particle_effects = ParticleEffects::load();
+
+    let mut broadcaster: Option<net::Broadcaster> = None;
+    match launch_mode {
+        LaunchMode::REPLAY(options) => {
+            if let Some(recording) = &replay_recording {
+                run_replay(raylib, &render_thread, &mut display, &texture_map, &hud, &settings, &gulp_sound, &mut particle_effects, &mut background_music, recording, options.speed);
+            }
+            return;
+        },
+        LaunchMode::CLIENT(server_address) => {
+            run_network_client(raylib, &render_thread, &mut display, &texture_map, &hud, &settings, &gulp_sound, &mut particle_effects, &mut background_music, &server_address);
+            return;
+        },
+        LaunchMode::SPECTATE(address) => {
+            run_spectator(raylib, &render_thread, &mut display, &texture_map, &hud, &settings, &gulp_sound, &mut particle_effects, &mut background_music, &address);
+            return;
+        },
+        LaunchMode::BOT(commands, _) => {
+            run_bot_match(raylib, &render_thread, &mut display, &texture_map, &hud, &settings, &gulp_sound, &mut particle_effects, &mut background_music, &commands, play_area);
+            return;
+        },
+        LaunchMode::BROADCAST(bind_address) => {
+            match net::Broadcaster::bind(&bind_address) {
+                Ok(new_broadcaster) => {
+                    println!("Broadcasting to spectators on {}", bind_address);
+                    broadcaster = Some(new_broadcaster);
+                },
+                Err(error) => {
+                    println!("{}", error);
+                    return;
+                }
+            }
+        },
+        LaunchMode::HOST(bind_address) => {
+            let mut server = match net::Server::bind(&bind_address, play_area) {
+                Ok(server) => server,
+                Err(error) => {
+                    println!("{}", error);
+                    return;
+                }
+            };
+            let port = server.local_address().map_or(7777, |address| address.port());
+            std::thread::spawn(move || server.run());
+
+            let server_address = format!("127.0.0.1:{}", port);
+            println!("Hosting on port {}, the other player can join with `snake client <your ip>:{}`", port, port);
+            run_network_client(raylib, &render_thread, &mut display, &texture_map, &hud, &settings, &gulp_sound, &mut particle_effects, &mut background_music, &server_address);
+            return;
+        },
+        _ => {},
+    }
+
+    // @note: None while we're sitting on the title screen
+    let mut game: Option<Game> = None;
+
+    // `snake play --mode` skips the title screen and --seed seeds that first game
+    let mut start_mode = play_options.mode;
+    let mut next_seed = play_options.seed;
+    // Inputs of the game being played, saved as a replay once it's over.
+    //  None for games the computer plays on its own and for practice.
+    let mut game_recording: Option<Recording> = None;
+
+    // Which players the computer steers, indexed by player
+    let mut computer_players: Vec<Option<Strategy>> = Vec::new();
+    let mut is_demo = false;
+    let mut idle_time = 0.0;
+    let mut demo_restart_time = 0.0;
+
+    let mut history = History::with_duration(REWIND_SECONDS);
+    let mut rewind: Option<Playback> = None;
+
+    // @note: Practice games can be rewound and carried on from any point kept
+    //  in the history, so their scores never count as high scores.
+    let mut is_practice = false;
+    let mut scrub_position: Option<f32> = None;
+
+    let mut high_scores = HighScores::load();
+    let mut high_score_rank: Option<usize> = None;
+
+    let mut stats = StatsTracker::load();
+    let mut show_stats = false;
+
+    let mut ui = Ui::new(&hud);
+    let mut settings_menu = SettingsMenu::new();
+    let mut show_settings = false;
+
+    let mut achievements = Achievements::load();
+    // The achievement being shown and how long it has been up
+    let mut toast: Option<(&'static Achievement, f32)> = None;
+
+    let mut particle_system = ParticleSystem::new(PARTICLE_CAPACITY);
+
+    let mut events = EventBus::new();
+    let mut head_trails = HeadTrails::new();
+    let mut camera = GameCamera::new(settings.motion, settings.palette, Vector2 {
+        x: SCREEN_WIDTH  as f32 / 2.0,
+        y: SCREEN_HEIGHT as f32 / 2.0
+    });
+    let mut death_sequence = DeathSequence::new();
+
+    while !raylib.window_should_close() {
+
+        display.present(raylib, &render_thread);
+        // F11 works anywhere, keep the setting in step with it
+        if settings.fullscreen != display.fullscreen {
+            settings.fullscreen = display.fullscreen;
+            settings_file.save(&settings);
+        }
+
+        background_music.update_stream();
+        if !background_music.is_stream_playing() {
+            background_music.play_stream();
+        }
+
+        if game.is_none() && show_settings {
+            ui.begin(raylib);
+            let mut draw_context = raylib.begin_texture_mode(&render_thread, &mut display.target);
+            draw_title_screen(&mut draw_context, &texture_map, &hud, &settings, false);
+            if settings_menu.draw(&mut ui, &mut draw_context, &mut settings) {
+                show_settings = false;
+                idle_time = 0.0;
+                settings_file.save(&settings);
+            }
+
+            background_music.set_volume(settings.music_volume);
+            gulp_sound.set_volume(settings.effects_volume);
+            crumble_sound.set_volume(settings.effects_volume);
+            camera.motion = settings.motion;
+            camera.palette = settings.palette;
+            hud.apply_settings(&settings);
+            display.fullscreen = settings.fullscreen;
+            display.integer_scaling = settings.integer_scaling;
+            continue;
+        }
+
+        if game.is_none() && show_stats {
+            if raylib.is_key_released(KeyboardKey::KEY_ENTER) || raylib.is_key_released(KeyboardKey::KEY_BACKSPACE) {
+                show_stats = false;
+            }
+
+            let mut draw_context = raylib.begin_texture_mode(&render_thread, &mut display.target);
+            draw_stats_screen(&mut draw_context, &texture_map, &hud, &stats, &achievements);
+            continue;
+        }
+
+        if game.is_none() {
+            idle_time += raylib.get_frame_time();
+            is_demo = false;
+            is_practice = false;
+            scrub_position = None;
+            high_score_rank = None;
+
+            let start = start_mode.take().or_else(|| read_title_choice(raylib, idle_time));
+            if let Some(mode) = start {
+                let seed = next_seed.take().unwrap_or_else(rand::random);
+                let game_mode = if matches!(mode, cli::PlayMode::VERSUS | cli::PlayMode::COMPUTER) { GameMode::VERSUS } else { GameMode::SOLO };
+                let new_game = new_local_game(game_mode, &settings, level.as_ref(), seed);
+
+                computer_players = match mode {
+                    cli::PlayMode::VERSUS   => vec![None, None],
+                    cli::PlayMode::COMPUTER => vec![None, Some(settings.computer_strategy)],
+                    cli::PlayMode::DEMO     => vec![Some(settings.computer_strategy)],
+                    _                       => vec![None],
+                };
+                is_demo = mode == cli::PlayMode::DEMO;
+                is_practice = mode == cli::PlayMode::PRACTICE;
+                game_recording = if is_demo || is_practice { None } else { Some(Recording::new(&new_game, seed)) };
+                game = Some(new_game);
+            } else if raylib.is_key_released(KeyboardKey::KEY_SIX) {
+                show_stats = true;
+                idle_time = 0.0;
+            } else if raylib.is_key_released(KeyboardKey::KEY_SEVEN) {
+                show_settings = true;
+                ui.reset();
+                idle_time = 0.0;
+            } else if raylib.is_key_released(KeyboardKey::KEY_S) {
+                let index = ai::STRATEGIES.iter().position(|strategy| *strategy == settings.computer_strategy).unwrap_or(0);
+                settings.computer_strategy = ai::STRATEGIES[(index + 1) % ai::STRATEGIES.len()];
+                settings_file.save(&settings);
+                idle_time = 0.0;
+            }
+
+            if game.is_some() {
+                history = History::with_duration(if is_practice { PRACTICE_REWIND_SECONDS } else { REWIND_SECONDS });
+                camera.reset();
+            }
+
+            let mut draw_context = raylib.begin_texture_mode(&render_thread, &mut display.target);
+            draw_title_screen(&mut draw_context, &texture_map, &hud, &settings, true);
+            if let Some(best) = high_scores.best() {
+                hud.text(
+                    &mut draw_context, &format!("High score: {}", best),
+                    Anchor::BOTTOMRIGHT, Vector2::zero(), TextStyle::new(20.0, Color::DARKGRAY)
+                );
+            }
+            continue;
+        }
+        idle_time = 0.0;
+        let current_game = game.as_mut().unwrap();
+
+        // A new round, a new game or carrying on from the rewind all start
+        //  playing again
+        if !current_game.round_over {
+            death_sequence.reset();
+        }
+        let round_finished = death_sequence.phase == RoundPhase::OVER;
+
+        // Any key leaves the attract mode, a finished demo just plays again
+        if is_demo {
+            if raylib.get_key_pressed().is_some() {
+                game = None;
+                continue;
+            }
+            if round_finished {
+                idle_time = 0.0;
+                demo_restart_time += raylib.get_frame_time();
+                if demo_restart_time >= DEMO_RESTART_DELAY {
+                    *current_game = new_local_game(GameMode::SOLO, &settings, level.as_ref(), rand::random());
+                    demo_restart_time = 0.0;
+                }
+            }
+        }
+
+        if !is_demo && scrub_position.is_none() && round_finished && raylib.is_key_released(KeyboardKey::KEY_ENTER) {
+            if current_game.is_match_over() {
+                if current_game.mode == GameMode::SOLO {
+                    let seed = rand::random();
+                    *current_game = new_local_game(GameMode::SOLO, &settings, level.as_ref(), seed);
+                    if !is_practice {
+                        game_recording = Some(Recording::new(current_game, seed));
+                    }
+                    high_score_rank = None;
+                } else {
+                    game = None;
+                    continue;
+                }
+            } else {
+                current_game.start_round();
+            }
+        }
+
+        if round_finished && raylib.is_key_released(KeyboardKey::KEY_BACKSPACE) {
+            game = None;
+            rewind = None;
+            continue;
+        }
+
+        if is_practice && death_sequence.phase != RoundPhase::DYING {
+            let last_frame = history.len().saturating_sub(1) as f32;
+            let scrub_step = raylib.get_frame_time() / TICK_DURATION * PRACTICE_SCRUB_SPEED;
+            if raylib.is_key_down(KeyboardKey::KEY_R) && !history.is_empty() {
+                scrub_position = Some((scrub_position.unwrap_or(last_frame) - scrub_step).max(0.0));
+            } else if raylib.is_key_down(KeyboardKey::KEY_F) {
+                scrub_position = scrub_position.map(|position| (position + scrub_step).min(last_frame));
+            }
+
+            // While scrubbing the game is paused on the chosen state. Steering
+            //  or ENTER carries on from there and drops everything after it.
+            if let Some(position) = scrub_position {
+                let frame = position as usize;
+                let resume = raylib.is_key_released(KeyboardKey::KEY_ENTER)
+                    || read_direction_input(raylib, &settings.controls_for(0)).is_some();
+
+                if resume {
+                    if let Some(past_game) = history.get(frame) {
+                        *current_game = past_game.clone();
+                        current_game.time_accumulator = 0.0;
+                    }
+                    // The restored state gets recorded again on the next step
+                    history.truncate(frame);
+                    scrub_position = None;
+                } else {
+                    let mut draw_context = raylib.begin_texture_mode(&render_thread, &mut display.target);
+                    if let Some(past_game) = history.get(frame) {
+                        draw_world(&mut draw_context, &texture_map, past_game, past_game, &particle_system, &camera, None, &settings);
+                        hud.draw_game_hud(&mut draw_context, past_game, high_scores.best());
+                    }
+                    draw_scrubber(&mut draw_context, &hud, frame, history.len());
+                    continue;
+                }
+            }
+        }
+
+        if !is_demo && !is_practice && round_finished && !history.is_empty() && raylib.is_key_released(KeyboardKey::KEY_R) {
+            rewind = Some(Playback::new(REWIND_PLAYBACK_SPEED));
+        }
+        if !current_game.round_over {
+            rewind = None;
+        }
+
+        if let Some(playback) = rewind.as_mut() {
+            let finished = playback.update(raylib.get_frame_time(), history.len());
+            let frame = playback.frame;
+
+            let mut draw_context = raylib.begin_texture_mode(&render_thread, &mut display.target);
+            if let Some(past_game) = history.get(frame) {
+                draw_world(&mut draw_context, &texture_map, past_game, current_game, &particle_system, &camera, None, &settings);
+            }
+            draw_rewind_overlay(&mut draw_context, &hud, frame, history.len());
+
+            if finished {
+                rewind = None;
+            }
+            continue;
+        }
+
+        for (index, player) in current_game.players.iter_mut().enumerate() {
+            if computer_players.get(index).copied().flatten().is_some() {
+                continue;
+            }
+            let controls = settings.controls_for(index);
+            if let Some(direction) = read_direction_input(raylib, &controls) {
+                game::steer_snake(&mut player.snake, direction);
+            }
+            player.speed_up = raylib.is_key_down(controls.boost);
+        }
+
+        let mut draw_context = raylib.begin_texture_mode(&render_thread, &mut display.target);
+
+        particle_system.step(draw_context.get_frame_time());
+        particle_effects.update(draw_context.get_frame_time());
+        death_sequence.update(draw_context.get_frame_time(), &mut particle_system, &mut particle_effects, settings.palette);
+        let game_time = camera.update(draw_context.get_frame_time());
+
+        let step_result = current_game.update(game_time, |game| {
+            ai::steer_ai_players(game, &computer_players);
+            if let Some(recording) = game_recording.as_mut() {
+                recording.record(game);
+            }
+            history.record(game);
+        });
+        let round_ended = step_result.events.iter().any(|event| matches!(event, GameEvent::DECIDED(_)));
+        events.emit_all(step_result.events);
+
+        // Keep the state the round ended on too, that's where the rewind stops
+        if round_ended {
+            history.record(current_game);
+
+            let is_human_solo = current_game.mode == GameMode::SOLO && computer_players.first().copied().flatten().is_none();
+            if is_human_solo && !is_practice {
+                let player = &current_game.players[0];
+                high_score_rank = high_scores.submit(HighScore { score: player.score, length: player.snake.parts.len() });
+            }
+
+            if current_game.is_match_over() {
+                if let Some(recording) = game_recording.take() {
+                    save_recording(&recording, &play_options.record_path());
+                }
+            }
+        }
+
+        let mut effects = GameEffects { gulp_sound: &gulp_sound, particle_system: &mut particle_system, particle_effects: &mut particle_effects, palette: settings.palette };
+        let tracks_stats = !is_demo && !is_practice && computer_players.first().copied().flatten().is_none();
+        if tracks_stats {
+            events.dispatch(current_game, &mut [&mut effects, &mut camera, &mut stats, &mut achievements]);
+        } else {
+            events.dispatch(current_game, &mut [&mut effects, &mut camera]);
+        }
+        head_trails.update(&mut particle_system, &mut particle_effects, current_game, settings.palette);
+
+        if round_ended {
+            death_sequence.start(current_game);
+            if death_sequence.phase == RoundPhase::DYING {
+                crumble_sound.play();
+            }
+        }
+
+        if let Some(broadcaster) = broadcaster.as_mut() {
+            broadcaster.publish(current_game);
+        }
+
+        draw_world(&mut draw_context, &texture_map, current_game, current_game, &particle_system, &camera, Some(&death_sequence), &settings);
+
+        if death_sequence.phase == RoundPhase::OVER {
+            draw_round_over(&mut draw_context, &hud, current_game, !is_demo);
 
-    let mut apple = Apple {
-        x: 200.0,
-        y: 200.0,
-        time_left: MAX_APPLE_DURATION
+            let center = hud::screen_center();
+            let hint = if is_practice {
+                Some("Hold R to rewind and keep going")
+            } else if !is_demo && !history.is_empty() {
+                Some("R - rewind")
+            } else {
+                None
+            };
+            if let Some(hint) = hint {
+                hud.text_at(
+                    &mut draw_context, hint, Vector2 { x: center.x, y: center.y + 92.0 }, Anchor::CENTER,
+                    TextStyle::new(20.0, Color::BLACK)
+                );
+            }
+            if let Some(rank) = high_score_rank {
+                hud.text_at(
+                    &mut draw_context, &format!("New high score! #{}", rank + 1), Vector2 { x: center.x, y: center.y - 40.0 },
+                    Anchor::CENTER, TextStyle::new(20.0, palette::colors(settings.palette).good).shadowed()
+                );
+            }
+        } else {
+            hud.draw_game_hud(&mut draw_context, current_game, high_scores.best());
+        }
+
+        if is_practice {
+            hud.text(&mut draw_context, "PRACTICE - hold R to rewind", Anchor::BOTTOMRIGHT, Vector2::zero(), TextStyle::new(20.0, Color::MAROON));
+        }
+
+        if is_demo {
+            hud.text(
+                &mut draw_context, &format!("DEMO ({})  press any key", ai::strategy_name(settings.computer_strategy)),
+                Anchor::BOTTOMLEFT, Vector2::zero(), TextStyle::new(20.0, Color::MAROON)
+            );
+        }
+
+        if let Some(broadcaster) = broadcaster.as_ref() {
+            hud.text(
+                &mut draw_context, &format!("{} watching", broadcaster.spectator_count()),
+                Anchor::BOTTOMLEFT, Vector2::zero(), TextStyle::new(20.0, Color::MAROON)
+            );
+        }
+
+        if toast.is_none() {
+            toast = achievements.next_toast().map(|achievement| (achievement, 0.0));
+        }
+        if let Some((achievement, time_shown)) = toast.as_mut() {
+            *time_shown += draw_context.get_frame_time();
+            draw_achievement_toast(&mut draw_context, &hud, achievement, *time_shown);
+            if *time_shown >= TOAST_DURATION {
+                toast = None;
+            }
+        }
+    }
+}
+
+fn run_network_client(
+    mut raylib:         &mut RaylibHandle,
+    render_thread:      &RaylibThread,
+    display:            &mut Display,
+    texture_map:        &TextureMap,
+    hud:                &Hud,
+    settings:           &Settings,
+    gulp_sound:         &Sound,
+    particle_effects:   &mut ParticleEffects,
+    background_music:   &mut Music,
+    server_address:     &str) {
+
+    let mut client = match net::Client::connect(server_address) {
+        Ok(client) => client,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
     };
 
-    let play_area = Rectangle {
-        x: 20.0, 
-        y: 40.0, 
-        width:  SCREEN_WIDTH  as f32 - 40.0, 
-        height: SCREEN_HEIGHT as f32 - 80.0
+    let mut particle_system = ParticleSystem::new(PARTICLE_CAPACITY);
+
+    let mut snapshot_watcher = SnapshotWatcher::new();
+    let mut events = EventBus::new();
+    let mut head_trails = HeadTrails::new();
+    let mut camera = GameCamera::new(settings.motion, settings.palette, Vector2 {
+        x: SCREEN_WIDTH  as f32 / 2.0,
+        y: SCREEN_HEIGHT as f32 / 2.0
+    });
+
+    while !raylib.window_should_close() {
+
+        display.present(raylib, render_thread);
+
+        background_music.update_stream();
+        if !background_music.is_stream_playing() {
+            background_music.play_stream();
+        }
+
+        // @note: Online each window is one player, so either set of keys works
+        let online_controls = [settings.controls_for(0), settings.controls_for(1)];
+        for controls in &online_controls {
+            if let Some(direction) = read_direction_input(raylib, controls) {
+                client.steer(direction);
+            }
+        }
+        client.set_speed_up(online_controls.iter().any(|controls| raylib.is_key_down(controls.boost)));
+
+        client.poll();
+
+        if let Some(server_game) = client.server_game() {
+            snapshot_watcher.update(server_game, &mut events);
+            let mut effects = GameEffects { gulp_sound: gulp_sound, particle_system: &mut particle_system, particle_effects: particle_effects, palette: settings.palette };
+            events.dispatch(server_game, &mut [&mut effects, &mut camera]);
+        }
+
+        let mut draw_context = raylib.begin_texture_mode(render_thread, &mut display.target);
+
+        particle_system.step(draw_context.get_frame_time());
+        particle_effects.update(draw_context.get_frame_time());
+        // The server's game can't be held still, so hit-stop does nothing online
+        camera.update(draw_context.get_frame_time());
+
+        let game = match client.predicted_game() {
+            Some(game) => game,
+            None => {
+                draw_title_screen(&mut draw_context, texture_map, hud, settings, false);
+                let status = if client.server_full {
+                    "That server is full".to_string()
+                } else {
+                    format!("Connecting to {}...", server_address)
+                };
+                draw_title_status(&mut draw_context, hud, &status);
+                continue;
+            }
+        };
+
+        head_trails.update(&mut particle_system, particle_effects, &game, settings.palette);
+        draw_world(&mut draw_context, texture_map, &game, &game, &particle_system, &camera, None, settings);
+
+        if client.waiting_for_players {
+            let center = hud::screen_center();
+            hud.text_at(&mut draw_context, "Waiting for an opponent...", center, Anchor::CENTER, TextStyle::new(20.0, Color::BLACK).shadowed());
+        } else if game.round_over {
+            draw_round_over(&mut draw_context, hud, &game, false);
+        } else {
+            hud.draw_game_hud(&mut draw_context, &game, None);
+        }
+
+        if let Some(index) = client.player_index {
+            hud.text(
+                &mut draw_context, &format!("You are player {}", index + 1),
+                Anchor::BOTTOMLEFT, Vector2::zero(), TextStyle::new(20.0, Color::BLACK)
+            );
+        }
+    }
+
+    client.disconnect();
+}
+
+fn run_bot_match(
+    mut raylib:         &mut RaylibHandle,
+    render_thread:      &RaylibThread,
+    display:            &mut Display,
+    texture_map:        &TextureMap,
+    hud:                &Hud,
+    settings:           &Settings,
+    gulp_sound:         &Sound,
+    particle_effects:   &mut ParticleEffects,
+    background_music:   &mut Music,
+    commands:           &[String],
+    play_area:          game::Bounds) {
+
+    let mut bot_match = match bot::BotMatch::new(commands, play_area, rand::random()) {
+        Ok(bot_match) => bot_match,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
     };
 
-    let mut score: u16 = 0;
-
-    const TIME_TO_MOVE: f32             = 0.15;
-    let mut time_since_last_move: f32   = 0.0;
-    let mut game_over: bool = false;
-    let mut enlarge_snake: bool = false;
-
-    let mut particle_system = particles::ParticleSystem::create_radial(
-        0.5, 
-        vec![Color::BLACK, Color::BLUE, Color::WHITE],
-        25, 
-        Vector2 {
-            x: SCREEN_WIDTH  as f32 / 2.0,
-            y: SCREEN_HEIGHT as f32 / 2.0
-        }, 
-        40.0,
-        0.25
-    );
+    let mut particle_system = ParticleSystem::new(PARTICLE_CAPACITY);
+
+    let mut events = EventBus::new();
+    let mut head_trails = HeadTrails::new();
+    let mut camera = GameCamera::new(settings.motion, settings.palette, Vector2 {
+        x: SCREEN_WIDTH  as f32 / 2.0,
+        y: SCREEN_HEIGHT as f32 / 2.0
+    });
 
     while !raylib.window_should_close() {
 
-        if game_over && raylib.is_key_released(KeyboardKey::KEY_ENTER) {
-            game_over   = false;
-            snake       = create_starting_snake();
+        display.present(raylib, render_thread);
+
+        background_music.update_stream();
+        if !background_music.is_stream_playing() {
+            background_music.play_stream();
+        }
+
+        if bot_match.game.round_over && raylib.is_key_released(KeyboardKey::KEY_ENTER) {
+            if bot_match.game.is_match_over() {
+                break;
+            }
+            bot_match.game.start_round();
+        }
+
+        let mut draw_context = raylib.begin_texture_mode(render_thread, &mut display.target);
+
+        particle_system.step(draw_context.get_frame_time());
+        particle_effects.update(draw_context.get_frame_time());
+
+        let game_time = camera.update(draw_context.get_frame_time());
+        events.emit_all(bot_match.update(game_time).events);
+        let mut effects = GameEffects { gulp_sound: gulp_sound, particle_system: &mut particle_system, particle_effects: particle_effects, palette: settings.palette };
+        events.dispatch(&bot_match.game, &mut [&mut effects, &mut camera]);
+        head_trails.update(&mut particle_system, particle_effects, &bot_match.game, settings.palette);
+
+        let game = &bot_match.game;
+        draw_world(&mut draw_context, texture_map, game, game, &particle_system, &camera, None, settings);
+
+        if game.round_over {
+            // ENTER ends a solo bot match, so it gets its own prompt
+            draw_round_over(&mut draw_context, hud, game, game.mode != GameMode::SOLO);
+            if game.mode == GameMode::SOLO {
+                let center = hud::screen_center();
+                hud.text_at(
+                    &mut draw_context, "ENTER - quit", Vector2 { x: center.x, y: center.y + 68.0 * hud.scale() },
+                    Anchor::CENTER, TextStyle::new(20.0, Color::BLACK)
+                );
+            }
+        } else {
+            hud.draw_game_hud(&mut draw_context, game, None);
+        }
+    }
+
+    bot_match.finish();
+}
+
+fn run_spectator(
+    mut raylib:         &mut RaylibHandle,
+    render_thread:      &RaylibThread,
+    display:            &mut Display,
+    texture_map:        &TextureMap,
+    hud:                &Hud,
+    settings:           &Settings,
+    gulp_sound:         &Sound,
+    particle_effects:   &mut ParticleEffects,
+    background_music:   &mut Music,
+    address:            &str) {
+
+    let mut spectator = match net::Spectator::connect(address) {
+        Ok(spectator) => spectator,
+        Err(error) => {
+            println!("{}", error);
+            return;
         }
+    };
+
+    let mut particle_system = ParticleSystem::new(PARTICLE_CAPACITY);
+
+    let mut snapshot_watcher = SnapshotWatcher::new();
+    let mut events = EventBus::new();
+    let mut head_trails = HeadTrails::new();
+    let mut camera = GameCamera::new(settings.motion, settings.palette, Vector2 {
+        x: SCREEN_WIDTH  as f32 / 2.0,
+        y: SCREEN_HEIGHT as f32 / 2.0
+    });
+
+    while !raylib.window_should_close() {
+
+        display.present(raylib, render_thread);
 
         background_music.update_stream();
         if !background_music.is_stream_playing() {
             background_music.play_stream();
         }
 
-        if raylib.is_key_released(KeyboardKey::KEY_W)        && snake.head_direction != Direction::DOWN  {
-            snake.head_direction = Direction::UP;
-        } else if raylib.is_key_released(KeyboardKey::KEY_D) && snake.head_direction != Direction::LEFT  {
-            snake.head_direction = Direction::RIGHT;
-        } else if raylib.is_key_released(KeyboardKey::KEY_S) && snake.head_direction != Direction::UP    {
-            snake.head_direction = Direction::DOWN;
-        } else if raylib.is_key_released(KeyboardKey::KEY_A) && snake.head_direction != Direction::RIGHT {
-            snake.head_direction = Direction::LEFT;
+        spectator.poll();
+
+        if let Some(game) = spectator.game() {
+            snapshot_watcher.update(game, &mut events);
+            let mut effects = GameEffects { gulp_sound: gulp_sound, particle_system: &mut particle_system, particle_effects: particle_effects, palette: settings.palette };
+            events.dispatch(game, &mut [&mut effects, &mut camera]);
+        }
+
+        let mut draw_context = raylib.begin_texture_mode(render_thread, &mut display.target);
+
+        particle_system.step(draw_context.get_frame_time());
+        particle_effects.update(draw_context.get_frame_time());
+        camera.update(draw_context.get_frame_time());
+
+        let game = match spectator.game() {
+            Some(game) => game,
+            None => {
+                draw_title_screen(&mut draw_context, texture_map, hud, settings, false);
+                draw_title_status(&mut draw_context, hud, &format!("Waiting for a game on {}...", address));
+                continue;
+            }
+        };
+
+        head_trails.update(&mut particle_system, particle_effects, game, settings.palette);
+        draw_world(&mut draw_context, texture_map, game, game, &particle_system, &camera, None, settings);
+
+        if spectator.waiting_for_players {
+            let center = hud::screen_center();
+            hud.text_at(&mut draw_context, "Waiting for players...", center, Anchor::CENTER, TextStyle::new(20.0, Color::BLACK).shadowed());
+        } else if game.round_over {
+            draw_round_over(&mut draw_context, hud, game, false);
+        } else {
+            hud.draw_game_hud(&mut draw_context, game, None);
         }
 
-        let speed_up: bool = raylib.is_key_down(KeyboardKey::KEY_SPACE);
+        hud.text(&mut draw_context, "SPECTATING", Anchor::BOTTOMLEFT, Vector2::zero(), TextStyle::new(20.0, Color::MAROON));
+    }
+}
 
-        let apple_overlaps_with_snake_head = Rectangle {
-                x: apple.x, 
-                y: apple.y, 
-                width:  20.0, 
-                height: 20.0
-        }.check_collision_point_rec(
-            Vector2 { x: snake.head().x, y: snake.head().y }
-        );
+// @note: Plays a recording back at `speed` times the game's own pace. Rounds
+//  carry on by themselves after the round over screen has been up a moment,
+//  ENTER skips ahead to the next one and quits at the end.
+fn run_replay(
+    mut raylib:         &mut RaylibHandle,
+    render_thread:      &RaylibThread,
+    display:            &mut Display,
+    texture_map:        &TextureMap,
+    hud:                &Hud,
+    settings:           &Settings,
+    gulp_sound:         &Sound,
+    particle_effects:   &mut ParticleEffects,
+    background_music:   &mut Music,
+    recording:          &Recording,
+    speed:              f32) {
+
+    const ROUND_PAUSE: f32 = 2.0;
+
+    let mut game = recording.start();
+    let mut replayer = Replayer::new();
+    let mut round_over_time = 0.0;
+
+    let mut particle_system = ParticleSystem::new(PARTICLE_CAPACITY);
+
+    let mut events = EventBus::new();
+    let mut head_trails = HeadTrails::new();
+    let mut camera = GameCamera::new(settings.motion, settings.palette, Vector2 {
+        x: SCREEN_WIDTH  as f32 / 2.0,
+        y: SCREEN_HEIGHT as f32 / 2.0
+    });
 
-        if apple_overlaps_with_snake_head {
-            enlarge_snake = true;
-            gulp_sound.play();
-            particle_system.reset(0.5, Vector2 { x: apple.x + 10.0, y: apple.y + 10.0});
-            place_apple(&mut apple, &snake, &raylib, play_area);
-            score += 1;
+    while !raylib.window_should_close() {
+
+        display.present(raylib, render_thread);
+
+        background_music.update_stream();
+        if !background_music.is_stream_playing() {
+            background_music.play_stream();
         }
 
-        if apple.time_left <= 0.0 {
-            place_apple(&mut apple, &snake, &raylib, play_area);
+        if game.round_over {
+            round_over_time += raylib.get_frame_time();
+            if round_over_time >= ROUND_PAUSE || raylib.is_key_released(KeyboardKey::KEY_ENTER) {
+                let is_last_round = !replayer.next_round(&mut game, recording);
+                if is_last_round && raylib.is_key_released(KeyboardKey::KEY_ENTER) {
+                    break;
+                }
+                round_over_time = 0.0;
+            }
         }
 
-        let mut draw_context = raylib.begin_drawing(&render_thread);
+        let mut draw_context = raylib.begin_texture_mode(render_thread, &mut display.target);
 
         particle_system.step(draw_context.get_frame_time());
-        if !game_over {
-            apple.time_left      -= draw_context.get_frame_time();
-            time_since_last_move += draw_context.get_frame_time();
-            if speed_up {
-                time_since_last_move += draw_context.get_frame_time();
+        particle_effects.update(draw_context.get_frame_time());
+
+        // A recording that stops before the round is decided, e.g. one that
+        //  was cut short, just holds on its last tick
+        let game_time = camera.update(draw_context.get_frame_time()) * speed;
+        if replayer.has_next(recording) {
+            events.emit_all(game.update(game_time, |game| { replayer.apply_next(game, recording); }).events);
+        }
+        let mut effects = GameEffects { gulp_sound: gulp_sound, particle_system: &mut particle_system, particle_effects: particle_effects, palette: settings.palette };
+        events.dispatch(&game, &mut [&mut effects, &mut camera]);
+        head_trails.update(&mut particle_system, particle_effects, &game, settings.palette);
+
+        draw_world(&mut draw_context, texture_map, &game, &game, &particle_system, &camera, None, settings);
+
+        if game.round_over {
+            draw_round_over(&mut draw_context, hud, &game, false);
+        } else {
+            hud.draw_game_hud(&mut draw_context, &game, None);
+        }
+
+        hud.text(
+            &mut draw_context, &format!("REPLAY  seed {}  x{}", recording.seed, speed),
+            Anchor::BOTTOMLEFT, Vector2::zero(), TextStyle::new(20.0, Color::MAROON)
+        );
+    }
+}
+
+// @note: Network games only get snapshots, not the steps in between, so this
+//  works out who ate and who died since the last one from the scores and
+//  deaths, and turns that into the same events a local game would emit.
+struct SnapshotWatcher {
+    last_scores: Vec<u16>,
+    last_deaths: Vec<Option<Death>>,
+    last_apple:  Option<Apple>
+}
+
+impl SnapshotWatcher {
+    fn new() -> Self {
+        SnapshotWatcher { last_scores: Vec::new(), last_deaths: Vec::new(), last_apple: None }
+    }
+
+    fn update(self: &mut Self, game: &Game, events: &mut EventBus) {
+        let scores: Vec<u16> = game.players.iter().map(|player| player.score).collect();
+        let deaths: Vec<Option<Death>> = game.players.iter().map(|player| player.death).collect();
+
+        if scores.len() == self.last_scores.len() {
+            for (index, (score, last_score)) in scores.iter().zip(&self.last_scores).enumerate() {
+                if let (true, Some(apple)) = (score > last_score, self.last_apple) {
+                    events.emit(GameEvent::EATEN(index, apple));
+                }
             }
-            if time_since_last_move >= TIME_TO_MOVE {
-                propogate_snake_movement(&mut snake, enlarge_snake);
-                enlarge_snake = false;
-                time_since_last_move = 0.0;
+            for (index, (death, last_death)) in deaths.iter().zip(&self.last_deaths).enumerate() {
+                if let (Some(death), None) = (death, last_death) {
+                    events.emit(GameEvent::DIED(index, *death));
+                }
             }
         }
 
-        draw_game(&mut draw_context, &texture_map, &snake, &apple, &particle_system);        
+        self.last_scores = scores;
+        self.last_deaths = deaths;
+        self.last_apple  = Some(game.apple);
+    }
+}
+
+// Sounds and particles, everything that only exists to make events feel good
+struct GameEffects<'a, 'aud> {
+    gulp_sound:         &'a Sound<'aud>,
+    particle_system:    &'a mut ParticleSystem,
+    particle_effects:   &'a mut ParticleEffects,
+    palette:            Palette
+}
 
-        if game_over {
-            draw_context.draw_text("GAME OVER", 640 / 2 - 50, 480 / 2, 20, Color::BLACK);
+impl<'a, 'aud> Subscriber for GameEffects<'a, 'aud> {
+    fn on_event(self: &mut Self, _game: &Game, event: &GameEvent) {
+        match *event {
+            GameEvent::EATEN(_, apple) => {
+                self.gulp_sound.play();
+                let position = Vector2 { x: apple.x + CELL_SIZE / 2.0, y: apple.y + CELL_SIZE / 2.0 };
+                self.particle_effects.burst(self.particle_system, "apple_eaten", position, palette::colors(self.palette).sparkle);
+            },
+            GameEvent::EXPIRED(apple) => {
+                let position = Vector2 { x: apple.x, y: apple.y + CELL_SIZE };
+                self.particle_effects.burst(self.particle_system, "apple_expired", position, Color::WHITE);
+            },
+            GameEvent::DIED(index, death) => {
+                let position = Vector2 { x: death.x + CELL_SIZE / 2.0, y: death.y + CELL_SIZE / 2.0 };
+                let tint = palette::player_tint(self.palette, index);
+                self.particle_effects.burst(self.particle_system, "death", position, tint);
+                self.particle_effects.burst(self.particle_system, "death_sparks", position, tint);
+            },
+            _ => {},
         }
+    }
+}
+
+// A trail behind every head that is boosting, one emitter per player that is
+//  kept on the head and switched on and off. They are started again when the
+//  effects get reloaded so edits to the trail show up too.
+struct HeadTrails {
+    emitters:   Vec<Option<EmitterId>>,
+    generation: u32
+}
+
+impl HeadTrails {
+    fn new() -> Self {
+        HeadTrails { emitters: Vec::new(), generation: 0 }
+    }
 
-        if !game_over {
-            let is_snake_inside_bounds = play_area.check_collision_point_rec(Vector2 { x: snake.head().x, y: snake.head().y });
-            if !is_snake_inside_bounds {
-                game_over = true; 
+    fn update(self: &mut Self, particle_system: &mut ParticleSystem, particle_effects: &mut ParticleEffects, game: &Game, palette: Palette) {
+        if self.generation != particle_effects.generation {
+            for emitter in self.emitters.drain(..).flatten() {
+                particle_system.release(emitter);
             }
+            self.generation = particle_effects.generation;
+        }
 
-            if does_snake_self_intersect(&snake) {
-                game_over = true;
+        for (index, player) in game.players.iter().enumerate() {
+            let head = player.snake.head();
+            let position = Vector2 { x: head.x + CELL_SIZE / 2.0, y: head.y + CELL_SIZE / 2.0 };
+            if index >= self.emitters.len() {
+                let tint = palette::player_tint(palette, index);
+                self.emitters.push(particle_effects.start(particle_system, "boost_trail", position, tint));
             }
 
-            draw_context.draw_text(&("Score: ".to_string() + &score.to_string()), 12, 12, 20, Color::BLACK);
+            let Some(emitter) = self.emitters[index] else { continue };
+            particle_system.move_to(emitter, position);
+            particle_system.set_emitting(emitter, player.alive && player.speed_up && !game.round_over);
         }
     }
 }
 
+
+// Which game the title screen's number keys start, sitting there long
+//  enough starts the demo
+fn read_title_choice(raylib: &RaylibHandle, idle_time: f32) -> Option<cli::PlayMode> {
+    if raylib.is_key_released(KeyboardKey::KEY_ONE) || raylib.is_key_released(KeyboardKey::KEY_ENTER) {
+        Some(cli::PlayMode::SOLO)
+    } else if raylib.is_key_released(KeyboardKey::KEY_TWO) {
+        Some(cli::PlayMode::VERSUS)
+    } else if raylib.is_key_released(KeyboardKey::KEY_THREE) {
+        Some(cli::PlayMode::COMPUTER)
+    } else if raylib.is_key_released(KeyboardKey::KEY_FOUR) || idle_time >= ATTRACT_MODE_DELAY {
+        Some(cli::PlayMode::DEMO)
+    } else if raylib.is_key_released(KeyboardKey::KEY_FIVE) {
+        Some(cli::PlayMode::PRACTICE)
+    } else {
+        None
+    }
+}
+
+// On the level when one was given, otherwise on the board size from the settings
+fn new_local_game(mode: GameMode, settings: &Settings, level: Option<&Level>, seed: u64) -> Game {
+    let mut game = match level {
+        Some(level) => {
+            let area = level.play_area(game::play_area_for_screen(SCREEN_WIDTH, SCREEN_HEIGHT));
+            Game::with_walls(mode, area, level.walls_in(area), seed)
+        },
+        None => Game::new(mode, settings.play_area(SCREEN_WIDTH, SCREEN_HEIGHT), seed),
+    };
+    game.set_food(settings.food);
+    game
+}
+
+fn save_recording(recording: &Recording, path: &std::path::Path) {
+    match snake::storage::write_atomically(path, &recording.to_text()) {
+        Ok(()) => println!("Saved the replay to {}, watch it with `snake replay {}`", path.display(), path.display()),
+        Err(error) => println!("WARN: Failed to save the replay to {}: {}", path.display(), error),
+    }
+}
+
+fn read_direction_input(raylib: &RaylibHandle, controls: &PlayerControls) -> Option<Direction> {
+    if raylib.is_key_released(controls.up) {
+        Some(Direction::UP)
+    } else if raylib.is_key_released(controls.right) {
+        Some(Direction::RIGHT)
+    } else if raylib.is_key_released(controls.down) {
+        Some(Direction::DOWN)
+    } else if raylib.is_key_released(controls.left) {
+        Some(Direction::LEFT)
+    } else {
+        None
+    }
+}
+
+fn draw_title_screen(
+    draw_context:       &mut Frame, 
+    texture_map:        &TextureMap,
+    hud:                &Hud,
+    settings:           &Settings,
+    full_menu:          bool) {
+
+    board::draw_backdrop(draw_context, &texture_map.background_texture, screen_rectangle());
+
+    let center = hud::screen_center();
+    let line = |offset: f32| Vector2 { x: center.x, y: center.y + offset * hud.scale() };
+
+    hud.text_at(draw_context, "SNAKE!", line(-130.0), Anchor::CENTER, TextStyle::new(40.0, Color::BLACK));
+
+    let versus = format!(
+        "2 - Versus ({} vs {})",
+        settings::steering_keys_name(&settings.controls_for(0)), settings::steering_keys_name(&settings.controls_for(1))
+    );
+    let mut menu = vec!["1 - Solo", versus.as_str()];
+    // The network screens reuse this as a backdrop, there's no computer to pick there
+    if full_menu {
+        menu.extend(["3 - Versus the computer", "4 - Watch the computer", "5 - Practice", "6 - Stats", "7 - Settings"]);
+        hud.text(
+            draw_context, &format!("S - Computer: {}", ai::strategy_name(settings.computer_strategy)),
+            Anchor::BOTTOMLEFT, Vector2::zero(), TextStyle::new(20.0, Color::DARKGRAY)
+        );
+    }
+
+    for (index, item) in menu.iter().enumerate() {
+        hud.text_at(draw_context, item, line(-70.0 + index as f32 * 30.0), Anchor::CENTER, TextStyle::new(20.0, Color::BLACK));
+    }
+}
+
+// What the network screens say under the title while there's no game to show
+fn draw_title_status(draw_context: &mut Frame, hud: &Hud, status: &str) {
+    let center = hud::screen_center();
+    hud.text_at(draw_context, status, Vector2 { x: center.x, y: center.y + 80.0 * hud.scale() }, Anchor::CENTER, TextStyle::new(20.0, Color::BLACK));
+}
+
+fn screen_rectangle() -> Rectangle {
+    Rectangle { x: 0.0, y: 0.0, width: SCREEN_WIDTH as f32, height: SCREEN_HEIGHT as f32 }
+}
+
+fn format_duration(seconds: f32) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+// A row's label and how to read it from one column's stats
+type StatsRow = (&'static str, fn(&Stats) -> String);
+
+fn draw_stats_screen(
+    draw_context: &mut Frame, 
+    texture_map:  &TextureMap,
+    hud:          &Hud,
+    stats:        &StatsTracker,
+    achievements: &Achievements) {
+
+    board::draw_backdrop(draw_context, &texture_map.background_texture, screen_rectangle());
+
+    // Sized here rather than by the HUD, so it can stop at STATS_MAX_SCALE
+    let scale = hud.scale().min(STATS_MAX_SCALE);
+    let style = |size: f32, color: Color| TextStyle::new(size * scale, color).fixed();
+
+    hud.text(draw_context, "STATS", Anchor::TOP, Vector2 { x: 0.0, y: 28.0 * scale }, style(40.0, Color::BLACK));
+
+    let columns: [(&str, &Stats); 3] = [("Last game", &stats.current), ("Session", &stats.session), ("Lifetime", &stats.lifetime)];
+    let rows: [StatsRow; 12] = [
+        ("Games played",    |stats| stats.games_played.to_string()),
+        ("Apples",          |stats| stats.apples_of(FoodType::APPLE).to_string()),
+        ("Golden apples",   |stats| stats.apples_of(FoodType::GOLDEN).to_string()),
+        ("Average score",   |stats| format!("{:.1}", stats.average_score())),
+        ("Best score",      |stats| stats.best_score.to_string()),
+        ("Longest snake",   |stats| stats.longest_snake.to_string()),
+        ("Time played",     |stats| format_duration(stats.time_played)),
+        ("Turns",           |stats| stats.turns.to_string()),
+        ("Boost time",      |stats| format_duration(stats.boost_time)),
+        ("Wall deaths",     |stats| stats.wall_deaths.to_string()),
+        ("Self deaths",     |stats| stats.self_deaths.to_string()),
+        ("Snake deaths",    |stats| stats.snake_deaths.to_string()),
+    ];
+
+    // The columns are right aligned against the right side of the screen,
+    //  `column_offset` is how far in from it each one ends
+    let column_offset = |column: usize| (columns.len() - 1 - column) as f32 * 130.0 * scale;
+    for (column, (title, _)) in columns.iter().enumerate() {
+        hud.text(
+            draw_context, title, Anchor::TOPRIGHT, Vector2 { x: column_offset(column), y: 88.0 * scale },
+            style(20.0, Color::DARKGRAY)
+        );
+    }
+    for (row, (label, value)) in rows.iter().enumerate() {
+        let y = (118.0 + row as f32 * 24.0) * scale;
+        hud.text(draw_context, label, Anchor::TOPLEFT, Vector2 { x: 28.0, y: y }, style(20.0, Color::BLACK));
+        for (column, (_, column_stats)) in columns.iter().enumerate() {
+            hud.text(
+                draw_context, &value(column_stats), Anchor::TOPRIGHT, Vector2 { x: column_offset(column), y: y },
+                style(20.0, Color::BLACK)
+            );
+        }
+    }
+
+    hud.text(
+        draw_context, &format!("Achievements: {}/{}", achievements.unlocked_count(), achievements::ACHIEVEMENTS.len()),
+        Anchor::BOTTOMRIGHT, Vector2::zero(), style(20.0, Color::DARKGRAY)
+    );
+    hud.text(draw_context, "ENTER - back", Anchor::BOTTOMLEFT, Vector2::zero(), style(20.0, Color::DARKGRAY));
+}
+
+// Slides down from the top, sits there for a bit and slides back up
+fn draw_achievement_toast(
+    draw_context: &mut Frame, 
+    hud:          &Hud,
+    achievement:  &Achievement,
+    time_shown:   f32) {
+
+    let slide_time = 0.3;
+    let visible = (time_shown / slide_time).min((TOAST_DURATION - time_shown) / slide_time).clamp(0.0, 1.0);
+
+    let title = format!("Achievement: {}", achievement.name);
+    let scale = hud.scale();
+    // Wide enough for whichever line is longer at the current text size
+    let text_width = hud.measure(&title, 20.0 * scale).x.max(hud.measure(achievement.description, 10.0 * scale).x);
+    let width = text_width.max(300.0 * scale) + 20.0 * scale;
+    let height = 50.0 * scale;
+    let toast = Rectangle {
+        x:      SCREEN_WIDTH as f32 / 2.0 - width / 2.0,
+        y:      -height + (height + 8.0) * visible,
+        width:  width,
+        height: height
+    };
+
+    draw_context.draw_rectangle_rec(toast, Color::DARKGRAY.fade(0.9));
+    draw_context.draw_rectangle_lines_ex(toast, 1.0, Color::GOLD);
+    hud.text_at(
+        draw_context, &title, Vector2 { x: toast.x + 10.0 * scale, y: toast.y + 6.0 * scale },
+        Anchor::TOPLEFT, TextStyle::new(20.0, Color::GOLD)
+    );
+    hud.text_at(
+        draw_context, achievement.description, Vector2 { x: toast.x + 10.0 * scale, y: toast.y + 30.0 * scale },
+        Anchor::TOPLEFT, TextStyle::new(10.0, Color::WHITE)
+    );
+}
+
+fn draw_round_over(
+    draw_context: &mut Frame, 
+    hud:          &Hud,
+    game:         &Game,
+    show_prompt:  bool) {
+
+    let center = hud::screen_center();
+    let line = |offset: f32| Vector2 { x: center.x, y: center.y + offset * hud.scale() };
+    let colors = palette::colors(hud.palette());
+
+    match game.mode {
+        GameMode::SOLO => {
+            hud.text_at(draw_context, "GAME OVER", line(0.0), Anchor::CENTER, TextStyle::new(40.0, Color::BLACK).shadowed());
+            if let Some(death) = &game.players[0].death {
+                hud.text_at(draw_context, &describe_death(death), line(36.0), Anchor::CENTER, TextStyle::new(20.0, colors.danger));
+            }
+
+            if show_prompt {
+                hud.text_at(draw_context, "ENTER - play again", line(68.0), Anchor::CENTER, TextStyle::new(20.0, Color::BLACK));
+            }
+        },
+        GameMode::VERSUS => {
+            let headline = if let Some(winner) = game.match_winner() {
+                format!("PLAYER {} WINS THE MATCH!", winner + 1)
+            } else if let Some(winner) = game.round_winner() {
+                format!("Player {} takes round {}", winner + 1, game.round)
+            } else {
+                format!("Round {} is a draw", game.round)
+            };
+            hud.text_at(draw_context, &headline, line(-40.0), Anchor::CENTER, TextStyle::new(20.0, Color::BLACK).shadowed());
+
+            for (index, player) in game.players.iter().enumerate() {
+                let mut text = format!("Player {}: {} rounds, {} points", index + 1, player.round_wins, player.score);
+                if let Some(death) = &player.death {
+                    text += &format!(" - {}", map_death_cause_to_short_text(death.cause));
+                }
+                hud.text_at(
+                    draw_context, &text, line(index as f32 * 24.0), Anchor::CENTER,
+                    TextStyle::new(20.0, colors.player_text[index.min(1)])
+                );
+            }
+
+            if show_prompt {
+                let prompt = if game.is_match_over() { "ENTER - back to title" } else { "ENTER - next round" };
+                hud.text_at(draw_context, prompt, line(60.0), Anchor::CENTER, TextStyle::new(20.0, Color::BLACK));
+            }
+        },
+    }
+}
+
+fn map_wall_side_to_name(side: WallSide) -> &'static str {
+    match side {
+        WallSide::TOP    => "top",
+        WallSide::LEFT   => "left",
+        WallSide::BOTTOM => "bottom",
+        WallSide::RIGHT  => "right",
+    }
+}
+
+fn map_death_cause_to_short_text(cause: DeathCause) -> String {
+    match cause {
+        DeathCause::WALL(side)          => format!("hit the {} wall", map_wall_side_to_name(side)),
+        DeathCause::OBSTACLE            => "hit a wall".to_string(),
+        DeathCause::SELF(_)             => "ran into itself".to_string(),
+        DeathCause::SNAKE(other, 0)     => format!("head on with P{}", other + 1),
+        DeathCause::SNAKE(other, _)     => format!("hit P{}", other + 1),
+    }
+}
+
+// The full story for the post-mortem, e.g. "Hit segment 4 of itself after 12.3s, boosting"
+fn describe_death(death: &Death) -> String {
+    let what = match death.cause {
+        DeathCause::WALL(side)              => format!("Hit the {} wall", map_wall_side_to_name(side)),
+        DeathCause::OBSTACLE                => "Hit a wall".to_string(),
+        DeathCause::SELF(segment)           => format!("Bit segment {} of {}", segment, death.length),
+        DeathCause::SNAKE(other, 0)         => format!("Head on with player {}", other + 1),
+        DeathCause::SNAKE(other, segment)   => format!("Hit segment {} of player {}", segment, other + 1),
+    };
+    let seconds = death.tick as f32 * TICK_DURATION;
+    let speed = if death.boosting { ", boosting" } else { "" };
+    format!("{} after {:.1}s{}", what, seconds, speed)
+}
+
+// Pulses a box around the cell every dead snake's head ended up in
+fn draw_fatal_cells(
+    draw_context: &mut impl RaylibDraw, 
+    game:         &Game,
+    time:         f64,
+    steady:       bool,
+    color:        Color) {
+
+    let pulse = if steady { 0.5 } else { ((time * 6.0).sin() * 0.5 + 0.5) as f32 };
+    for player in &game.players {
+        let Some(death) = &player.death else { continue };
+        let cell = Rectangle { x: death.x, y: death.y, width: CELL_SIZE, height: CELL_SIZE };
+        draw_context.draw_rectangle_rec(cell, color.fade(0.2 + 0.3 * pulse));
+        draw_context.draw_rectangle_lines_ex(cell, 2.0, color);
+    }
+}
+
+// While scrubbing in practice: where in the history we are and how to get out
+fn draw_scrubber(
+    draw_context: &mut Frame, 
+    hud:          &Hud,
+    frame:        usize,
+    frame_count:  usize) {
+
+    let progress = if frame_count > 1 { frame as f32 / (frame_count - 1) as f32 } else { 1.0 };
+    let seconds_back = frame_count.saturating_sub(frame + 1) as f32 * TICK_DURATION;
+
+    let help = hud.text(
+        draw_context, &format!("-{:.1}s   R/F scrub, steer or ENTER to play on", seconds_back),
+        Anchor::BOTTOMLEFT, Vector2 { x: 8.0, y: 0.0 }, TextStyle::new(20.0, Color::MAROON)
+    );
+
+    // The bar sits just above the help text, however big that is
+    let y = help.y - 12.0;
+    draw_context.draw_rectangle(20, y as i32, 600, 8, Color::LIGHTGRAY);
+    draw_context.draw_rectangle(20, y as i32, (600.0 * progress) as i32, 8, Color::MAROON);
+    draw_context.draw_circle(20 + (600.0 * progress) as i32, y as i32 + 4, 7.0, Color::MAROON);
+}
+
+fn draw_rewind_overlay(
+    draw_context: &mut Frame, 
+    hud:          &Hud,
+    frame:        usize,
+    frame_count:  usize) {
+
+    let label = hud.text(draw_context, "REWIND", Anchor::TOPLEFT, Vector2::zero(), TextStyle::new(20.0, Color::MAROON));
+
+    let progress = if frame_count > 1 { frame as f32 / (frame_count - 1) as f32 } else { 1.0 };
+    let x = (label.x + label.width + 16.0) as i32;
+    let y = (label.y + label.height / 2.0) as i32 - 4;
+    draw_context.draw_rectangle(x, y, 200, 8, Color::LIGHTGRAY);
+    draw_context.draw_rectangle(x, y, (200.0 * progress) as i32, 8, Color::MAROON);
+}
+
 // @note: This assumes that all images are drawn facing up
 fn map_direction_to_rotation(direction: Direction) -> f32 {
     match direction {
@@ -238,22 +1564,22 @@ fn map_difference_in_direction_to_rotation_for_snake_corner(
     }
 
     match segment_before {
-        Direction::UP    => return match current_direction {
+        Direction::UP    => match current_direction {
             Direction::LEFT  => 270.0,
             Direction::RIGHT => 180.0,
             _ => 0.0
         },
-        Direction::LEFT  => return match current_direction {
+        Direction::LEFT  => match current_direction {
             Direction::UP    => 90.0,
             Direction::DOWN  => 180.0,
             _ => 0.0
         },
-        Direction::DOWN  => return match current_direction {
+        Direction::DOWN  => match current_direction {
             Direction::LEFT  =>   0.0,
             Direction::RIGHT =>  90.0,
             _ => 0.0
         },
-        Direction::RIGHT => return match current_direction {
+        Direction::RIGHT => match current_direction {
             Direction::UP    => 0.0,
             Direction::DOWN  => 270.0,
             _ => 0.0
@@ -262,45 +1588,159 @@ fn map_difference_in_direction_to_rotation_for_snake_corner(
 }
 
 fn draw_game(
-    draw_context:           &mut RaylibDrawHandle, 
+    draw_context:           &mut impl RaylibDraw, 
     texture_map:            &TextureMap,
-    snake:                  &Snake,
-    apple:                  &Apple,
-    particle_system:        &particles::ParticleSystem) {
-
-    draw_context.clear_background(Color::WHITE);
+    game:                   &Game,
+    particle_system:        &ParticleSystem,
+    camera:                 &GameCamera,
+    death_sequence:         Option<&DeathSequence>,
+    settings:               &Settings) {
+
+    board::draw_board(draw_context, &texture_map.background_texture, game, screen_rectangle(), settings);
+
+    particle_system.draw(draw_context, &texture_map.particle_atlas_texture);
+
+    // Every outline goes under every snake, so a snake lying next to another
+    //  one doesn't get the other's outline drawn over it
+    let passes: &[bool] = if settings.high_contrast { &[true, false] } else { &[false] };
+    for outlined in passes {
+        for (index, player) in game.players.iter().enumerate() {
+            let mut tint = palette::player_tint(settings.palette, index);
+            if !player.alive && game.mode == GameMode::VERSUS {
+                tint = tint.fade(0.4);
+            }
 
-    draw_context.draw_texture(&texture_map.background_texture, 0, 0, Color::WHITE);
+            let (tint, outline) = if *outlined { (OUTLINE_COLOR.fade(tint.a as f32 / 255.0), OUTLINE_WIDTH) } else { (tint, 0.0) };
+            draw_snake(
+                draw_context,
+                texture_map,
+                &player.snake,
+                tint,
+                camera.head_scale(index),
+                death_sequence.map_or(0.0, |sequence| sequence.crumbled(index)),
+                outline
+            );
+        }
+    }
 
-    particle_system.draw(draw_context, &texture_map.apple_texture);
+    draw_apple(draw_context, &texture_map.apple_texture, &game.apple, settings);
+}
 
-    draw_snake(
-        draw_context,
-        &texture_map,
-        &snake,
-    );
+// The board and everything on it, seen through the camera. `fatal_cells_of`
+//  is the game whose deaths get marked, usually the one being drawn but a
+//  rewind marks where the round is going to end.
+fn draw_world(
+    draw_context:           &mut Frame, 
+    texture_map:            &TextureMap,
+    game:                   &Game,
+    fatal_cells_of:         &Game,
+    particle_system:        &ParticleSystem,
+    camera:                 &GameCamera,
+    death_sequence:         Option<&DeathSequence>,
+    settings:               &Settings) {
+
+    let time = draw_context.get_time();
+    camera.draw(draw_context, |world| {
+        draw_game(world, texture_map, game, particle_system, camera, death_sequence, settings);
+        draw_fatal_cells(world, fatal_cells_of, time, settings.reduce_flashing, palette::colors(settings.palette).danger);
+    });
+}
 
-    draw_apple(draw_context, &texture_map.apple_texture, apple);
+// @note: With food shapes on, food that isn't a plain apple is drawn as a
+//  polygon with this many sides instead of the round apple sprite, so it
+//  can't be told apart by its colour alone
+fn map_food_type_to_sides(food_type: FoodType) -> Option<i32> {
+    match food_type {
+        FoodType::APPLE  => None,
+        FoodType::GOLDEN => Some(4),
+    }
 }
 
 fn draw_apple(
-    draw_context:  &mut RaylibDrawHandle, 
+    draw_context:  &mut impl RaylibDraw, 
     apple_texture: &Texture2D,
-    apple:         &Apple) {
+    apple:         &Apple,
+    settings:      &Settings) {
 
-    draw_context.draw_texture(
-        apple_texture, apple.x as i32, apple.y as i32, Color {
-            r: 255, g: 255, b: 255, a: lerp(0.0, 255.0, apple.time_left) as u8
-        }
+    // Reduced flashing holds it steady instead of blinking it out at the end
+    let alpha = if settings.reduce_flashing { 1.0 } else { apple.alpha() };
+    let tint  = palette::food_tint(settings.palette, apple.food_type).fade(alpha);
+
+    if apple.settings.show_timer_ring {
+        draw_apple_timer_ring(draw_context, apple, alpha, settings.palette);
+    }
+
+    let outline = OUTLINE_COLOR.fade(alpha);
+    let sides = if settings.food_shapes { map_food_type_to_sides(apple.food_type) } else { None };
+    match sides {
+        Some(sides) => {
+            let center = Vector2 { x: apple.x + CELL_SIZE / 2.0, y: apple.y + CELL_SIZE / 2.0 };
+            if settings.high_contrast {
+                draw_context.draw_poly(center, sides, FOOD_SHAPE_RADIUS + OUTLINE_WIDTH, 0.0, outline);
+            }
+            draw_context.draw_poly(center, sides, FOOD_SHAPE_RADIUS, 0.0, tint);
+            draw_context.draw_poly_lines(center, sides, FOOD_SHAPE_RADIUS, 0.0, tint.brightness(-0.4));
+        },
+        None => {
+            if settings.high_contrast {
+                draw_snake_part(draw_context, apple_texture, apple.x, apple.y, 0.0, outline, camera::UNSCALED, OUTLINE_WIDTH);
+            }
+            draw_context.draw_texture(apple_texture, apple.x as i32, apple.y as i32, tint);
+        },
+    }
+}
+
+// @note: The ring empties clockwise starting from the top, raylib measures 
+//  angles clockwise from the positive x axis so the top is -90.0. 
+fn draw_apple_timer_ring(
+    draw_context: &mut impl RaylibDraw, 
+    apple:        &Apple,
+    alpha:        f32,
+    palette:      Palette) {
+
+    let center = Vector2 { x: apple.x + 10.0, y: apple.y + 10.0 };
+    let fraction_left = apple.fraction_left();
+
+    draw_context.draw_ring(
+        center, 12.0, 14.0, 0.0, 360.0, 32, Color { r: 0, g: 0, b: 0, a: 40 }
+    );
+
+    let colors = palette::colors(palette);
+    let ring_color = if apple.early_bonus() > 0 {
+        colors.good
+    } else if fraction_left > 0.25 {
+        colors.warning
+    } else {
+        colors.danger
+    };
+
+    draw_context.draw_ring(
+        center, 
+        12.0, 
+        14.0, 
+        -90.0, 
+        -90.0 + 360.0 * fraction_left, 
+        32, 
+        Color { r: ring_color.r, g: ring_color.g, b: ring_color.b, a: lerp(0.0, 255.0, alpha) as u8 }
     );
 }
 
 fn draw_snake_part(
-    draw_context: &mut RaylibDrawHandle,
+    draw_context: &mut impl RaylibDraw,
     texture:      &Texture2D,
     x:            f32,
     y:            f32,
-    rotation:     f32 ) {
+    rotation:     f32,
+    tint:         Color,
+    scale:        Vector2,
+    // Grows it by this much on every side, drawn in a flat colour under the
+    //  real thing that makes an outline in the sprite's own shape
+    outline:      f32) {
+
+    let destination_size = Vector2 {
+        x: texture.width  as f32 * scale.x + outline * 2.0,
+        y: texture.height as f32 * scale.y + outline * 2.0
+    };
 
     draw_context.draw_texture_pro(
         texture, 
@@ -313,25 +1753,32 @@ fn draw_snake_part(
         Rectangle {
             x: x + 10.0, 
             y: y + 10.0,
-            width:  texture.width  as f32,
-            height: texture.height as f32
+            width:  destination_size.x,
+            height: destination_size.y
         },
-        Vector2 { x: 10.0, y: 10.0 },
+        Vector2 { x: destination_size.x / 2.0, y: destination_size.y / 2.0 },
         rotation,
-        Color::WHITE
+        tint
     );
 }
 
 fn draw_snake(
-    draw_context: &mut RaylibDrawHandle, 
+    draw_context: &mut impl RaylibDraw, 
     texture_map:  &TextureMap,
-    snake:        &Snake) {
+    snake:        &Snake,
+    tint:         Color,
+    head_scale:   Vector2,
+    // Segments gone from the head end while the snake comes apart
+    crumbled:     f32,
+    outline:      f32) {
 
     let mut previous_direction: Option<Direction> = None;
     let mut last_drawn_was_head: bool = true;
     // @note: GIANT note. We're reverse iterating here. The head of the snake is always
     //  at the end of the vector. 
-    for (_, window) in (&snake.parts).windows(2).into_iter().rev().enumerate() {
+    for (step, window) in snake.parts.windows(2).rev().enumerate() {
+        let visible = (step as f32 + 1.0 - crumbled).clamp(0.0, 1.0);
+        let tint = tint.fade(tint.a as f32 / 255.0 * visible);
 
         // @note: The current position is 1 because, we're reverse iterating 
         //  above.  
@@ -346,7 +1793,10 @@ fn draw_snake(
                     &texture_map.snake_head_straight_texture, 
                     snake_part.x, 
                     snake_part.y, 
-                    map_direction_to_rotation(snake.head_direction)
+                    map_direction_to_rotation(snake.head_direction),
+                    tint,
+                    head_scale,
+                    outline
                 );
             } else {
                 draw_snake_part(
@@ -371,8 +1821,11 @@ fn draw_snake(
                     }, 
                     snake_part.x, 
                     snake_part.y, 
-                    0.0
+                    0.0,
                     // map_direction_to_rotation(snake.head_direction)
+                    tint,
+                    head_scale,
+                    outline
                 );
                 
             }
@@ -393,7 +1846,10 @@ fn draw_snake(
                             &texture_map.snake_middle_texture, 
                             snake_part.x, 
                             snake_part.y, 
-                            0.0
+                            0.0,
+                            tint,
+                            camera::UNSCALED,
+                            outline
                         );
                     }
                     Direction::LEFT | Direction::RIGHT => {
@@ -402,7 +1858,10 @@ fn draw_snake(
                             &texture_map.snake_middle_texture, 
                             snake_part.x, 
                             snake_part.y, 
-                            90.0
+                            90.0,
+                            tint,
+                            camera::UNSCALED,
+                            outline
                         );
                     }
                 }
@@ -416,7 +1875,10 @@ fn draw_snake(
                     map_difference_in_direction_to_rotation_for_snake_corner(
                         previous_direction.unwrap(), 
                         snake_part.direction
-                    )
+                    ),
+                    tint,
+                    camera::UNSCALED,
+                    outline
                 );
 
                 // draw_context.draw_rectangle_rounded(
@@ -436,109 +1898,3 @@ fn draw_snake(
     }
 
 }
-
-fn create_starting_snake() -> Snake {
-    let mut snake_parts: Vec<SnakePart> = Vec::new();
-    for _ in 0..STARTING_SNAKE_LENGTH-1 {
-        snake_parts.push(SnakePart {
-            x: 100.0,
-            y: 120.0,
-            direction: Direction::RIGHT
-        });
-    }
-    snake_parts.push(SnakePart {
-        x: 120.0,
-        y: 120.0,
-        direction: Direction::RIGHT
-    });
-
-    Snake {
-        parts: snake_parts,
-        head_direction: Direction::RIGHT
-    }
-}
-
-fn propogate_snake_movement(snake: &mut Snake, enlarge_snake: bool) {
-    let mut new_head = snake.head();
-    match snake.head_direction {
-        Direction::UP => {
-            new_head.y -= 20.0;
-        }, 
-        Direction::LEFT => {
-            new_head.x -= 20.0;
-        }, 
-        Direction::DOWN => {
-            new_head.y += 20.0;
-        },
-        Direction::RIGHT => {
-            new_head.x += 20.0;
-        }
-    }
-    new_head.direction = snake.head_direction;
-    if !enlarge_snake {
-        snake.parts.remove(0);
-    }
-
-    snake.parts.push(new_head);
-}
-
-fn does_snake_self_intersect(snake: &Snake) -> bool {
-
-    for index in 0..snake.parts.len()-1 {
-        let snake_part = snake.parts[index];
-
-        if (Rectangle {
-            x: snake_part.x,
-            y: snake_part.y,
-            width:  20.0,
-            height: 20.0
-        }).check_collision_point_rec(
-            Vector2 { x: snake.head().x, y: snake.head().y }
-        ) {
-            return true;
-        }
-    }
-
-    false
-}
-
-fn place_apple(apple: &mut Apple, snake: &Snake, raylib_handle: &RaylibHandle, bounds: Rectangle) {
-    loop {
-        let new_apple = Apple {
-            x: raylib_handle.get_random_value::<i32>(bounds.x as i32..(bounds.x+bounds.width)  as i32) as f32,
-            y: raylib_handle.get_random_value::<i32>(bounds.y as i32..(bounds.y+bounds.height) as i32) as f32,
-            time_left: MAX_APPLE_DURATION
-        };
-        let new_apple = snap_apple_to_grid(&new_apple);
-        println!("New apple location is: {:?}", new_apple);
-
-        let mut pick_new_apple = false;
-        for snake_part in &snake.parts {
-            let is_snake_where_apple_is = Rectangle {
-                x: snake_part.x, 
-                y: snake_part.y, 
-                width:  20.0, 
-                height: 20.0
-            }.check_collision_point_rec(Vector2 { x: new_apple.x, y: new_apple.y });
-            if is_snake_where_apple_is {
-                pick_new_apple = true;
-                break; 
-            }
-        }
-        if pick_new_apple {
-            continue;
-        }
-
-        *apple = new_apple;
-
-        break;
-    }
-}
-
-fn snap_apple_to_grid(apple: &Apple) -> Apple{
-    Apple {
-        x: apple.x - apple.x % 20.0,
-        y: apple.y - apple.y % 20.0,
-        time_left: apple.time_left
-    }
-}
\ No newline at end of file
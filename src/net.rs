@@ -0,0 +1,1230 @@
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::food::{Apple, FoodSettings, FoodTable, FOOD_TYPES};
+use crate::game::{self, Death, DeathCause, Direction, Game, GameMode, Player, Snake, SnakePart, TICK_DURATION, WALL_SIDES};
+
+// @note: Online play is server authoritative. The server owns the only real
+//  `Game`, clients just send the direction they want and draw whatever the
+//  latest snapshot says. To keep controls feeling responsive the client rolls
+//  back to the latest snapshot every frame and re-simulates the few ticks since
+//  then with its own unacknowledged input applied on top (see `predicted_game`).
+//
+// Spectators send SPECTATE every so often to stay registered and get sent the
+//  same snapshots the players get. Snapshots are always the full state so a
+//  spectator joining halfway through a match doesn't need anything special. A
+//  local game can be watched the same way through a `Broadcaster`.
+//
+// Every snapshot carries the id of the match it's from, which changes whenever
+//  the server starts over. Ticks and rounds are only compared within a match,
+//  a new match starts back at round 1 tick 0 and would otherwise look older
+//  than everything before it.
+
+pub const DEFAULT_BIND_ADDRESS:   &str = "0.0.0.0:7777";
+pub const DEFAULT_SERVER_ADDRESS: &str = "127.0.0.1:7777";
+
+const PROTOCOL_ID:      u16 = 0x534E;
+const PROTOCOL_VERSION: u8  = 4;
+const MAX_PACKET_SIZE:  usize = 65507;
+
+const CLIENT_TIMEOUT:       Duration = Duration::from_secs(5);
+const ROUND_RESTART_DELAY:  Duration = Duration::from_secs(3);
+const JOIN_RETRY_INTERVAL:  Duration = Duration::from_millis(500);
+const MAX_PREDICTED_TICKS:  u32 = 4;
+
+const MAX_SPECTATORS:                   usize = 32;
+const SPECTATOR_KEEPALIVE_INTERVAL:     Duration = Duration::from_secs(1);
+
+const MESSAGE_JOIN:     u8 = 1;
+const MESSAGE_INPUT:    u8 = 2;
+const MESSAGE_LEAVE:    u8 = 3;
+const MESSAGE_SPECTATE: u8 = 4;
+const MESSAGE_WELCOME:  u8 = 10;
+const MESSAGE_FULL:     u8 = 11;
+const MESSAGE_SNAPSHOT: u8 = 12;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClientMessage {
+    JOIN,
+    // `sequence` only goes up when the player presses a new direction. Clients
+    //  resend their latest input every frame so a lost packet doesn't lose a turn.
+    INPUT { sequence: u32, direction: Option<Direction>, speed_up: bool },
+    LEAVE,
+    // Doubles as the spectator's keepalive
+    SPECTATE
+}
+
+#[derive(Clone, Debug)]
+pub enum ServerMessage {
+    WELCOME { player_index: u8 },
+    FULL,
+    // Boxed, it's far bigger than the other messages
+    SNAPSHOT(Box<Snapshot>)
+}
+
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub match_id:               u32,
+    pub mode:                   GameMode,
+    pub tick:                   u64,
+    pub round:                  u32,
+    pub round_over:             bool,
+    pub waiting_for_players:    bool,
+    // The board can differ from the watcher's own, e.g. a level or a smaller size
+    pub play_area:              game::Bounds,
+    pub walls:                  Vec<(f32, f32)>,
+    // What the next apples are placed with, the current one carries its own
+    pub food:                   FoodTable,
+    pub apple:                  Apple,
+    pub players:                Vec<Player>,
+    // The last input sequence the server applied for each player
+    pub input_acks:             Vec<u32>
+}
+
+impl Snapshot {
+    pub fn from_game(game: &Game, match_id: u32, waiting_for_players: bool, input_acks: Vec<u32>) -> Self {
+        Snapshot {
+            match_id:               match_id,
+            mode:                   game.mode,
+            tick:                   game.tick,
+            round:                  game.round,
+            round_over:             game.round_over,
+            waiting_for_players:    waiting_for_players,
+            play_area:              game.play_area,
+            walls:                  game.walls.clone(),
+            food:                   game.food,
+            apple:                  game.apple,
+            players:                game.players.clone(),
+            input_acks:             input_acks
+        }
+    }
+
+    // UDP can reorder, never go back in time within a match. A snapshot from
+    //  another match is always taken, the server has started over.
+    pub fn is_stale(self: &Self, current: Option<&Game>, current_match_id: u32) -> bool {
+        current.is_some_and(|game| {
+            self.match_id == current_match_id && (self.round, self.tick) < (game.round, game.tick)
+        })
+    }
+
+    pub fn apply_to(self: &Self, game: &mut Game) {
+        game.mode       = self.mode;
+        game.tick       = self.tick;
+        game.round      = self.round;
+        game.round_over = self.round_over;
+        game.play_area  = self.play_area;
+        game.walls      = self.walls.clone();
+        game.food       = self.food;
+        game.apple      = self.apple;
+        game.players    = self.players.clone();
+        game.time_accumulator = 0.0;
+    }
+
+    pub fn to_game(self: &Self) -> Game {
+        let mut game = Game::new(self.mode, self.play_area, 0);
+        self.apply_to(&mut game);
+        game
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Encoding. Everything is little endian and prefixed with the protocol id and
+//  version so stray packets are ignored.
+
+pub struct PacketWriter {
+    pub bytes: Vec<u8>
+}
+
+impl PacketWriter {
+    pub fn new(message_type: u8) -> Self {
+        let mut writer = PacketWriter { bytes: Vec::new() };
+        writer.write_u16(PROTOCOL_ID);
+        writer.write_u8(PROTOCOL_VERSION);
+        writer.write_u8(message_type);
+        writer
+    }
+
+    pub fn write_u8(self: &mut Self, value: u8)   { self.bytes.push(value); }
+    pub fn write_u16(self: &mut Self, value: u16) { self.bytes.extend_from_slice(&value.to_le_bytes()); }
+    pub fn write_u32(self: &mut Self, value: u32) { self.bytes.extend_from_slice(&value.to_le_bytes()); }
+    pub fn write_u64(self: &mut Self, value: u64) { self.bytes.extend_from_slice(&value.to_le_bytes()); }
+    pub fn write_f32(self: &mut Self, value: f32) { self.bytes.extend_from_slice(&value.to_le_bytes()); }
+    pub fn write_bool(self: &mut Self, value: bool) { self.write_u8(value as u8); }
+
+    pub fn write_direction(self: &mut Self, direction: Option<Direction>) {
+        self.write_u8(match direction {
+            Some(Direction::UP)    => 0,
+            Some(Direction::LEFT)  => 1,
+            Some(Direction::DOWN)  => 2,
+            Some(Direction::RIGHT) => 3,
+            None                   => 255,
+        });
+    }
+
+    pub fn write_food_settings(self: &mut Self, settings: &FoodSettings) {
+        self.write_f32(settings.duration);
+        self.write_f32(settings.fade_time);
+        self.write_f32(settings.min_alpha);
+        self.write_f32(settings.blink_time);
+        self.write_f32(settings.blink_frequency);
+        self.write_bool(settings.show_timer_ring);
+        self.write_u16(settings.points);
+        self.write_u16(settings.early_bonus);
+        self.write_f32(settings.early_bonus_time);
+        self.write_u32(settings.spawn_weight);
+    }
+
+    pub fn write_apple(self: &mut Self, apple: &Apple) {
+        self.write_f32(apple.x);
+        self.write_f32(apple.y);
+        self.write_f32(apple.time_left);
+        self.write_u8(FOOD_TYPES.iter().position(|food_type| *food_type == apple.food_type).unwrap_or(0) as u8);
+        self.write_food_settings(&apple.settings);
+    }
+
+    pub fn write_snake(self: &mut Self, snake: &Snake) {
+        self.write_direction(Some(snake.head_direction));
+        self.write_u32(snake.parts.len() as u32);
+        for part in &snake.parts {
+            self.write_f32(part.x);
+            self.write_f32(part.y);
+            self.write_direction(Some(part.direction));
+        }
+    }
+
+    pub fn write_death(self: &mut Self, death: Option<Death>) {
+        let Some(death) = death else {
+            self.write_u8(0);
+            return;
+        };
+
+        match death.cause {
+            DeathCause::WALL(side) => {
+                self.write_u8(1);
+                self.write_u8(WALL_SIDES.iter().position(|wall_side| *wall_side == side).unwrap_or(0) as u8);
+            },
+            DeathCause::SELF(segment) => {
+                self.write_u8(2);
+                self.write_u32(segment as u32);
+            },
+            DeathCause::SNAKE(index, segment) => {
+                self.write_u8(3);
+                self.write_u8(index as u8);
+                self.write_u32(segment as u32);
+            },
+            DeathCause::OBSTACLE => self.write_u8(4),
+        }
+        self.write_f32(death.x);
+        self.write_f32(death.y);
+        self.write_u64(death.tick);
+        self.write_bool(death.boosting);
+        self.write_u32(death.length as u32);
+    }
+
+    pub fn write_player(self: &mut Self, player: &Player) {
+        self.write_u16(player.score);
+        self.write_u32(player.round_wins);
+        self.write_bool(player.alive);
+        self.write_bool(player.speed_up);
+        self.write_bool(player.enlarge_snake);
+        self.write_u32(player.move_progress);
+        self.write_death(player.death);
+        self.write_snake(&player.snake);
+    }
+
+    pub fn write_snapshot(self: &mut Self, snapshot: &Snapshot) {
+        self.write_u32(snapshot.match_id);
+        self.write_u8(match snapshot.mode { GameMode::SOLO => 0, GameMode::VERSUS => 1 });
+        self.write_u64(snapshot.tick);
+        self.write_u32(snapshot.round);
+        self.write_bool(snapshot.round_over);
+        self.write_bool(snapshot.waiting_for_players);
+        self.write_f32(snapshot.play_area.x);
+        self.write_f32(snapshot.play_area.y);
+        self.write_f32(snapshot.play_area.width);
+        self.write_f32(snapshot.play_area.height);
+        self.write_u32(snapshot.walls.len() as u32);
+        for (x, y) in &snapshot.walls {
+            self.write_f32(*x);
+            self.write_f32(*y);
+        }
+        for food_type in FOOD_TYPES {
+            self.write_food_settings(&snapshot.food.get(food_type));
+        }
+        self.write_apple(&snapshot.apple);
+        self.write_u8(snapshot.players.len() as u8);
+        for (index, player) in snapshot.players.iter().enumerate() {
+            self.write_player(player);
+            self.write_u32(snapshot.input_acks.get(index).copied().unwrap_or(0));
+        }
+    }
+}
+
+pub struct PacketReader<'a> {
+    bytes:    &'a [u8],
+    position: usize
+}
+
+impl<'a> PacketReader<'a> {
+    // Returns the reader positioned after the header along with the message
+    //  type, or None if this isn't one of our packets.
+    pub fn new(bytes: &'a [u8]) -> Option<(Self, u8)> {
+        let mut reader = PacketReader { bytes: bytes, position: 0 };
+        if reader.read_u16()? != PROTOCOL_ID || reader.read_u8()? != PROTOCOL_VERSION {
+            return None;
+        }
+        let message_type = reader.read_u8()?;
+        Some((reader, message_type))
+    }
+
+    fn take(self: &mut Self, count: usize) -> Option<&'a [u8]> {
+        if self.position + count > self.bytes.len() {
+            return None;
+        }
+        let slice = &self.bytes[self.position..self.position + count];
+        self.position += count;
+        Some(slice)
+    }
+
+    fn remaining(self: &Self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    pub fn read_u8(self: &mut Self) -> Option<u8>   { Some(self.take(1)?[0]) }
+    pub fn read_u16(self: &mut Self) -> Option<u16> { Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?)) }
+    pub fn read_u32(self: &mut Self) -> Option<u32> { Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?)) }
+    pub fn read_u64(self: &mut Self) -> Option<u64> { Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?)) }
+    pub fn read_f32(self: &mut Self) -> Option<f32> { Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?)) }
+    pub fn read_bool(self: &mut Self) -> Option<bool> { Some(self.read_u8()? != 0) }
+
+    // Outer None means the packet is malformed, inner None is "no direction"
+    pub fn read_direction(self: &mut Self) -> Option<Option<Direction>> {
+        match self.read_u8()? {
+            0   => Some(Some(Direction::UP)),
+            1   => Some(Some(Direction::LEFT)),
+            2   => Some(Some(Direction::DOWN)),
+            3   => Some(Some(Direction::RIGHT)),
+            255 => Some(None),
+            _   => None,
+        }
+    }
+
+    pub fn read_food_settings(self: &mut Self) -> Option<FoodSettings> {
+        let settings = FoodSettings {
+            duration:           self.read_f32()?,
+            fade_time:          self.read_f32()?,
+            min_alpha:          self.read_f32()?,
+            blink_time:         self.read_f32()?,
+            blink_frequency:    self.read_f32()?,
+            show_timer_ring:    self.read_bool()?,
+            points:             self.read_u16()?,
+            early_bonus:        self.read_u16()?,
+            early_bonus_time:   self.read_f32()?,
+            spawn_weight:       self.read_u32()?
+        };
+        let times = [settings.duration, settings.fade_time, settings.min_alpha, settings.blink_time, settings.blink_frequency, settings.early_bonus_time];
+        if times.iter().all(|time| time.is_finite()) { Some(settings) } else { None }
+    }
+
+    pub fn read_apple(self: &mut Self) -> Option<Apple> {
+        let x         = self.read_f32()?;
+        let y         = self.read_f32()?;
+        let time_left = self.read_f32()?;
+        let food_type = *FOOD_TYPES.get(self.read_u8()? as usize)?;
+        let mut apple = Apple::new(x, y, food_type, self.read_food_settings()?);
+        apple.time_left = time_left;
+        Some(apple)
+    }
+
+    pub fn read_snake(self: &mut Self) -> Option<Snake> {
+        let head_direction = self.read_direction()??;
+        let part_count = self.read_u32()? as usize;
+        // Each part is 9 bytes, don't trust a count the packet can't hold
+        if part_count == 0 || part_count > self.remaining() / 9 {
+            return None;
+        }
+
+        let mut parts: Vec<SnakePart> = Vec::with_capacity(part_count);
+        for _ in 0..part_count {
+            let part = SnakePart {
+                x:          self.read_f32()?,
+                y:          self.read_f32()?,
+                direction:  self.read_direction()??
+            };
+            if !part.x.is_finite() || !part.y.is_finite() {
+                return None;
+            }
+            parts.push(part);
+        }
+
+        Some(Snake { parts: parts, head_direction: head_direction })
+    }
+
+    pub fn read_death(self: &mut Self) -> Option<Option<Death>> {
+        let cause = match self.read_u8()? {
+            0 => return Some(None),
+            1 => DeathCause::WALL(*WALL_SIDES.get(self.read_u8()? as usize)?),
+            2 => DeathCause::SELF(self.read_u32()? as usize),
+            3 => DeathCause::SNAKE(self.read_u8()? as usize, self.read_u32()? as usize),
+            4 => DeathCause::OBSTACLE,
+            _ => return None,
+        };
+
+        Some(Some(Death {
+            cause:      cause,
+            x:          self.read_f32()?,
+            y:          self.read_f32()?,
+            tick:       self.read_u64()?,
+            boosting:   self.read_bool()?,
+            length:     self.read_u32()? as usize
+        }))
+    }
+
+    pub fn read_player(self: &mut Self) -> Option<Player> {
+        Some(Player {
+            score:          self.read_u16()?,
+            round_wins:     self.read_u32()?,
+            alive:          self.read_bool()?,
+            speed_up:       self.read_bool()?,
+            enlarge_snake:  self.read_bool()?,
+            move_progress:  self.read_u32()?,
+            death:          self.read_death()?,
+            snake:          self.read_snake()?
+        })
+    }
+
+    pub fn read_snapshot(self: &mut Self) -> Option<Snapshot> {
+        let match_id = self.read_u32()?;
+        let mode = match self.read_u8()? { 0 => GameMode::SOLO, 1 => GameMode::VERSUS, _ => return None };
+        let tick                = self.read_u64()?;
+        let round               = self.read_u32()?;
+        let round_over          = self.read_bool()?;
+        let waiting_for_players = self.read_bool()?;
+        let play_area = game::Bounds {
+            x:      self.read_f32()?,
+            y:      self.read_f32()?,
+            width:  self.read_f32()?,
+            height: self.read_f32()?
+        };
+        let finite = [play_area.x, play_area.y, play_area.width, play_area.height].iter().all(|value| value.is_finite());
+        if !finite || play_area.width <= 0.0 || play_area.height <= 0.0 {
+            return None;
+        }
+
+        let wall_count = self.read_u32()? as usize;
+        // Each wall is 8 bytes
+        if wall_count > self.remaining() / 8 {
+            return None;
+        }
+        let mut walls: Vec<(f32, f32)> = Vec::with_capacity(wall_count);
+        for _ in 0..wall_count {
+            walls.push((self.read_f32()?, self.read_f32()?));
+        }
+
+        let mut food = FoodTable::default();
+        for food_type in FOOD_TYPES {
+            food.set_settings(food_type, self.read_food_settings()?);
+        }
+        let apple               = self.read_apple()?;
+
+        // Everything drawing the game indexes players by the mode's count
+        let player_count = self.read_u8()? as usize;
+        if player_count != mode.player_count() {
+            return None;
+        }
+        let mut players:    Vec<Player> = Vec::with_capacity(player_count);
+        let mut input_acks: Vec<u32>    = Vec::with_capacity(player_count);
+        for _ in 0..player_count {
+            players.push(self.read_player()?);
+            input_acks.push(self.read_u32()?);
+        }
+
+        Some(Snapshot {
+            match_id:               match_id,
+            mode:                   mode,
+            tick:                   tick,
+            round:                  round,
+            round_over:             round_over,
+            waiting_for_players:    waiting_for_players,
+            play_area:              play_area,
+            walls:                  walls,
+            food:                   food,
+            apple:                  apple,
+            players:                players,
+            input_acks:             input_acks
+        })
+    }
+}
+
+pub fn encode_client_message(message: &ClientMessage) -> Vec<u8> {
+    match message {
+        ClientMessage::JOIN  => PacketWriter::new(MESSAGE_JOIN).bytes,
+        ClientMessage::LEAVE => PacketWriter::new(MESSAGE_LEAVE).bytes,
+        ClientMessage::SPECTATE => PacketWriter::new(MESSAGE_SPECTATE).bytes,
+        ClientMessage::INPUT { sequence, direction, speed_up } => {
+            let mut writer = PacketWriter::new(MESSAGE_INPUT);
+            writer.write_u32(*sequence);
+            writer.write_direction(*direction);
+            writer.write_bool(*speed_up);
+            writer.bytes
+        },
+    }
+}
+
+pub fn decode_client_message(bytes: &[u8]) -> Option<ClientMessage> {
+    let (mut reader, message_type) = PacketReader::new(bytes)?;
+    match message_type {
+        MESSAGE_JOIN  => Some(ClientMessage::JOIN),
+        MESSAGE_LEAVE => Some(ClientMessage::LEAVE),
+        MESSAGE_SPECTATE => Some(ClientMessage::SPECTATE),
+        MESSAGE_INPUT => Some(ClientMessage::INPUT {
+            sequence:   reader.read_u32()?,
+            direction:  reader.read_direction()?,
+            speed_up:   reader.read_bool()?
+        }),
+        _ => None,
+    }
+}
+
+pub fn encode_server_message(message: &ServerMessage) -> Vec<u8> {
+    match message {
+        ServerMessage::WELCOME { player_index } => {
+            let mut writer = PacketWriter::new(MESSAGE_WELCOME);
+            writer.write_u8(*player_index);
+            writer.bytes
+        },
+        ServerMessage::FULL => PacketWriter::new(MESSAGE_FULL).bytes,
+        ServerMessage::SNAPSHOT(snapshot) => {
+            let mut writer = PacketWriter::new(MESSAGE_SNAPSHOT);
+            writer.write_snapshot(snapshot);
+            writer.bytes
+        },
+    }
+}
+
+pub fn decode_server_message(bytes: &[u8]) -> Option<ServerMessage> {
+    let (mut reader, message_type) = PacketReader::new(bytes)?;
+    match message_type {
+        MESSAGE_WELCOME  => Some(ServerMessage::WELCOME { player_index: reader.read_u8()? }),
+        MESSAGE_FULL     => Some(ServerMessage::FULL),
+        MESSAGE_SNAPSHOT => Some(ServerMessage::SNAPSHOT(Box::new(reader.read_snapshot()?))),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Spectators
+
+struct SpectatorSlot {
+    address:    SocketAddr,
+    last_heard: Instant
+}
+
+struct Spectators {
+    slots: Vec<SpectatorSlot>
+}
+
+impl Spectators {
+    fn new() -> Self {
+        Spectators { slots: Vec::new() }
+    }
+
+    // Returns true if this is a new spectator
+    fn handle_spectate(self: &mut Self, address: SocketAddr) -> bool {
+        if let Some(slot) = self.slots.iter_mut().find(|slot| slot.address == address) {
+            slot.last_heard = Instant::now();
+            return false;
+        }
+
+        if self.slots.len() >= MAX_SPECTATORS {
+            return false;
+        }
+
+        println!("Spectator joined from {}", address);
+        self.slots.push(SpectatorSlot { address: address, last_heard: Instant::now() });
+        true
+    }
+
+    fn drop_timed_out(self: &mut Self) {
+        self.slots.retain(|slot| {
+            let keep = slot.last_heard.elapsed() <= CLIENT_TIMEOUT;
+            if !keep {
+                println!("Spectator {} left", slot.address);
+            }
+            keep
+        });
+    }
+
+    fn send_to_all(self: &Self, socket: &UdpSocket, bytes: &[u8]) {
+        for slot in &self.slots {
+            let _ = socket.send_to(bytes, slot.address);
+        }
+    }
+}
+
+// Lets spectators watch a game that isn't running on a `Server`, i.e. a local
+//  game. Call `publish` once a frame with the current game.
+pub struct Broadcaster {
+    socket:         UdpSocket,
+    spectators:     Spectators,
+    match_id:       u32,
+    last_sent:      Option<(u32, u64, bool)>,
+    last_sent_at:   Instant
+}
+
+impl Broadcaster {
+    pub fn bind(bind_address: &str) -> Result<Self, String> {
+        let socket = UdpSocket::bind(bind_address)
+            .map_err(|error| format!("Failed to bind broadcast socket to {}: {}", bind_address, error))?;
+        socket.set_nonblocking(true)
+            .map_err(|error| format!("Failed to make broadcast socket non-blocking: {}", error))?;
+
+        Ok(Broadcaster {
+            socket:         socket,
+            spectators:     Spectators::new(),
+            match_id:       rand::random(),
+            last_sent:      None,
+            last_sent_at:   Instant::now()
+        })
+    }
+
+    pub fn spectator_count(self: &Self) -> usize {
+        self.spectators.slots.len()
+    }
+
+    pub fn publish(self: &mut Self, game: &Game) {
+        let mut buffer = [0u8; 64];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, address)) => {
+                    let is_spectate = decode_client_message(&buffer[..size]) == Some(ClientMessage::SPECTATE);
+                    if is_spectate && self.spectators.handle_spectate(address) {
+                        // Make sure the new spectator gets something even if the game is paused
+                        self.last_sent = None;
+                    }
+                },
+                Err(ref error) if error.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(_) => continue,
+            }
+        }
+        self.spectators.drop_timed_out();
+
+        // Only send when the game actually moved on, or every so often while it
+        //  sits still in case a packet went missing
+        let state = (game.round, game.tick, game.round_over);
+        // @note: The game is handed over in order every frame, so going back
+        //  in time means it was replaced with a new one
+        if self.last_sent.is_some_and(|(round, tick, _)| (game.round, game.tick) < (round, tick)) {
+            self.match_id = self.match_id.wrapping_add(1);
+        }
+        if self.last_sent == Some(state) && self.last_sent_at.elapsed() < SPECTATOR_KEEPALIVE_INTERVAL {
+            return;
+        }
+        self.last_sent    = Some(state);
+        self.last_sent_at = Instant::now();
+
+        let bytes = encode_server_message(&ServerMessage::SNAPSHOT(Box::new(Snapshot::from_game(game, self.match_id, false, Vec::new()))));
+        self.spectators.send_to_all(&self.socket, &bytes);
+    }
+}
+
+pub struct Spectator {
+    socket:             UdpSocket,
+    game:               Option<Game>,
+    match_id:           u32,
+    last_keepalive:     Option<Instant>,
+    pub waiting_for_players: bool
+}
+
+impl Spectator {
+    pub fn connect(address: &str) -> Result<Self, String> {
+        let socket = UdpSocket::bind("0.0.0.0:0")
+            .map_err(|error| format!("Failed to bind spectator socket: {}", error))?;
+        socket.connect(address)
+            .map_err(|error| format!("Failed to connect to {}: {}", address, error))?;
+        socket.set_nonblocking(true)
+            .map_err(|error| format!("Failed to make spectator socket non-blocking: {}", error))?;
+
+        Ok(Spectator {
+            socket:                 socket,
+            game:                   None,
+            match_id:               0,
+            last_keepalive:         None,
+            waiting_for_players:    false
+        })
+    }
+
+    pub fn poll(self: &mut Self) {
+        let should_send = self.last_keepalive.is_none_or(|sent| sent.elapsed() >= SPECTATOR_KEEPALIVE_INTERVAL);
+        if should_send {
+            let _ = self.socket.send(&encode_client_message(&ClientMessage::SPECTATE));
+            self.last_keepalive = Some(Instant::now());
+        }
+
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+        while let Ok(size) = self.socket.recv(&mut buffer) {
+            if let Some(ServerMessage::SNAPSHOT(snapshot)) = decode_server_message(&buffer[..size]) {
+                if snapshot.is_stale(self.game.as_ref(), self.match_id) {
+                    continue;
+                }
+
+                self.waiting_for_players = snapshot.waiting_for_players;
+                self.game = Some(snapshot.to_game());
+                self.match_id = snapshot.match_id;
+            }
+        }
+    }
+
+    pub fn game(self: &Self) -> Option<&Game> {
+        self.game.as_ref()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Server
+
+struct ClientSlot {
+    address:        SocketAddr,
+    last_heard:     Instant,
+    last_sequence:  u32
+}
+
+pub struct Server {
+    socket:             UdpSocket,
+    game:               Game,
+    // Changes on every reset_match
+    match_id:           u32,
+    slots:              Vec<Option<ClientSlot>>,
+    spectators:         Spectators,
+    round_over_since:   Option<Instant>
+}
+
+impl Server {
+    pub fn bind(bind_address: &str, play_area: game::Bounds) -> Result<Self, String> {
+        let socket = UdpSocket::bind(bind_address)
+            .map_err(|error| format!("Failed to bind server socket to {}: {}", bind_address, error))?;
+        socket.set_nonblocking(true)
+            .map_err(|error| format!("Failed to make server socket non-blocking: {}", error))?;
+
+        let game = Game::new(GameMode::VERSUS, play_area, rand::random());
+        let slot_count = game.players.len();
+        let mut slots: Vec<Option<ClientSlot>> = Vec::new();
+        for _ in 0..slot_count {
+            slots.push(None);
+        }
+
+        Ok(Server {
+            socket:             socket,
+            game:               game,
+            match_id:           rand::random(),
+            slots:              slots,
+            spectators:         Spectators::new(),
+            round_over_since:   None
+        })
+    }
+
+    pub fn local_address(self: &Self) -> Option<SocketAddr> {
+        self.socket.local_addr().ok()
+    }
+
+    fn is_full(self: &Self) -> bool {
+        self.slots.iter().all(|slot| slot.is_some())
+    }
+
+    fn slot_for(self: &Self, address: SocketAddr) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.as_ref().is_some_and(|slot| slot.address == address))
+    }
+
+    fn send(self: &Self, address: SocketAddr, message: &ServerMessage) {
+        let bytes = encode_server_message(message);
+        if let Err(error) = self.socket.send_to(&bytes, address) {
+            println!("WARN: Failed to send to {}: {}", address, error);
+        }
+    }
+
+    fn reset_match(self: &mut Self) {
+        self.game = Game::new(GameMode::VERSUS, self.game.play_area, rand::random());
+        self.match_id = self.match_id.wrapping_add(1);
+        self.round_over_since = None;
+    }
+
+    pub fn receive_messages(self: &mut Self) {
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+        loop {
+            let (size, address) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(ref error) if error.kind() == std::io::ErrorKind::WouldBlock => break,
+                // @note: On windows an ICMP port unreachable from a client that
+                //  went away shows up as a receive error, just skip it.
+                Err(_) => continue,
+            };
+
+            let message = match decode_client_message(&buffer[..size]) {
+                Some(message) => message,
+                None => continue,
+            };
+
+            self.handle_message(address, message);
+        }
+    }
+
+    fn handle_message(self: &mut Self, address: SocketAddr, message: ClientMessage) {
+        match message {
+            ClientMessage::JOIN => {
+                let index = match self.slot_for(address) {
+                    Some(index) => index,
+                    None => match self.slots.iter().position(|slot| slot.is_none()) {
+                        Some(index) => {
+                            println!("Player {} joined from {}", index + 1, address);
+                            self.slots[index] = Some(ClientSlot {
+                                address:        address,
+                                last_heard:     Instant::now(),
+                                last_sequence:  0
+                            });
+                            if self.is_full() {
+                                self.reset_match();
+                            }
+                            index
+                        },
+                        None => {
+                            self.send(address, &ServerMessage::FULL);
+                            return;
+                        },
+                    },
+                };
+                self.send(address, &ServerMessage::WELCOME { player_index: index as u8 });
+            },
+            ClientMessage::INPUT { sequence, direction, speed_up } => {
+                let index = match self.slot_for(address) {
+                    Some(index) => index,
+                    None => return,
+                };
+
+                let slot = self.slots[index].as_mut().unwrap();
+                slot.last_heard = Instant::now();
+
+                let player = &mut self.game.players[index];
+                player.speed_up = speed_up;
+                if sequence > slot.last_sequence {
+                    slot.last_sequence = sequence;
+                    if let Some(direction) = direction {
+                        game::steer_snake(&mut player.snake, direction);
+                    }
+                }
+            },
+            ClientMessage::LEAVE => {
+                if let Some(index) = self.slot_for(address) {
+                    self.drop_client(index);
+                }
+            },
+            ClientMessage::SPECTATE => {
+                self.spectators.handle_spectate(address);
+            },
+        }
+    }
+
+    fn drop_client(self: &mut Self, index: usize) {
+        println!("Player {} left", index + 1);
+        self.slots[index] = None;
+        self.reset_match();
+    }
+
+    pub fn drop_timed_out_clients(self: &mut Self) {
+        for index in 0..self.slots.len() {
+            let timed_out = self.slots[index].as_ref().is_some_and(|slot| slot.last_heard.elapsed() > CLIENT_TIMEOUT);
+            if timed_out {
+                self.drop_client(index);
+            }
+        }
+
+        self.spectators.drop_timed_out();
+    }
+
+    pub fn step(self: &mut Self) {
+        let waiting_for_players = !self.is_full();
+
+        if !waiting_for_players {
+            if self.game.round_over {
+                let since = *self.round_over_since.get_or_insert(Instant::now());
+                if since.elapsed() >= ROUND_RESTART_DELAY {
+                    if self.game.is_match_over() {
+                        self.reset_match();
+                    } else {
+                        self.game.start_round();
+                        self.round_over_since = None;
+                    }
+                }
+            } else {
+                self.game.step();
+            }
+        }
+
+        let input_acks: Vec<u32> = self.slots.iter()
+            .map(|slot| slot.as_ref().map_or(0, |slot| slot.last_sequence))
+            .collect();
+        let bytes = encode_server_message(
+            &ServerMessage::SNAPSHOT(Box::new(Snapshot::from_game(&self.game, self.match_id, waiting_for_players, input_acks)))
+        );
+        for slot in self.slots.iter().flatten() {
+            let _ = self.socket.send_to(&bytes, slot.address);
+        }
+        self.spectators.send_to_all(&self.socket, &bytes);
+    }
+
+    // Blocks forever, stepping the game at the fixed tick rate.
+    pub fn run(self: &mut Self) {
+        let tick_duration = Duration::from_secs_f32(TICK_DURATION);
+        let mut next_step = Instant::now();
+        loop {
+            self.receive_messages();
+            self.drop_timed_out_clients();
+
+            while Instant::now() >= next_step {
+                self.step();
+                next_step += tick_duration;
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+pub fn run_server(bind_address: &str, play_area: game::Bounds) {
+    let mut server = match Server::bind(bind_address, play_area) {
+        Ok(server) => server,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+
+    println!("Server listening on {}", bind_address);
+    server.run();
+}
+
+// ---------------------------------------------------------------------------
+// Client
+
+pub struct Client {
+    socket:                 UdpSocket,
+    pub player_index:       Option<usize>,
+    pub server_full:        bool,
+    pub waiting_for_players: bool,
+    server_game:            Option<Game>,
+    match_id:               u32,
+    snapshot_received_at:   Instant,
+    last_join_sent:         Option<Instant>,
+    input_sequence:         u32,
+    acked_sequence:         u32,
+    pending_direction:      Option<Direction>,
+    speed_up:               bool
+}
+
+impl Client {
+    pub fn connect(server_address: &str) -> Result<Self, String> {
+        let socket = UdpSocket::bind("0.0.0.0:0")
+            .map_err(|error| format!("Failed to bind client socket: {}", error))?;
+        socket.connect(server_address)
+            .map_err(|error| format!("Failed to connect to {}: {}", server_address, error))?;
+        socket.set_nonblocking(true)
+            .map_err(|error| format!("Failed to make client socket non-blocking: {}", error))?;
+
+        Ok(Client {
+            socket:                 socket,
+            player_index:           None,
+            server_full:            false,
+            waiting_for_players:    true,
+            server_game:            None,
+            match_id:               0,
+            snapshot_received_at:   Instant::now(),
+            last_join_sent:         None,
+            input_sequence:         0,
+            acked_sequence:         0,
+            pending_direction:      None,
+            speed_up:               false
+        })
+    }
+
+    fn send(self: &Self, message: &ClientMessage) {
+        // @note: Errors here are almost always "connection refused" because the
+        //  server isn't up yet, we just keep retrying.
+        let _ = self.socket.send(&encode_client_message(message));
+    }
+
+    pub fn steer(self: &mut Self, direction: Direction) {
+        self.input_sequence   += 1;
+        self.pending_direction = Some(direction);
+    }
+
+    pub fn set_speed_up(self: &mut Self, speed_up: bool) {
+        self.speed_up = speed_up;
+    }
+
+    // Call once a frame. Reads everything the server sent and sends our input.
+    pub fn poll(self: &mut Self) {
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+        while let Ok(size) = self.socket.recv(&mut buffer) {
+            match decode_server_message(&buffer[..size]) {
+                Some(ServerMessage::WELCOME { player_index }) => {
+                    self.player_index = Some(player_index as usize);
+                },
+                Some(ServerMessage::FULL) => {
+                    self.server_full = true;
+                },
+                Some(ServerMessage::SNAPSHOT(snapshot)) => {
+                    if snapshot.is_stale(self.server_game.as_ref(), self.match_id) {
+                        continue;
+                    }
+
+                    if let Some(index) = self.player_index {
+                        self.acked_sequence = snapshot.input_acks.get(index).copied().unwrap_or(0);
+                    }
+                    self.waiting_for_players = snapshot.waiting_for_players;
+                    self.server_game = Some(snapshot.to_game());
+                    self.match_id = snapshot.match_id;
+                    self.snapshot_received_at = Instant::now();
+                },
+                None => {},
+            }
+        }
+
+        if self.player_index.is_none() {
+            let should_join = self.last_join_sent.is_none_or(|sent| sent.elapsed() >= JOIN_RETRY_INTERVAL);
+            if should_join && !self.server_full {
+                self.send(&ClientMessage::JOIN);
+                self.last_join_sent = Some(Instant::now());
+            }
+            return;
+        }
+
+        self.send(&ClientMessage::INPUT {
+            sequence:   self.input_sequence,
+            direction:  self.pending_direction,
+            speed_up:   self.speed_up
+        });
+    }
+
+    pub fn server_game(self: &Self) -> Option<&Game> {
+        self.server_game.as_ref()
+    }
+
+    // @note: Rolls back to the last authoritative state and replays the ticks
+    //  that should have happened since, with our not yet acknowledged turn
+    //  applied. The opponent just keeps going the way it was heading.
+    pub fn predicted_game(self: &Self) -> Option<Game> {
+        let mut game = self.server_game.clone()?;
+        if self.waiting_for_players || game.round_over {
+            return Some(game);
+        }
+
+        if let (Some(index), Some(direction)) = (self.player_index, self.pending_direction) {
+            if self.input_sequence > self.acked_sequence && index < game.players.len() {
+                game::steer_snake(&mut game.players[index].snake, direction);
+            }
+        }
+
+        let ticks_since_snapshot = (self.snapshot_received_at.elapsed().as_secs_f32() / TICK_DURATION) as u32;
+        for _ in 0..ticks_since_snapshot.min(MAX_PREDICTED_TICKS) {
+            game.step();
+        }
+
+        Some(game)
+    }
+
+    pub fn disconnect(self: &mut Self) {
+        if self.player_index.is_some() {
+            self.send(&ClientMessage::LEAVE);
+        }
+        self.player_index = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::WallSide;
+
+    fn snapshot_with(deaths: [Option<DeathCause>; 2]) -> Snapshot {
+        let mut game = Game::new(GameMode::VERSUS, game::play_area_for_screen(640, 480), 42);
+        for _ in 0..5 {
+            game.step();
+        }
+        for (player, cause) in game.players.iter_mut().zip(deaths) {
+            player.alive = cause.is_none();
+            player.death = cause.map(|cause| Death { cause: cause, x: 60.0, y: -20.0, tick: 5, boosting: true, length: 3 });
+        }
+        Snapshot::from_game(&game, 7, false, vec![3, 9])
+    }
+
+    fn round_trip(snapshot: &Snapshot) -> Snapshot {
+        match decode_server_message(&encode_server_message(&ServerMessage::SNAPSHOT(Box::new(snapshot.clone())))) {
+            Some(ServerMessage::SNAPSHOT(decoded)) => *decoded,
+            other => panic!("expected a snapshot, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn client_messages_round_trip() {
+        let messages = [
+            ClientMessage::JOIN,
+            ClientMessage::LEAVE,
+            ClientMessage::SPECTATE,
+            ClientMessage::INPUT { sequence: 12, direction: Some(Direction::LEFT), speed_up: true },
+            ClientMessage::INPUT { sequence: 0, direction: None, speed_up: false },
+        ];
+        for message in messages {
+            assert_eq!(decode_client_message(&encode_client_message(&message)), Some(message));
+        }
+    }
+
+    #[test]
+    fn welcome_and_full_round_trip() {
+        let welcome = decode_server_message(&encode_server_message(&ServerMessage::WELCOME { player_index: 1 }));
+        assert!(matches!(welcome, Some(ServerMessage::WELCOME { player_index: 1 })));
+        let full = decode_server_message(&encode_server_message(&ServerMessage::FULL));
+        assert!(matches!(full, Some(ServerMessage::FULL)));
+    }
+
+    #[test]
+    fn snapshot_round_trips() {
+        let snapshot = snapshot_with([None, None]);
+        let decoded = round_trip(&snapshot);
+        assert_eq!(decoded.match_id, 7);
+        assert_eq!(decoded.tick, snapshot.tick);
+        assert_eq!(decoded.round, snapshot.round);
+        assert_eq!(decoded.apple, snapshot.apple);
+        assert_eq!(decoded.input_acks, vec![3, 9]);
+        for (decoded, player) in decoded.players.iter().zip(&snapshot.players) {
+            assert_eq!(decoded.snake, player.snake);
+            assert_eq!(decoded.score, player.score);
+            assert_eq!(decoded.move_progress, player.move_progress);
+        }
+    }
+
+    #[test]
+    fn snapshots_carry_the_board() {
+        let play_area = game::Bounds { x: 100.0, y: 60.0, width: 320.0, height: 240.0 };
+        let walls = vec![(120.0, 80.0), (140.0, 80.0), (400.0, 280.0)];
+        let game = Game::with_walls(GameMode::SOLO, play_area, walls.clone(), 5);
+
+        let decoded = round_trip(&Snapshot::from_game(&game, 1, false, Vec::new()));
+        assert_eq!(decoded.play_area, play_area);
+        assert_eq!(decoded.walls, walls);
+
+        let watched = decoded.to_game();
+        assert_eq!(watched.play_area, play_area);
+        assert_eq!(watched.walls, walls);
+        assert!(watched.is_wall(140.0, 80.0));
+    }
+
+    #[test]
+    fn snapshots_with_a_bad_board_are_dropped() {
+        for width in [0.0, -20.0, f32::NAN, f32::INFINITY] {
+            let mut snapshot = snapshot_with([None, None]);
+            snapshot.play_area.width = width;
+            let bytes = encode_server_message(&ServerMessage::SNAPSHOT(Box::new(snapshot)));
+            assert!(decode_server_message(&bytes).is_none(), "decoded a board {} wide", width);
+        }
+    }
+
+    #[test]
+    fn snapshots_carry_the_food_settings() {
+        let area = game::play_area_for_screen(640, 480);
+        let mut game = Game::new(GameMode::SOLO, area, 3);
+        let mut food = FoodTable::default();
+        food.set("apple.duration", "9.5").unwrap();
+        food.set("golden.blink_time", "0.25").unwrap();
+        game.set_food(food);
+
+        let decoded = round_trip(&Snapshot::from_game(&game, 1, false, Vec::new()));
+        assert_eq!(decoded.food, food);
+        assert_eq!(decoded.apple.settings, food.get(decoded.apple.food_type));
+        assert_eq!(decoded.to_game().food, food);
+
+        // Food already on the board keeps what it was placed with
+        let mut snapshot = Snapshot::from_game(&game, 1, false, Vec::new());
+        snapshot.apple.settings.duration = 2.0;
+        assert_eq!(round_trip(&snapshot).apple.settings.duration, 2.0);
+    }
+
+    #[test]
+    fn every_death_cause_round_trips() {
+        let causes = [
+            DeathCause::WALL(WallSide::BOTTOM),
+            DeathCause::SELF(4),
+            DeathCause::SNAKE(1, 2),
+            DeathCause::OBSTACLE,
+        ];
+        for cause in causes {
+            let snapshot = snapshot_with([Some(cause), None]);
+            let decoded = round_trip(&snapshot);
+            assert_eq!(decoded.players[0].death, snapshot.players[0].death);
+            assert_eq!(decoded.players[1].death, None);
+        }
+    }
+
+    #[test]
+    fn truncated_packets_are_dropped() {
+        let bytes = encode_server_message(&ServerMessage::SNAPSHOT(Box::new(snapshot_with([Some(DeathCause::SELF(1)), None]))));
+        for length in 0..bytes.len() {
+            assert!(decode_server_message(&bytes[..length]).is_none(), "decoded a snapshot cut to {} bytes", length);
+        }
+
+        let input = encode_client_message(&ClientMessage::INPUT { sequence: 1, direction: Some(Direction::UP), speed_up: false });
+        for length in 0..input.len() {
+            assert_eq!(decode_client_message(&input[..length]), None);
+        }
+    }
+
+    #[test]
+    fn snapshots_with_the_wrong_player_count_are_dropped() {
+        let mut snapshot = snapshot_with([None, None]);
+        snapshot.players.truncate(1);
+        let bytes = encode_server_message(&ServerMessage::SNAPSHOT(Box::new(snapshot)));
+        assert!(decode_server_message(&bytes).is_none());
+
+        let mut snapshot = snapshot_with([None, None]);
+        snapshot.mode = GameMode::SOLO;
+        assert!(decode_server_message(&encode_server_message(&ServerMessage::SNAPSHOT(Box::new(snapshot)))).is_none());
+    }
+
+    #[test]
+    fn snakes_off_the_map_are_dropped() {
+        for x in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let mut snapshot = snapshot_with([None, None]);
+            snapshot.players[1].snake.parts[0].x = x;
+            let bytes = encode_server_message(&ServerMessage::SNAPSHOT(Box::new(snapshot)));
+            assert!(decode_server_message(&bytes).is_none(), "decoded a snake part at x={}", x);
+        }
+    }
+
+    #[test]
+    fn other_versions_and_protocols_are_ignored() {
+        let mut bytes = encode_client_message(&ClientMessage::JOIN);
+        bytes[2] = PROTOCOL_VERSION - 1;
+        assert_eq!(decode_client_message(&bytes), None);
+
+        let mut bytes = encode_server_message(&ServerMessage::FULL);
+        bytes[0] ^= 0xFF;
+        assert!(decode_server_message(&bytes).is_none());
+
+        // A client message type sent to a client
+        assert!(decode_server_message(&encode_client_message(&ClientMessage::JOIN)).is_none());
+    }
+
+    #[test]
+    fn snapshots_are_only_stale_within_a_match() {
+        let mut game = Game::new(GameMode::VERSUS, game::play_area_for_screen(640, 480), 1);
+        game.tick = 50;
+        let mut snapshot = Snapshot::from_game(&game, 3, false, Vec::new());
+        snapshot.tick = 10;
+        assert!(snapshot.is_stale(Some(&game), 3));
+        // The server started a new match, back at tick 10 of round 1
+        assert!(!snapshot.is_stale(Some(&game), 2));
+        assert!(!snapshot.is_stale(None, 3));
+
+        snapshot.round = game.round + 1;
+        assert!(!snapshot.is_stale(Some(&game), 3));
+    }
+}
@@ -0,0 +1,116 @@
+use raylib::prelude::*;
+
+use snake::food::FoodType;
+
+// @note: Every colour that tells the player something (whose snake, what kind
+//  of food, good news or danger) comes from here instead of being written
+//  where it's drawn, so it can be swapped for colours that stay apart with
+//  colour blindness. The colour blind palettes are built from the Okabe-Ito
+//  colours, which stay distinct for all three kinds, and lean on lightness
+//  as well as hue. Purely decorative colours (the background, the UI) stay
+//  as they are.
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Palette {
+    STANDARD,
+    // Red weak
+    PROTANOPIA,
+    // Green weak
+    DEUTERANOPIA,
+    // Blue weak
+    TRITANOPIA
+}
+
+pub const PALETTES: [Palette; 4] = [Palette::STANDARD, Palette::PROTANOPIA, Palette::DEUTERANOPIA, Palette::TRITANOPIA];
+
+pub fn palette_name(palette: Palette) -> &'static str {
+    match palette {
+        Palette::STANDARD     => "Standard",
+        Palette::PROTANOPIA   => "Protanopia",
+        Palette::DEUTERANOPIA => "Deuteranopia",
+        Palette::TRITANOPIA   => "Tritanopia",
+    }
+}
+
+pub struct Colors {
+    // Tints the snake sprites, per player
+    pub players:        [Color; 2],
+    // The same players in text on the light background
+    pub player_text:    [Color; 2],
+    pub golden:         Color,
+    // Bonuses and high scores
+    pub good:           Color,
+    // Boosting, time running short
+    pub warning:        Color,
+    // What killed you, time nearly up
+    pub danger:         Color,
+    // Bursts when an apple is eaten
+    pub sparkle:        Color
+}
+
+const ORANGE:         Color = Color { r: 230, g: 159, b: 0,   a: 255 };
+const SKY_BLUE:       Color = Color { r: 86,  g: 180, b: 233, a: 255 };
+const BLUISH_GREEN:   Color = Color { r: 0,   g: 158, b: 115, a: 255 };
+const YELLOW:         Color = Color { r: 240, g: 228, b: 66,  a: 255 };
+const BLUE:           Color = Color { r: 0,   g: 114, b: 178, a: 255 };
+const VERMILLION:     Color = Color { r: 213, g: 94,  b: 0,   a: 255 };
+const REDDISH_PURPLE: Color = Color { r: 204, g: 121, b: 167, a: 255 };
+
+pub fn colors(palette: Palette) -> Colors {
+    match palette {
+        Palette::STANDARD => Colors {
+            players:        [Color::WHITE, Color::SKYBLUE],
+            player_text:    [Color::BLACK, Color::DARKBLUE],
+            golden:         Color::GOLD,
+            good:           Color::DARKGREEN,
+            warning:        Color::ORANGE,
+            danger:         Color::RED,
+            sparkle:        Color { r: 0, g: 121, b: 241, a: 255 }
+        },
+        // Red and green both look like muddy yellows, so it's blue against
+        //  orange and a lot of difference in lightness
+        Palette::PROTANOPIA => Colors {
+            players:        [Color::WHITE, BLUE],
+            player_text:    [Color::BLACK, BLUE],
+            golden:         YELLOW,
+            good:           BLUE,
+            warning:        ORANGE,
+            danger:         VERMILLION,
+            sparkle:        SKY_BLUE
+        },
+        Palette::DEUTERANOPIA => Colors {
+            players:        [Color::WHITE, BLUE],
+            player_text:    [Color::BLACK, BLUE],
+            golden:         YELLOW,
+            good:           SKY_BLUE,
+            warning:        ORANGE,
+            danger:         REDDISH_PURPLE,
+            sparkle:        SKY_BLUE
+        },
+        // Blue runs into green and yellow into pink, reds hold up
+        Palette::TRITANOPIA => Colors {
+            players:        [Color::WHITE, VERMILLION],
+            player_text:    [Color::BLACK, VERMILLION],
+            golden:         Color::GOLD,
+            good:           BLUISH_GREEN,
+            warning:        REDDISH_PURPLE,
+            danger:         Color { r: 200, g: 0, b: 40, a: 255 },
+            sparkle:        VERMILLION
+        },
+    }
+}
+
+pub fn player_tint(palette: Palette, player_index: usize) -> Color {
+    colors(palette).players[player_index.min(1)]
+}
+
+pub fn player_text_color(palette: Palette, player_index: usize) -> Color {
+    colors(palette).player_text[player_index.min(1)]
+}
+
+pub fn food_tint(palette: Palette, food_type: FoodType) -> Color {
+    match food_type {
+        FoodType::APPLE  => Color::WHITE,
+        FoodType::GOLDEN => colors(palette).golden,
+    }
+}
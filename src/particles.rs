@@ -2,9 +2,155 @@ use raylib::prelude::*;
 use rand::seq::SliceRandom;
 use rand::*;
 
-#[derive(Debug)]
-pub struct ParticleSystem {
-    particles: Vec<Particle>
+// @note: Particles live in one pool that is allocated up front and recycled,
+//  dead slots go on a free list and new particles take them from there. When
+//  the pool is full new particles are simply not spawned, which is a lot less
+//  noticeable than stealing ones that are still on screen.
+//
+// Emitters are what spawn them. An emitter has a burst that goes off when it
+//  starts and a rate it keeps spawning at for `duration` seconds (or until it
+//  is released), so a one off puff and a trail that follows the head are the
+//  same thing with different settings.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmitterShape {
+    POINT,
+    CIRCLE(f32),
+    // The other end of the line, relative to the emitter's position
+    LINE(Vector2),
+    // Width and height, centered on the emitter
+    RECTANGLE(f32, f32)
+}
+
+// What a single particle looks like
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParticleLook {
+    CIRCLE,
+    SQUARE,
+    // A streak along the direction it's moving, longer the faster it goes
+    SPARK,
+    // A cell of the particle atlas, indexed like ATLAS_SPRITES
+    SPRITE(usize)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParticleBlend {
+    ALPHA,
+    // Adds to what's underneath, overlapping particles glow brighter
+    ADDITIVE
+}
+
+// A size of 1.0 in pixels, also the size of a cell in the particle atlas
+pub const PARTICLE_SIZE: f32 = 20.0;
+
+// The cells of assets\sprites\particles_20x20.png from left to right
+pub const ATLAS_SPRITES: [&str; 4] = ["glow", "star", "ring", "puff"];
+
+// How much longer a spark gets per pixel per second of speed
+const SPARK_STRETCH: f32 = 0.04;
+
+#[derive(Clone, Debug)]
+pub struct EmitterSettings {
+    pub shape:              EmitterShape,
+    // Spawned all at once when the emitter starts
+    pub burst:              u32,
+    // Particles per second after that
+    pub rate:               f32,
+    // How long the emitter keeps spawning at `rate`, None for until it's stopped
+    pub duration:           Option<f32>,
+    // Seconds, picked per particle between the two
+    pub lifetime:           (f32, f32),
+    pub speed:              (f32, f32),
+    // Degrees, 0 is to the right and positive is clockwise like raylib. The
+    //  velocity is picked inside a cone of `spread` degrees around it.
+    pub direction:          f32,
+    pub spread:             f32,
+    // Pixels per second squared
+    pub gravity:            Vector2,
+    // Fraction of the velocity lost per second
+    pub drag:               f32,
+    // Every particle picks one of these when it spawns...
+    pub colors:             Vec<Color>,
+    // ...which is then tinted by this over its life, (time 0 to 1, color)
+    pub color_over_life:    Vec<(f32, Color)>,
+    // Size over life in PARTICLE_SIZEs, (time 0 to 1, size)
+    pub size_over_life:     Vec<(f32, f32)>,
+    // Fades alpha out towards the end of the particle's life
+    pub fade:               bool,
+    pub look:               ParticleLook,
+    pub blend:              ParticleBlend,
+    // Degrees and degrees per second, picked per particle between the two
+    pub rotation:           (f32, f32),
+    pub angular_velocity:   (f32, f32)
+}
+
+impl Default for EmitterSettings {
+    fn default() -> Self {
+        EmitterSettings {
+            shape:              EmitterShape::POINT,
+            burst:              0,
+            rate:               0.0,
+            duration:           Some(0.0),
+            lifetime:           (1.0, 1.0),
+            speed:              (0.0, 0.0),
+            direction:          0.0,
+            spread:             360.0,
+            gravity:            Vector2 { x: 0.0, y: 0.0 },
+            drag:               0.0,
+            colors:             vec![Color::WHITE],
+            color_over_life:    Vec::new(),
+            size_over_life:     Vec::new(),
+            fade:               false,
+            look:               ParticleLook::CIRCLE,
+            blend:              ParticleBlend::ALPHA,
+            rotation:           (0.0, 0.0),
+            angular_velocity:   (0.0, 0.0)
+        }
+    }
+}
+
+// Linear interpolation between the keys around `time`, keys must be sorted by time
+fn sample_curve(keys: &[(f32, f32)], time: f32, default: f32) -> f32 {
+    let Some(first) = keys.first() else { return default };
+    if time <= first.0 {
+        return first.1;
+    }
+
+    for pair in keys.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        if time <= to.0 {
+            let amount = if to.0 > from.0 { (time - from.0) / (to.0 - from.0) } else { 1.0 };
+            return lerp(from.1, to.1, amount);
+        }
+    }
+    keys.last().unwrap().1
+}
+
+fn sample_gradient(keys: &[(f32, Color)], time: f32) -> Color {
+    let channel = |pick: fn(&Color) -> u8| -> u8 {
+        let channel_keys: Vec<(f32, f32)> = keys.iter().map(|(time, color)| (*time, pick(color) as f32)).collect();
+        sample_curve(&channel_keys, time, 255.0).round().clamp(0.0, 255.0) as u8
+    };
+
+    Color {
+        r: channel(|color| color.r),
+        g: channel(|color| color.g),
+        b: channel(|color| color.b),
+        a: channel(|color| color.a)
+    }
+}
+
+fn multiply_colors(a: Color, b: Color) -> Color {
+    Color {
+        r: (a.r as u16 * b.r as u16 / 255) as u8,
+        g: (a.g as u16 * b.g as u16 / 255) as u8,
+        b: (a.b as u16 * b.b as u16 / 255) as u8,
+        a: (a.a as u16 * b.a as u16 / 255) as u8
+    }
+}
+
+fn random_between(rng: &mut impl Rng, range: (f32, f32)) -> f32 {
+    if range.1 > range.0 { rng.gen_range(range.0..range.1) } else { range.0 }
 }
 
 #[derive(Debug)]
@@ -12,92 +158,311 @@ pub struct Particle {
     position:   Vector2,
     velocity:   Vector2,
     color:      Color,
+    // Degrees
+    rotation:   f32,
+    angular_velocity: f32,
+    age:        f32,
     lifetime:   f32,
-    scale:      f32
+    alive:      bool,
+    // Which emitter's settings it follows
+    emitter:    usize
 }
 
 impl Particle {
-    pub fn step(self: &mut Self, timestep: f32) {
-        self.position.x = self.position.x + self.velocity.x * timestep;
-        self.position.y = self.position.y + self.velocity.y * timestep;
-        if self.lifetime > 0.0 {
-            self.lifetime -= timestep;
+    pub fn step(self: &mut Self, timestep: f32, settings: &EmitterSettings) {
+        self.velocity.x += settings.gravity.x * timestep;
+        self.velocity.y += settings.gravity.y * timestep;
+        let keep = (1.0 - settings.drag * timestep).max(0.0);
+        self.velocity.x *= keep;
+        self.velocity.y *= keep;
+
+        self.position.x += self.velocity.x * timestep;
+        self.position.y += self.velocity.y * timestep;
+        self.rotation  += self.angular_velocity * timestep;
+
+        self.age += timestep;
+        if self.age >= self.lifetime {
+            self.alive = false;
         }
     }
 
-    pub fn draw(self: &Self, draw_context: &mut RaylibDrawHandle, texture: &Texture2D) {
-        if self.lifetime < 0.0 { return; }
+    pub fn draw(self: &Self, draw_context: &mut impl RaylibDraw, atlas: &Texture2D, settings: &EmitterSettings) {
+        if !self.alive { return; }
 
-        let destination_size = Vector2 {
-            x: self.scale * texture.width  as f32, 
-            y: self.scale * texture.height as f32
-        };
+        let life = (self.age / self.lifetime).clamp(0.0, 1.0);
+
+        let scale = sample_curve(&settings.size_over_life, life, 1.0);
+        let mut color = self.color;
+        if !settings.color_over_life.is_empty() {
+            color = multiply_colors(color, sample_gradient(&settings.color_over_life, life));
+        }
+        if settings.fade {
+            color = color.fade(color.a as f32 / 255.0 * (1.0 - life));
+        }
 
-        draw_context.draw_texture_pro(
-            texture, 
-            Rectangle {
-                x: 0.0,
-                y: 0.0,
-                width:  texture.width  as f32,
-                height: texture.height as f32
+        let size = scale * PARTICLE_SIZE;
+        match settings.look {
+            ParticleLook::CIRCLE => {
+                draw_context.draw_circle_v(self.position, size / 2.0, color);
+            },
+            ParticleLook::SQUARE => {
+                draw_context.draw_rectangle_pro(
+                    Rectangle { x: self.position.x, y: self.position.y, width: size, height: size },
+                    Vector2 { x: size / 2.0, y: size / 2.0 },
+                    self.rotation,
+                    color
+                );
             },
-            Rectangle {
-                x: self.position.x + destination_size.x / 2.0, 
-                y: self.position.y + destination_size.y / 2.0,
-                width:  destination_size.x,
-                height: destination_size.y
+            ParticleLook::SPARK => {
+                let speed = self.velocity.length();
+                if speed <= 0.0 {
+                    return;
+                }
+                let length = size * (1.0 + speed * SPARK_STRETCH);
+                let tail = Vector2 {
+                    x: self.position.x - self.velocity.x / speed * length,
+                    y: self.position.y - self.velocity.y / speed * length
+                };
+                draw_context.draw_line_ex(tail, self.position, (size * 0.2).max(1.0), color);
             },
-            Vector2 { 
-                x: destination_size.x / 2.0,
-                y: destination_size.y / 2.0 
+            ParticleLook::SPRITE(index) => {
+                draw_context.draw_texture_pro(
+                    atlas,
+                    Rectangle {
+                        x: index as f32 * PARTICLE_SIZE,
+                        y: 0.0,
+                        width:  PARTICLE_SIZE,
+                        height: PARTICLE_SIZE
+                    },
+                    Rectangle {
+                        x: self.position.x,
+                        y: self.position.y,
+                        width:  size,
+                        height: size
+                    },
+                    Vector2 {
+                        x: size / 2.0,
+                        y: size / 2.0
+                    },
+                    self.rotation,
+                    color
+                );
             },
-            self.lifetime * 360.0,
-            self.color
-        );
+        }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmitterId(usize);
+
+#[derive(Debug)]
+struct Emitter {
+    settings:           EmitterSettings,
+    position:           Vector2,
+    // Seconds since it started
+    time:               f32,
+    // Fractional particles owed at `rate`, carried between steps
+    spawn_debt:         f32,
+    emitting:           bool,
+    // Nobody holds on to it any more, it goes away once its particles are gone
+    released:           bool,
+    live_particles:     usize
+}
+
+#[derive(Debug)]
+pub struct ParticleSystem {
+    particles:  Vec<Particle>,
+    free:       Vec<usize>,
+    // Slots are reused once an emitter is released and has no particles left
+    emitters:   Vec<Option<Emitter>>
+}
+
 impl ParticleSystem {
-    pub fn step(self: &mut Self, timestep: f32) {
-        for particle in &mut self.particles {
-            particle.step(timestep);
+    pub fn new(capacity: usize) -> Self {
+        let mut particles = Vec::with_capacity(capacity);
+        for _ in 0..capacity {
+            particles.push(Particle {
+                position:   Vector2 { x: 0.0, y: 0.0 },
+                velocity:   Vector2 { x: 0.0, y: 0.0 },
+                color:      Color::WHITE,
+                rotation:   0.0,
+                angular_velocity: 0.0,
+                age:        0.0,
+                lifetime:   0.0,
+                alive:      false,
+                emitter:    0
+            });
+        }
+
+        ParticleSystem {
+            particles:  particles,
+            // Reversed so slot 0 is handed out first
+            free:       (0..capacity).rev().collect(),
+            emitters:   Vec::new()
         }
     }
 
-    pub fn draw(self: &Self, draw_context: &mut RaylibDrawHandle, texture: &Texture2D) {
-        for particle in &self.particles {
-            particle.draw(draw_context, texture);
+    // Starts an emitter, it keeps spawning until its duration runs out and
+    //  stays around (and can be moved) until it is released.
+    pub fn start(self: &mut Self, settings: &EmitterSettings, position: Vector2) -> EmitterId {
+        let emitter = Emitter {
+            settings:       settings.clone(),
+            position:       position,
+            time:           0.0,
+            spawn_debt:     0.0,
+            emitting:       settings.rate > 0.0,
+            released:       false,
+            live_particles: 0
+        };
+
+        let index = match self.emitters.iter().position(|slot| slot.is_none()) {
+            Some(index) => {
+                self.emitters[index] = Some(emitter);
+                index
+            },
+            None => {
+                self.emitters.push(Some(emitter));
+                self.emitters.len() - 1
+            }
+        };
+
+        self.spawn(index, settings.burst);
+        EmitterId(index)
+    }
+
+    // Fire and forget, e.g. a puff when an apple gets eaten
+    pub fn burst(self: &mut Self, settings: &EmitterSettings, position: Vector2) {
+        let id = self.start(settings, position);
+        self.release(id);
+    }
+
+    pub fn move_to(self: &mut Self, id: EmitterId, position: Vector2) {
+        if let Some(Some(emitter)) = self.emitters.get_mut(id.0) {
+            emitter.position = position;
+        }
+    }
+
+    // Pauses or resumes spawning at the emitter's rate
+    pub fn set_emitting(self: &mut Self, id: EmitterId, emitting: bool) {
+        if let Some(Some(emitter)) = self.emitters.get_mut(id.0) {
+            emitter.emitting = emitting;
+        }
+    }
+
+    // Lets go of the emitter. One without a duration stops spawning right
+    //  away, otherwise it runs out its duration first. Either way it goes away
+    //  by itself once its last particle is gone.
+    pub fn release(self: &mut Self, id: EmitterId) {
+        if let Some(Some(emitter)) = self.emitters.get_mut(id.0) {
+            emitter.released = true;
+            if emitter.settings.duration.is_none() {
+                emitter.emitting = false;
+            }
         }
     }
 
-    pub fn create_radial(lifetime: f32, colors: Vec<Color>, count: u8, starting_pos: Vector2, intensity: f32, scale: f32) -> Self {
-        let mut particles: Vec<Particle> = Vec::new();
+    fn spawn(self: &mut Self, emitter_index: usize, count: u32) {
+        let Some(Some(emitter)) = self.emitters.get_mut(emitter_index) else { return };
+        let settings = &emitter.settings;
         let mut rng = thread_rng();
+
         for _ in 0..count {
-            particles.push(
-                Particle {
-                    position:   starting_pos,
-                    velocity:   Vector2 {
-                        x: rng.gen_range(-1.0..=1.0) * intensity,
-                        y: rng.gen_range(-1.0..=1.0) * intensity
-                    },
-                    color:      colors.choose(&mut rand::thread_rng()).unwrap().clone(),
-                    lifetime:   lifetime,
-                    scale:      scale
-                }
-            );
+            let Some(slot) = self.free.pop() else { return };
+
+            let offset = match settings.shape {
+                EmitterShape::POINT => Vector2 { x: 0.0, y: 0.0 },
+                EmitterShape::CIRCLE(radius) => {
+                    // sqrt keeps them evenly spread instead of bunched in the middle
+                    let angle: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+                    let distance = radius * rng.gen_range(0.0f32..=1.0).sqrt();
+                    Vector2 { x: angle.cos() * distance, y: angle.sin() * distance }
+                },
+                EmitterShape::LINE(end) => {
+                    let along: f32 = rng.gen_range(0.0..=1.0);
+                    Vector2 { x: end.x * along, y: end.y * along }
+                },
+                EmitterShape::RECTANGLE(width, height) => Vector2 {
+                    x: rng.gen_range(-0.5f32..=0.5) * width,
+                    y: rng.gen_range(-0.5f32..=0.5) * height
+                },
+            };
+
+            let angle = (settings.direction + rng.gen_range(-0.5f32..=0.5) * settings.spread).to_radians();
+            let speed = random_between(&mut rng, settings.speed);
+
+            self.particles[slot] = Particle {
+                position:   Vector2 { x: emitter.position.x + offset.x, y: emitter.position.y + offset.y },
+                velocity:   Vector2 { x: angle.cos() * speed, y: angle.sin() * speed },
+                color:      settings.colors.choose(&mut rng).copied().unwrap_or(Color::WHITE),
+                rotation:   random_between(&mut rng, settings.rotation),
+                angular_velocity: random_between(&mut rng, settings.angular_velocity),
+                age:        0.0,
+                lifetime:   random_between(&mut rng, settings.lifetime).max(0.001),
+                alive:      true,
+                emitter:    emitter_index
+            };
+            emitter.live_particles += 1;
         }
+    }
 
-        ParticleSystem {
-            particles: particles
+    pub fn step(self: &mut Self, timestep: f32) {
+        for index in 0..self.emitters.len() {
+            let Some(emitter) = self.emitters[index].as_mut() else { continue };
+            emitter.time += timestep;
+            if emitter.duration_over() {
+                emitter.emitting = false;
+            }
+            if !emitter.emitting {
+                continue;
+            }
+
+            emitter.spawn_debt += emitter.settings.rate * timestep;
+            let count = emitter.spawn_debt.floor();
+            emitter.spawn_debt -= count;
+            self.spawn(index, count as u32);
+        }
+
+        for (index, particle) in self.particles.iter_mut().enumerate() {
+            if !particle.alive {
+                continue;
+            }
+            let Some(Some(emitter)) = self.emitters.get_mut(particle.emitter) else { continue };
+
+            particle.step(timestep, &emitter.settings);
+            if !particle.alive {
+                emitter.live_particles -= 1;
+                self.free.push(index);
+            }
+        }
+
+        for slot in &mut self.emitters {
+            let finished = slot.as_ref().map(|emitter| emitter.released && !emitter.emitting && emitter.live_particles == 0);
+            if finished == Some(true) {
+                *slot = None;
+            }
+        }
+    }
+
+    // Everything blended normally first, then the additive ones on top
+    pub fn draw(self: &Self, draw_context: &mut impl RaylibDraw, atlas: &Texture2D) {
+        self.draw_blended(draw_context, atlas, ParticleBlend::ALPHA);
+
+        let mut additive = draw_context.begin_blend_mode(BlendMode::BLEND_ADDITIVE);
+        self.draw_blended(&mut additive, atlas, ParticleBlend::ADDITIVE);
+    }
+
+    fn draw_blended(self: &Self, draw_context: &mut impl RaylibDraw, atlas: &Texture2D, blend: ParticleBlend) {
+        for particle in &self.particles {
+            let Some(Some(emitter)) = self.emitters.get(particle.emitter) else { continue };
+            if emitter.settings.blend == blend {
+                particle.draw(draw_context, atlas, &emitter.settings);
+            }
         }
     }
+}
 
-    pub fn reset(self: &mut Self, lifetime: f32, starting_pos: Vector2) {
- 	    let mut rng = thread_rng();
-    	for particle in &mut self.particles {
-    		particle.position = starting_pos;
-    		particle.lifetime = lifetime;
-    	}
+impl Emitter {
+    fn duration_over(self: &Self) -> bool {
+        self.settings.duration.map(|duration| self.time >= duration).unwrap_or(false)
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,368 @@
+use crate::food::FoodTable;
+use crate::game::{self, Bounds, Direction, Game, GameMode, CELL_SIZE};
+
+// @note: A game is fully determined by its seed and what the players did
+//  each tick, so that's all a replay keeps. The file is plain text:
+//
+//      version=1
+//      mode=solo
+//      seed=1234
+//      area=20,40,600,400
+//      walls=5:3,6:3
+//      food.apple.duration=4.00
+//      round
+//      R
+//      R+
+//      U
+//
+//  Every line after `round` is one tick with a direction per player (U, L, D
+//  or R) and a `+` while they boost. Walls are cells counted from the area's
+//  top left. Only the food settings that differ from the defaults are written.
+//  Playing it back feeds those into a fresh game the same way the
+//  keyboard or the computer would have.
+
+const REPLAY_VERSION: u32 = 1;
+
+// What every player was steering and whether they boosted, going into a tick
+pub type TickInput = Vec<(Direction, bool)>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    pub mode:       GameMode,
+    pub seed:       u64,
+    pub play_area:  Bounds,
+    pub walls:      Vec<(f32, f32)>,
+    pub food:       FoodTable,
+    // One list of ticks per round
+    pub rounds:     Vec<Vec<TickInput>>
+}
+
+impl Recording {
+    pub fn new(game: &Game, seed: u64) -> Self {
+        Recording {
+            mode:       game.mode,
+            seed:       seed,
+            play_area:  game.play_area,
+            walls:      game.walls.clone(),
+            food:       game.food,
+            rounds:     Vec::new()
+        }
+    }
+
+    // Call right before every step, after the players have steered
+    pub fn record(self: &mut Self, game: &Game) {
+        if self.rounds.len() < game.round as usize {
+            self.rounds.push(Vec::new());
+        }
+        let input = game.players.iter().map(|player| (player.snake.head_direction, player.speed_up)).collect();
+        if let Some(round) = self.rounds.last_mut() {
+            round.push(input);
+        }
+    }
+
+    pub fn ticks(self: &Self) -> usize {
+        self.rounds.iter().map(|round| round.len()).sum()
+    }
+
+    // The game as it was before anyone moved
+    pub fn start(self: &Self) -> Game {
+        let mut game = Game::with_walls(self.mode, self.play_area, self.walls.clone(), self.seed);
+        game.set_food(self.food);
+        game
+    }
+
+    pub fn to_text(self: &Self) -> String {
+        let mut text = format!("version={}\n", REPLAY_VERSION);
+        text += &format!("mode={}\n", map_mode_to_name(self.mode));
+        text += &format!("seed={}\n", self.seed);
+        let area = self.play_area;
+        text += &format!("area={},{},{},{}\n", area.x, area.y, area.width, area.height);
+
+        let walls: Vec<String> = self.walls.iter()
+            .map(|(x, y)| format!("{}:{}", ((x - area.x) / CELL_SIZE) as i32, ((y - area.y) / CELL_SIZE) as i32))
+            .collect();
+        text += &format!("walls={}\n", walls.join(","));
+
+        let defaults = FoodTable::default().entries();
+        for ((key, value), (_, default)) in self.food.entries().iter().zip(defaults) {
+            if *value != default {
+                text += &format!("food.{}={}\n", key, value);
+            }
+        }
+
+        for round in &self.rounds {
+            text += "round\n";
+            for input in round {
+                let players: Vec<String> = input.iter()
+                    .map(|(direction, boosting)| format!("{}{}", map_direction_to_letter(*direction), if *boosting { "+" } else { "" }))
+                    .collect();
+                text += &players.join(" ");
+                text += "\n";
+            }
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Recording, String> {
+        let mut recording = Recording {
+            mode:       GameMode::SOLO,
+            seed:       0,
+            play_area:  game::play_area_for_screen(640, 480),
+            walls:      Vec::new(),
+            food:       FoodTable::default(),
+            rounds:     Vec::new()
+        };
+        let mut wall_cells: Vec<(i32, i32)> = Vec::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "round" {
+                recording.rounds.push(Vec::new());
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim();
+                match key.trim() {
+                    "version" => {
+                        let version = value.parse::<u32>().map_err(|_| format!("Line {}: bad version '{}'", line_number, value))?;
+                        if version > REPLAY_VERSION {
+                            return Err(format!("The replay was recorded by a newer version of the game ({} > {})", version, REPLAY_VERSION));
+                        }
+                    },
+                    "mode" => {
+                        recording.mode = parse_mode(value).ok_or(format!("Line {}: unknown mode '{}'", line_number, value))?;
+                    },
+                    "seed" => {
+                        recording.seed = value.parse::<u64>().map_err(|_| format!("Line {}: bad seed '{}'", line_number, value))?;
+                    },
+                    "area" => {
+                        let numbers: Vec<f32> = value.split(',').filter_map(|number| number.trim().parse::<f32>().ok()).collect();
+                        let [x, y, width, height] = numbers[..] else {
+                            return Err(format!("Line {}: expected area=x,y,width,height", line_number));
+                        };
+                        // The board has to be whole cells, the same as every board the game makes
+                        let on_grid = |size: f32| size > 0.0 && size % CELL_SIZE == 0.0;
+                        if !on_grid(width) || !on_grid(height) || !x.is_finite() || !y.is_finite() {
+                            return Err(format!("Line {}: bad area '{}', the size has to be whole cells of {}", line_number, value, CELL_SIZE));
+                        }
+                        recording.play_area = Bounds { x: x, y: y, width: width, height: height };
+                    },
+                    "walls" => {
+                        for cell in value.split(',').filter(|cell| !cell.trim().is_empty()) {
+                            let parsed = cell.split_once(':')
+                                .and_then(|(column, row)| Some((column.trim().parse::<i32>().ok()?, row.trim().parse::<i32>().ok()?)));
+                            wall_cells.push(parsed.ok_or(format!("Line {}: bad wall '{}', expected column:row", line_number, cell))?);
+                        }
+                    },
+                    other if other.starts_with("food.") => {
+                        recording.food.set(&other["food.".len()..], value).map_err(|error| format!("Line {}: {}", line_number, error))?;
+                    },
+                    other => return Err(format!("Line {}: unknown key '{}'", line_number, other)),
+                }
+                continue;
+            }
+
+            let input = line.split_whitespace()
+                .map(parse_player_input)
+                .collect::<Option<TickInput>>()
+                .ok_or(format!("Line {}: bad tick '{}'", line_number, line))?;
+            if input.len() != recording.mode.player_count() {
+                return Err(format!("Line {}: {} players in a tick, the mode has {}", line_number, input.len(), recording.mode.player_count()));
+            }
+            let round = recording.rounds.last_mut().ok_or(format!("Line {}: tick before the first round", line_number))?;
+            round.push(input);
+        }
+
+        let area = recording.play_area;
+        recording.walls = wall_cells.iter()
+            .map(|(column, row)| (area.x + *column as f32 * CELL_SIZE, area.y + *row as f32 * CELL_SIZE))
+            .collect();
+        Ok(recording)
+    }
+}
+
+// @note: Feeds a recording into a game one tick at a time, the game itself
+//  comes from `Recording::start`. Rounds are started by the caller, the same
+//  way the round over screen does it.
+pub struct Replayer {
+    round:  usize,
+    tick:   usize
+}
+
+impl Default for Replayer {
+    fn default() -> Self {
+        Replayer::new()
+    }
+}
+
+impl Replayer {
+    pub fn new() -> Self {
+        Replayer { round: 0, tick: 0 }
+    }
+
+    pub fn has_next(self: &Self, recording: &Recording) -> bool {
+        recording.rounds.get(self.round).is_some_and(|round| self.tick < round.len())
+    }
+
+    // Puts the next tick's input on the players, false once the round's
+    //  recording has run out
+    pub fn apply_next(self: &mut Self, game: &mut Game, recording: &Recording) -> bool {
+        let Some(input) = recording.rounds.get(self.round).and_then(|round| round.get(self.tick)) else {
+            return false;
+        };
+        for (player, (direction, boosting)) in game.players.iter_mut().zip(input) {
+            player.snake.head_direction = *direction;
+            player.speed_up = *boosting;
+        }
+        self.tick += 1;
+        true
+    }
+
+    // Starts the next recorded round, false if this was the last one
+    pub fn next_round(self: &mut Self, game: &mut Game, recording: &Recording) -> bool {
+        if self.round + 1 >= recording.rounds.len() {
+            return false;
+        }
+        self.round += 1;
+        self.tick = 0;
+        game.start_round();
+        true
+    }
+}
+
+// Plays the whole recording without stopping and returns where it ended up
+pub fn play_to_end(recording: &Recording) -> Game {
+    let mut game = recording.start();
+    let mut replayer = Replayer::new();
+    loop {
+        while !game.round_over && replayer.apply_next(&mut game, recording) {
+            game.step();
+        }
+        if !replayer.next_round(&mut game, recording) {
+            return game;
+        }
+    }
+}
+
+pub fn map_mode_to_name(mode: GameMode) -> &'static str {
+    match mode {
+        GameMode::SOLO   => "solo",
+        GameMode::VERSUS => "versus",
+    }
+}
+
+fn parse_mode(name: &str) -> Option<GameMode> {
+    [GameMode::SOLO, GameMode::VERSUS].into_iter().find(|mode| map_mode_to_name(*mode) == name)
+}
+
+fn map_direction_to_letter(direction: Direction) -> char {
+    match direction {
+        Direction::UP    => 'U',
+        Direction::LEFT  => 'L',
+        Direction::DOWN  => 'D',
+        Direction::RIGHT => 'R',
+    }
+}
+
+fn parse_player_input(text: &str) -> Option<(Direction, bool)> {
+    let mut letters = text.chars();
+    let direction = match letters.next()? {
+        'U' => Direction::UP,
+        'L' => Direction::LEFT,
+        'D' => Direction::DOWN,
+        'R' => Direction::RIGHT,
+        _   => return None,
+    };
+    match letters.as_str() {
+        ""  => Some((direction, false)),
+        "+" => Some((direction, true)),
+        _   => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{self, Strategy};
+
+    // A versus match between two computer players on a board with walls and
+    //  changed food, recorded the way the game records one
+    fn recorded_match() -> (Recording, Game) {
+        let area = game::play_area_for_screen(640, 480);
+        let walls = vec![(area.x + 10.0 * CELL_SIZE, area.y + 2.0 * CELL_SIZE), (area.x + 11.0 * CELL_SIZE, area.y + 2.0 * CELL_SIZE)];
+        let mut game = Game::with_walls(GameMode::VERSUS, area, walls, 77);
+        let mut food = FoodTable::default();
+        food.set("golden.spawn_weight", "5").unwrap();
+        food.set("apple.duration", "6.5").unwrap();
+        game.set_food(food);
+
+        let mut recording = Recording::new(&game, 77);
+        let strategies = [Some(Strategy::GREEDY), Some(Strategy::PATHFINDING)];
+        while recording.ticks() < 3000 {
+            if game.round_over {
+                if game.round == 3 || game.is_match_over() {
+                    break;
+                }
+                game.start_round();
+                continue;
+            }
+            ai::steer_ai_players(&mut game, &strategies);
+            game.players[0].speed_up = game.tick % 11 < 3;
+            recording.record(&game);
+            game.step();
+        }
+        (recording, game)
+    }
+
+    #[test]
+    fn recordings_survive_the_text_format() {
+        let (recording, _) = recorded_match();
+        let text = recording.to_text();
+        assert!(text.contains("food.golden.spawn_weight=5"));
+        assert!(text.contains("walls=10:2,11:2"));
+        assert_eq!(Recording::from_text(&text), Ok(recording));
+    }
+
+    #[test]
+    fn playing_back_a_parsed_recording_ends_the_same() {
+        let (recording, game) = recorded_match();
+        assert!(recording.rounds.len() > 1, "the match only lasted one round");
+        let replayed = play_to_end(&Recording::from_text(&recording.to_text()).unwrap());
+
+        assert_eq!(replayed.round, game.round);
+        assert_eq!(replayed.tick, game.tick);
+        assert_eq!(replayed.round_over, game.round_over);
+        assert_eq!(replayed.apple, game.apple);
+        for (replayed, player) in replayed.players.iter().zip(&game.players) {
+            assert_eq!(replayed.snake, player.snake);
+            assert_eq!(replayed.score, player.score);
+            assert_eq!(replayed.round_wins, player.round_wins);
+            assert_eq!(replayed.death, player.death);
+        }
+    }
+
+    #[test]
+    fn areas_off_the_grid_are_errors() {
+        for area in ["20,40,0,400", "20,40,-600,400", "20,40,610,400", "20,40,600,inf", "20,40,600,NaN", "inf,40,600,400"] {
+            let text = format!("version=1\nmode=solo\nseed=1\narea={}\nround\nR\n", area);
+            assert!(Recording::from_text(&text).is_err(), "accepted area={}", area);
+        }
+        let recording = Recording::from_text("version=1\nmode=solo\nseed=1\narea=0,0,200,100\nround\nR\n").unwrap();
+        assert_eq!(recording.play_area, Bounds { x: 0.0, y: 0.0, width: 200.0, height: 100.0 });
+    }
+
+    #[test]
+    fn bad_lines_are_errors() {
+        assert!(Recording::from_text("version=99\n").is_err());
+        assert!(Recording::from_text("mode=race\n").is_err());
+        assert!(Recording::from_text("R\n").is_err());
+        assert!(Recording::from_text("mode=versus\nround\nR\n").is_err());
+        assert!(Recording::from_text("round\nX\n").is_err());
+        assert!(Recording::from_text("walls=1-2\n").is_err());
+    }
+}
@@ -0,0 +1,874 @@
+use std::fs;
+use std::path::PathBuf;
+
+use raylib::prelude::*;
+use raylib::core::input::key_from_i32;
+
+use snake::ai::{self, Strategy};
+use snake::food::FoodTable;
+use snake::game::{self, Bounds, CELL_SIZE};
+use snake::storage;
+
+use crate::camera::{self, MotionLevel};
+use crate::display::Frame;
+use crate::palette::{self, Palette};
+use crate::ui::{self, Ui};
+
+// @note: Everything the player can change from the settings menu. The menu
+//  edits this directly and the game loop reads it from here, so a change is
+//  picked up on the next frame (or the next game for the board size).
+//
+// Settings live in the config directory as `section.name=value` lines under
+//  a `version=N` line. Every value is checked when it's read, numbers out of
+//  range are clamped and anything that can't be understood keeps its default,
+//  with a WARN either way so a typo doesn't go unnoticed. `--set key=value` on
+//  the command line overrides a setting for one run without touching the file.
+
+const SETTINGS_FILE:    &str = "settings.txt";
+const SETTINGS_VERSION: u32  = 1;
+
+// @note: Each step rewrites the entries of a file from one version into the
+//  next, MIGRATIONS[0] takes version 1 to 2 and so on, so an old file goes
+//  through every step after it in order. Renaming or reinterpreting a key
+//  means bumping SETTINGS_VERSION and adding a step here.
+type Migration = fn(&mut Vec<(String, String)>);
+const MIGRATIONS: [Migration; SETTINGS_VERSION as usize - 1] = [];
+
+// 0 leaves the frame rate uncapped
+const MIN_TARGET_FPS: u32 = 30;
+const MAX_TARGET_FPS: u32 = 500;
+
+// The highest key code raylib knows about (KEY_KB_MENU)
+const MAX_KEY_CODE: i32 = 348;
+
+// Of the in-game text, the menus keep their size so their layout holds
+const MIN_UI_SCALE: f32 = 0.75;
+const MAX_UI_SCALE: f32 = 1.5;
+// What the menu offers, the file takes anything in between
+const UI_SCALES: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+
+const CONTROL_NAMES: [&str; 5] = ["up", "left", "down", "right", "boost"];
+
+// The menus and the window take these whatever the bindings say
+const RESERVED_KEYS: [KeyboardKey; 4] = [KeyboardKey::KEY_ENTER, KeyboardKey::KEY_BACKSPACE, KeyboardKey::KEY_ESCAPE, KeyboardKey::KEY_F11];
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct PlayerControls {
+    pub up:     KeyboardKey,
+    pub left:   KeyboardKey,
+    pub down:   KeyboardKey,
+    pub right:  KeyboardKey,
+    pub boost:  KeyboardKey
+}
+
+impl PlayerControls {
+    // In the order of CONTROL_NAMES
+    fn keys(self: &Self) -> [KeyboardKey; 5] {
+        [self.up, self.left, self.down, self.right, self.boost]
+    }
+
+    fn key_mut(self: &mut Self, control: &str) -> &mut KeyboardKey {
+        match control {
+            "up"    => &mut self.up,
+            "left"  => &mut self.left,
+            "down"  => &mut self.down,
+            "right" => &mut self.right,
+            _       => &mut self.boost,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum BoardSize {
+    SMALL,
+    MEDIUM,
+    LARGE
+}
+
+pub const BOARD_SIZES: [BoardSize; 3] = [BoardSize::SMALL, BoardSize::MEDIUM, BoardSize::LARGE];
+
+pub fn board_size_name(size: BoardSize) -> &'static str {
+    match size {
+        BoardSize::SMALL  => "Small",
+        BoardSize::MEDIUM => "Medium",
+        BoardSize::LARGE  => "Large",
+    }
+}
+
+// Tints the background, the board keeps working the same
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Theme {
+    MEADOW,
+    DUSK,
+    NIGHT
+}
+
+pub const THEMES: [Theme; 3] = [Theme::MEADOW, Theme::DUSK, Theme::NIGHT];
+
+pub fn theme_name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::MEADOW => "Meadow",
+        Theme::DUSK   => "Dusk",
+        Theme::NIGHT  => "Night",
+    }
+}
+
+pub fn theme_background_tint(theme: Theme) -> Color {
+    match theme {
+        Theme::MEADOW => Color::WHITE,
+        Theme::DUSK   => Color { r: 255, g: 205, b: 170, a: 255 },
+        Theme::NIGHT  => Color { r: 140, g: 150, b: 190, a: 255 },
+    }
+}
+
+// How the background picture covers the board
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum BackgroundFit {
+    TILED,
+    STRETCHED
+}
+
+pub const BACKGROUND_FITS: [BackgroundFit; 2] = [BackgroundFit::TILED, BackgroundFit::STRETCHED];
+
+pub fn background_fit_name(fit: BackgroundFit) -> &'static str {
+    match fit {
+        BackgroundFit::TILED     => "Tiled",
+        BackgroundFit::STRETCHED => "Stretched",
+    }
+}
+
+// What marks out the cells on the board, if anything
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum BoardGrid {
+    OFF,
+    CHECKERBOARD,
+    LINES
+}
+
+pub const BOARD_GRIDS: [BoardGrid; 3] = [BoardGrid::OFF, BoardGrid::CHECKERBOARD, BoardGrid::LINES];
+
+pub fn board_grid_name(grid: BoardGrid) -> &'static str {
+    match grid {
+        BoardGrid::OFF          => "Off",
+        BoardGrid::CHECKERBOARD => "Checkerboard",
+        BoardGrid::LINES        => "Lines",
+    }
+}
+
+pub struct Settings {
+    pub target_fps:         u32,
+    pub fullscreen:         bool,
+    // Only scale the picture up in whole steps so the pixel art stays sharp
+    pub integer_scaling:    bool,
+    pub music_volume:       f32,
+    pub effects_volume:     f32,
+    pub computer_strategy:  Strategy,
+    pub board_size:         BoardSize,
+    pub theme:              Theme,
+    pub background_fit:     BackgroundFit,
+    pub grid:               BoardGrid,
+    pub motion:             MotionLevel,
+    // Keeps the apple from blinking and the fatal cell from pulsing
+    pub reduce_flashing:    bool,
+    pub palette:            Palette,
+    // Outlines the snakes and the food
+    pub high_contrast:      bool,
+    // Gives every kind of food its own shape, not just its own colour
+    pub food_shapes:        bool,
+    pub ui_scale:           f32,
+    // How long each food lasts and what it's worth, only in the file
+    pub food:               FoodTable,
+    // @note: Player one starts on WASD + Space, player two on the arrow keys
+    pub controls:           [PlayerControls; 2]
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Settings {
+            target_fps:         0,
+            fullscreen:         false,
+            integer_scaling:    false,
+            music_volume:       1.0,
+            effects_volume:     1.0,
+            computer_strategy:  Strategy::PATHFINDING,
+            board_size:         BoardSize::LARGE,
+            theme:              Theme::MEADOW,
+            background_fit:     BackgroundFit::STRETCHED,
+            grid:               BoardGrid::OFF,
+            motion:             MotionLevel::FULL,
+            reduce_flashing:    false,
+            palette:            Palette::STANDARD,
+            high_contrast:      false,
+            food_shapes:        false,
+            ui_scale:           1.0,
+            food:               FoodTable::default(),
+            controls:           [
+                PlayerControls {
+                    up:     KeyboardKey::KEY_W,
+                    left:   KeyboardKey::KEY_A,
+                    down:   KeyboardKey::KEY_S,
+                    right:  KeyboardKey::KEY_D,
+                    boost:  KeyboardKey::KEY_SPACE
+                },
+                PlayerControls {
+                    up:     KeyboardKey::KEY_UP,
+                    left:   KeyboardKey::KEY_LEFT,
+                    down:   KeyboardKey::KEY_DOWN,
+                    right:  KeyboardKey::KEY_RIGHT,
+                    boost:  KeyboardKey::KEY_RIGHT_SHIFT
+                }
+            ]
+        }
+    }
+
+    pub fn controls_for(self: &Self, player_index: usize) -> PlayerControls {
+        self.controls[player_index.min(self.controls.len() - 1)]
+    }
+
+    // Every setting as it's written to the file, in file order
+    pub fn entries(self: &Self) -> Vec<(String, String)> {
+        let mut entries = vec![
+            ("graphics.theme".to_string(),       theme_name(self.theme).to_lowercase()),
+            ("graphics.background".to_string(),  background_fit_name(self.background_fit).to_lowercase()),
+            ("graphics.grid".to_string(),        board_grid_name(self.grid).to_lowercase()),
+            ("graphics.target_fps".to_string(),  self.target_fps.to_string()),
+            ("graphics.fullscreen".to_string(),  self.fullscreen.to_string()),
+            ("graphics.integer_scaling".to_string(), self.integer_scaling.to_string()),
+            ("audio.music_volume".to_string(),   format!("{:.2}", self.music_volume)),
+            ("audio.effects_volume".to_string(), format!("{:.2}", self.effects_volume)),
+        ];
+        for (index, controls) in self.controls.iter().enumerate() {
+            for (name, key) in CONTROL_NAMES.iter().zip(controls.keys()) {
+                entries.push((format!("controls.p{}_{}", index + 1, name), key_id(key)));
+            }
+        }
+        entries.push(("gameplay.computer".to_string(),   ai::strategy_name(self.computer_strategy).to_string()));
+        entries.push(("gameplay.board_size".to_string(), board_size_name(self.board_size).to_lowercase()));
+        for (key, value) in self.food.entries() {
+            entries.push((format!("gameplay.{}", key), value));
+        }
+        entries.push(("accessibility.motion".to_string(),          camera::motion_level_name(self.motion).to_lowercase()));
+        entries.push(("accessibility.reduce_flashing".to_string(), self.reduce_flashing.to_string()));
+        entries.push(("accessibility.palette".to_string(),         palette::palette_name(self.palette).to_lowercase()));
+        entries.push(("accessibility.high_contrast".to_string(),   self.high_contrast.to_string()));
+        entries.push(("accessibility.food_shapes".to_string(),     self.food_shapes.to_string()));
+        entries.push(("accessibility.ui_scale".to_string(),        format!("{:.2}", self.ui_scale)));
+        entries
+    }
+
+    // Sets one setting from its file form. Numbers out of range are clamped
+    //  with a warning, anything else that doesn't fit is an error and the
+    //  setting is left as it was.
+    pub fn set(self: &mut Self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match key {
+            "graphics.theme"       => self.theme = parse_named(key, value, &THEMES, theme_name)?,
+            "graphics.background"  => self.background_fit = parse_named(key, value, &BACKGROUND_FITS, background_fit_name)?,
+            "graphics.grid"        => self.grid = parse_named(key, value, &BOARD_GRIDS, board_grid_name)?,
+            "graphics.target_fps"  => {
+                let fps = parse_number::<u32>(key, value)?;
+                self.target_fps = if fps == 0 { 0 } else { clamped(key, fps, MIN_TARGET_FPS, MAX_TARGET_FPS) };
+            },
+            "audio.music_volume"   => self.music_volume   = clamped(key, parse_number::<f32>(key, value)?, 0.0, 1.0),
+            "audio.effects_volume" => self.effects_volume = clamped(key, parse_number::<f32>(key, value)?, 0.0, 1.0),
+            "gameplay.computer"    => {
+                self.computer_strategy = ai::parse_strategy(value)
+                    .ok_or(format!("Unknown computer strategy '{}' for {}", value, key))?;
+            },
+            "gameplay.board_size"  => self.board_size = parse_named(key, value, &BOARD_SIZES, board_size_name)?,
+            "accessibility.motion" => self.motion = parse_named(key, value, &camera::MOTION_LEVELS, camera::motion_level_name)?,
+            "graphics.fullscreen"  => self.fullscreen = parse_bool(key, value)?,
+            "graphics.integer_scaling" => self.integer_scaling = parse_bool(key, value)?,
+            "accessibility.reduce_flashing" => self.reduce_flashing = parse_bool(key, value)?,
+            "accessibility.palette" => self.palette = parse_named(key, value, &palette::PALETTES, palette::palette_name)?,
+            "accessibility.high_contrast" => self.high_contrast = parse_bool(key, value)?,
+            "accessibility.food_shapes" => self.food_shapes = parse_bool(key, value)?,
+            "accessibility.ui_scale" => {
+                self.ui_scale = clamped(key, parse_number::<f32>(key, value)?, MIN_UI_SCALE, MAX_UI_SCALE);
+            },
+            _ if key.starts_with("gameplay.apple.") || key.starts_with("gameplay.golden.") => {
+                self.food.set(&key["gameplay.".len()..], value)?;
+            },
+            _ => {
+                let (player_index, control) = parse_control_key(key).ok_or(format!("Unknown setting '{}'", key))?;
+                let bound = parse_key(value).ok_or(format!("Unknown key '{}' for {}", value, key))?;
+                if RESERVED_KEYS.contains(&bound) {
+                    return Err(format!("{} is kept for the menus, it can't be used for {}", key_id(bound), key));
+                }
+                if let Some(other) = self.bound_to(bound).filter(|other| other != key) {
+                    return Err(format!("{} is already used for {}, it can't be used for {} as well", key_id(bound), other, key));
+                }
+                *self.controls[player_index].key_mut(control) = bound;
+            },
+        }
+        Ok(())
+    }
+
+    // The control a key is bound to, as its settings key
+    fn bound_to(self: &Self, key: KeyboardKey) -> Option<String> {
+        self.controls.iter().enumerate().find_map(|(index, controls)| {
+            CONTROL_NAMES.iter().zip(controls.keys())
+                .find(|(_, bound)| *bound == key)
+                .map(|(name, _)| format!("controls.p{}_{}", index + 1, name))
+        })
+    }
+
+    // @note: Reads what it can and warns about the rest. A file without a
+    //  version line is taken to be the current version, one from a newer game
+    //  is read the same way, keys this version doesn't know are just skipped.
+    //  Returns the version the file was written by as well.
+    pub fn from_text(text: &str) -> (Settings, u32) {
+        Settings::from_text_migrated(text, &MIGRATIONS)
+    }
+
+    // `migrations` is always MIGRATIONS outside of the tests
+    fn from_text_migrated(text: &str, migrations: &[Migration]) -> (Settings, u32) {
+        let mut version = SETTINGS_VERSION;
+        let mut entries: Vec<(String, String)> = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                println!("WARN: Skipping settings line '{}', expected key=value", line);
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            if key == "version" {
+                match value.parse::<u32>() {
+                    Ok(number) if number >= 1 => version = number,
+                    _ => println!("WARN: Bad settings version '{}', reading it as version {}", value, SETTINGS_VERSION),
+                }
+                continue;
+            }
+            entries.push((key.to_string(), value.to_string()));
+        }
+
+        if version > SETTINGS_VERSION {
+            println!("WARN: Settings were written by a newer version of the game ({} > {}), some may be ignored", version, SETTINGS_VERSION);
+        } else {
+            for migration in &migrations[(version - 1) as usize..] {
+                migration(&mut entries);
+            }
+        }
+
+        // @note: The bindings in the file are only checked against each other,
+        //  not the defaults they replace, so two keys can be swapped. One
+        //  that's rejected gets its default back afterwards.
+        let mut settings = Settings::new();
+        for (key, _) in &entries {
+            if let Some((player_index, control)) = parse_control_key(key) {
+                *settings.controls[player_index].key_mut(control) = KeyboardKey::KEY_NULL;
+            }
+        }
+        for (key, value) in entries {
+            if let Err(error) = settings.set(&key, &value) {
+                println!("WARN: {}, keeping the default", error);
+            }
+        }
+
+        let defaults = Settings::new().controls;
+        for (controls, mut default) in settings.controls.iter_mut().zip(defaults) {
+            for control in CONTROL_NAMES {
+                if *controls.key_mut(control) == KeyboardKey::KEY_NULL {
+                    *controls.key_mut(control) = *default.key_mut(control);
+                }
+            }
+        }
+        (settings, version)
+    }
+
+    // @note: Smaller boards are a fixed number of cells centred in the large
+    //  one, kept on its grid. The sizes have an even side so the computer's
+    //  HAMILTONIAN strategy still finds a cycle on them.
+    pub fn play_area(self: &Self, screen_width: u32, screen_height: u32) -> Bounds {
+        let full = game::play_area_for_screen(screen_width, screen_height);
+        let (columns, rows) = match self.board_size {
+            BoardSize::SMALL  => (16.0, 10.0),
+            BoardSize::MEDIUM => (24.0, 14.0),
+            BoardSize::LARGE  => return full,
+        };
+
+        let width  = (columns * CELL_SIZE).min(full.width);
+        let height = (rows    * CELL_SIZE).min(full.height);
+        Bounds {
+            x:      full.x + ((full.width  - width)  / 2.0 / CELL_SIZE).floor() * CELL_SIZE,
+            y:      full.y + ((full.height - height) / 2.0 / CELL_SIZE).floor() * CELL_SIZE,
+            width:  width,
+            height: height
+        }
+    }
+}
+
+// @note: Keeps track of what's on disk so only real changes get written, and
+//  so a setting given on the command line isn't saved unless it's changed
+//  again in the game.
+pub struct SettingsFile {
+    path:       PathBuf,
+    // What the file holds, empty when there's no file yet
+    saved:      Vec<(String, String)>,
+    // From the command line, the value as the setting writes it
+    overrides:  Vec<(String, String)>,
+    // A newer game's file is read but never written over
+    read_only:  bool
+}
+
+impl SettingsFile {
+    pub fn load(path: Option<PathBuf>) -> (Self, Settings) {
+        let path = path.unwrap_or(storage::config_file(SETTINGS_FILE));
+        let (settings, saved, read_only) = match fs::read_to_string(&path) {
+            Ok(text) => {
+                let (settings, version) = Settings::from_text(&text);
+                let saved = settings.entries();
+                (settings, saved, version > SETTINGS_VERSION)
+            },
+            Err(_) => (Settings::new(), Vec::new(), false),
+        };
+
+        let file = SettingsFile {
+            path:       path,
+            saved:      saved,
+            overrides:  Vec::new(),
+            read_only:  read_only
+        };
+        (file, settings)
+    }
+
+    // Each override is `key=value`, one that can't be used is skipped
+    pub fn apply_overrides(self: &mut Self, settings: &mut Settings, overrides: &[String]) {
+        for text in overrides {
+            let Some((key, value)) = text.split_once('=') else {
+                println!("WARN: Ignoring --set {}, expected key=value", text);
+                continue;
+            };
+            let key = key.trim();
+            if let Err(error) = settings.set(key, value) {
+                println!("WARN: Ignoring --set {}: {}", text, error);
+                continue;
+            }
+
+            let applied = settings.entries().into_iter().find(|(entry_key, _)| entry_key == key);
+            if let Some(entry) = applied {
+                self.overrides.retain(|(override_key, _)| override_key != key);
+                self.overrides.push(entry);
+            }
+        }
+    }
+
+    pub fn save(self: &mut Self, settings: &Settings) {
+        if self.read_only {
+            return;
+        }
+
+        // An override that's still in place keeps what the file had, or the
+        //  default when there's no file yet
+        let defaults = Settings::new().entries();
+        let mut entries = settings.entries();
+        for (key, value) in &mut entries {
+            if !self.overrides.iter().any(|(override_key, override_value)| override_key == key && override_value == value) {
+                continue;
+            }
+            let saved = self.saved.iter().chain(defaults.iter()).find(|(saved_key, _)| saved_key == key);
+            if let Some((_, saved_value)) = saved {
+                *value = saved_value.clone();
+            }
+        }
+        if entries == self.saved {
+            return;
+        }
+
+        match storage::write_atomically(&self.path, &entries_to_text(&entries)) {
+            Ok(()) => self.saved = entries,
+            Err(error) => println!("WARN: Failed to save settings to {}: {}", self.path.display(), error),
+        }
+    }
+}
+
+// With a comment line starting each section
+fn entries_to_text(entries: &[(String, String)]) -> String {
+    let mut text = format!("version={}\n", SETTINGS_VERSION);
+    let mut section = "";
+    for (key, value) in entries {
+        let key_section = key.split('.').next().unwrap_or("");
+        if key_section != section {
+            text += &format!("\n# {}\n", key_section);
+            section = key_section;
+        }
+        text += &format!("{}={}\n", key, value);
+    }
+    text
+}
+
+// KEY_RIGHT_SHIFT -> "RIGHT_SHIFT"
+fn key_id(key: KeyboardKey) -> String {
+    format!("{:?}", key).trim_start_matches("KEY_").to_string()
+}
+
+// How the title screen names a player's steering keys: "Arrows" for the
+//  arrow keys, "WASD" when they're all letters, otherwise "I/J/K/L" style
+pub fn steering_keys_name(controls: &PlayerControls) -> String {
+    let keys = [controls.up, controls.left, controls.down, controls.right];
+    if keys == [KeyboardKey::KEY_UP, KeyboardKey::KEY_LEFT, KeyboardKey::KEY_DOWN, KeyboardKey::KEY_RIGHT] {
+        return "Arrows".to_string();
+    }
+
+    let ids: Vec<String> = keys.iter().map(|key| key_id(*key)).collect();
+    if ids.iter().all(|id| id.len() == 1) {
+        ids.concat()
+    } else {
+        ids.join("/")
+    }
+}
+
+// Takes the name with or without KEY_, in any case and with spaces for
+//  underscores, so "right shift" works as well as "RIGHT_SHIFT"
+fn parse_key(name: &str) -> Option<KeyboardKey> {
+    let name = name.trim().to_uppercase().replace(' ', "_");
+    let name = name.trim_start_matches("KEY_");
+    (1..=MAX_KEY_CODE)
+        .filter_map(key_from_i32)
+        .find(|key| *key != KeyboardKey::KEY_NULL && key_id(*key) == name)
+}
+
+// "controls.p2_boost" -> (1, "boost")
+fn parse_control_key(key: &str) -> Option<(usize, &str)> {
+    let (player, control) = key.strip_prefix("controls.p")?.split_once('_')?;
+    let player_index = player.parse::<usize>().ok()?.checked_sub(1)?;
+    if player_index >= 2 || !CONTROL_NAMES.contains(&control) {
+        return None;
+    }
+    Some((player_index, control))
+}
+
+fn parse_named<T: Copy>(key: &str, value: &str, options: &[T], name: fn(T) -> &'static str) -> Result<T, String> {
+    options.iter().copied()
+        .find(|option| name(*option).eq_ignore_ascii_case(value))
+        .ok_or_else(|| {
+            let names: Vec<String> = options.iter().map(|option| name(*option).to_lowercase()).collect();
+            format!("Unknown value '{}' for {}, expected one of {}", value, key, names.join(", "))
+        })
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    value.parse::<bool>().map_err(|_| format!("Expected true or false for {}, got '{}'", key, value))
+}
+
+// NaN and infinity parse as floats but no setting can use them, and NaN would
+//  slip through `clamped` since it compares false with everything
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    if value.parse::<f64>().is_ok_and(|number| !number.is_finite()) {
+        return Err(format!("Expected a number for {}, got '{}'", key, value));
+    }
+    value.parse::<T>().map_err(|_| format!("Expected a number for {}, got '{}'", key, value))
+}
+
+fn clamped<T: PartialOrd + Copy + std::fmt::Display>(key: &str, value: T, min: T, max: T) -> T {
+    if value < min || value > max {
+        let clamped_value = if value < min { min } else { max };
+        println!("WARN: {} for {} is outside {}..{}, using {}", value, key, min, max, clamped_value);
+        return clamped_value;
+    }
+    value
+}
+
+const SECTIONS: [&str; 5] = ["Audio", "Video", "Game", "Controls", "Access"];
+
+pub struct SettingsMenu {
+    section: usize
+}
+
+impl SettingsMenu {
+    pub fn new() -> Self {
+        SettingsMenu { section: 0 }
+    }
+
+    // Returns true when the player leaves the menu
+    pub fn draw(self: &mut Self, ui: &mut Ui, draw_context: &mut Frame, settings: &mut Settings) -> bool {
+        let screen_width  = crate::SCREEN_WIDTH  as f32;
+        let screen_height = crate::SCREEN_HEIGHT as f32;
+        let panel = Rectangle {
+            x:      20.0,
+            y:      20.0,
+            width:  screen_width  - 40.0,
+            height: screen_height - 40.0
+        };
+        draw_context.draw_rectangle_rec(panel, ui::PANEL_COLOR);
+
+        ui.column(Rectangle { x: panel.x + 16.0, y: panel.y + 12.0, width: 160.0, height: panel.height });
+        ui.heading(draw_context, "Settings");
+        ui.list(draw_context, &SECTIONS, &mut self.section, SECTIONS.len());
+        let mut close = ui.button(draw_context, "Back");
+
+        ui.column(Rectangle { x: panel.x + 196.0, y: panel.y + 58.0, width: panel.width - 212.0, height: panel.height });
+        match self.section {
+            0 => {
+                ui.slider(draw_context, "Music", &mut settings.music_volume, 0.0, 1.0, 0.1);
+                ui.slider(draw_context, "Effects", &mut settings.effects_volume, 0.0, 1.0, 0.1);
+            },
+            1 => {
+                ui.toggle(draw_context, "Fullscreen (F11)", &mut settings.fullscreen);
+                ui.toggle(draw_context, "Integer scaling", &mut settings.integer_scaling);
+
+                let names: Vec<&str> = BACKGROUND_FITS.iter().map(|fit| background_fit_name(*fit)).collect();
+                let mut fit = BACKGROUND_FITS.iter().position(|fit| *fit == settings.background_fit).unwrap_or(0);
+                if ui.dropdown(draw_context, "Background", &names, &mut fit) {
+                    settings.background_fit = BACKGROUND_FITS[fit];
+                }
+
+                let names: Vec<&str> = BOARD_GRIDS.iter().map(|grid| board_grid_name(*grid)).collect();
+                let mut grid = BOARD_GRIDS.iter().position(|grid| *grid == settings.grid).unwrap_or(0);
+                if ui.dropdown(draw_context, "Grid", &names, &mut grid) {
+                    settings.grid = BOARD_GRIDS[grid];
+                }
+            },
+            2 => {
+                let names: Vec<&str> = ai::STRATEGIES.iter().map(|strategy| ai::strategy_name(*strategy)).collect();
+                let mut strategy = ai::STRATEGIES.iter().position(|strategy| *strategy == settings.computer_strategy).unwrap_or(0);
+                if ui.dropdown(draw_context, "Computer", &names, &mut strategy) {
+                    settings.computer_strategy = ai::STRATEGIES[strategy];
+                }
+
+                let names: Vec<&str> = BOARD_SIZES.iter().map(|size| board_size_name(*size)).collect();
+                let mut size = BOARD_SIZES.iter().position(|size| *size == settings.board_size).unwrap_or(0);
+                if ui.dropdown(draw_context, "Board size", &names, &mut size) {
+                    settings.board_size = BOARD_SIZES[size];
+                }
+
+                let names: Vec<&str> = THEMES.iter().map(|theme| theme_name(*theme)).collect();
+                let mut theme = THEMES.iter().position(|theme| *theme == settings.theme).unwrap_or(0);
+                if ui.dropdown(draw_context, "Theme", &names, &mut theme) {
+                    settings.theme = THEMES[theme];
+                }
+            },
+            3 => {
+                for (index, controls) in settings.controls.iter_mut().enumerate() {
+                    let player = index + 1;
+                    ui.key_binding(draw_context, &format!("P{} up", player), &mut controls.up);
+                    ui.key_binding(draw_context, &format!("P{} left", player), &mut controls.left);
+                    ui.key_binding(draw_context, &format!("P{} down", player), &mut controls.down);
+                    ui.key_binding(draw_context, &format!("P{} right", player), &mut controls.right);
+                    ui.key_binding(draw_context, &format!("P{} boost", player), &mut controls.boost);
+                }
+            },
+            _ => {
+                let names: Vec<&str> = camera::MOTION_LEVELS.iter().map(|motion| camera::motion_level_name(*motion)).collect();
+                let mut motion = camera::MOTION_LEVELS.iter().position(|motion| *motion == settings.motion).unwrap_or(0);
+                if ui.dropdown(draw_context, "Motion", &names, &mut motion) {
+                    settings.motion = camera::MOTION_LEVELS[motion];
+                }
+                ui.toggle(draw_context, "Reduce flashing", &mut settings.reduce_flashing);
+
+                let names: Vec<&str> = palette::PALETTES.iter().map(|palette| palette::palette_name(*palette)).collect();
+                let mut palette = palette::PALETTES.iter().position(|palette| *palette == settings.palette).unwrap_or(0);
+                if ui.dropdown(draw_context, "Colours", &names, &mut palette) {
+                    settings.palette = palette::PALETTES[palette];
+                }
+                ui.toggle(draw_context, "High contrast", &mut settings.high_contrast);
+                ui.toggle(draw_context, "Food shapes", &mut settings.food_shapes);
+
+                let names: Vec<String> = UI_SCALES.iter().map(|scale| format!("{:.0}%", scale * 100.0)).collect();
+                let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+                let mut scale = UI_SCALES.iter().position(|scale| *scale >= settings.ui_scale).unwrap_or(UI_SCALES.len() - 1);
+                if ui.dropdown(draw_context, "Text size", &names, &mut scale) {
+                    settings.ui_scale = UI_SCALES[scale];
+                }
+            },
+        }
+
+        ui.end(draw_context);
+        close |= ui.back_pressed();
+        close
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str) -> Settings {
+        Settings::from_text(text).0
+    }
+
+    // A settings file of its own in the temp directory, removed again on drop
+    struct TempFile {
+        path: PathBuf
+    }
+
+    impl TempFile {
+        fn new(name: &str, text: Option<&str>) -> Self {
+            let path = std::env::temp_dir().join(format!("snake-settings-{}-{}.txt", name, std::process::id()));
+            match text {
+                Some(text) => fs::write(&path, text).unwrap(),
+                None       => { let _ = fs::remove_file(&path); },
+            }
+            TempFile { path: path }
+        }
+
+        fn load(self: &Self) -> (SettingsFile, Settings) {
+            SettingsFile::load(Some(self.path.clone()))
+        }
+
+        fn value(self: &Self, key: &str) -> Option<String> {
+            let text = fs::read_to_string(&self.path).ok()?;
+            text.lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(line_key, _)| *line_key == key)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(self: &mut Self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    #[test]
+    fn numbers_out_of_range_are_clamped() {
+        let settings = read("audio.music_volume=1.5\naccessibility.ui_scale=0.1\ngraphics.target_fps=5000");
+        assert_eq!(settings.music_volume, 1.0);
+        assert_eq!(settings.ui_scale, MIN_UI_SCALE);
+        assert_eq!(settings.target_fps, MAX_TARGET_FPS);
+    }
+
+    #[test]
+    fn bad_values_keep_the_default() {
+        let defaults = Settings::new();
+        let settings = read(concat!(
+            "audio.music_volume=loud\n",
+            "audio.effects_volume=nan\n",
+            "accessibility.ui_scale=NaN\n",
+            "graphics.target_fps=inf\n",
+            "graphics.theme=purple\n",
+            "graphics.fullscreen=yes\n",
+            "gameplay.apple.duration=-1\n",
+            "no.such_setting=1\n",
+            "not a setting line\n",
+        ));
+        assert_eq!(settings.entries(), defaults.entries());
+    }
+
+    #[test]
+    fn non_finite_numbers_are_errors() {
+        let mut settings = Settings::new();
+        for value in ["nan", "NaN", "inf", "-infinity"] {
+            assert!(settings.set("audio.music_volume", value).is_err(), "accepted {}", value);
+            assert!(settings.set("accessibility.ui_scale", value).is_err(), "accepted {}", value);
+        }
+        assert_eq!(settings.music_volume, Settings::new().music_volume);
+    }
+
+    #[test]
+    fn clashing_and_reserved_bindings_keep_the_default() {
+        let settings = read("controls.p1_up=UP\ncontrols.p2_boost=enter\ncontrols.p1_left=backspace\ncontrols.p2_down=Q");
+        let defaults = Settings::new();
+        assert_eq!(settings.controls[0].up, defaults.controls[0].up);
+        assert_eq!(settings.controls[1].boost, defaults.controls[1].boost);
+        assert_eq!(settings.controls[0].left, defaults.controls[0].left);
+        assert_eq!(settings.controls[1].down, KeyboardKey::KEY_Q);
+
+        let mut settings = Settings::new();
+        assert!(settings.set("controls.p2_up", "w").is_err());
+        assert!(settings.set("controls.p1_boost", "right shift").is_err());
+        // Binding a key to where it already is is fine
+        assert!(settings.set("controls.p1_up", "W").is_ok());
+    }
+
+    #[test]
+    fn the_players_can_swap_keys_in_the_file() {
+        let settings = read(concat!(
+            "controls.p1_up=UP\ncontrols.p1_left=LEFT\ncontrols.p1_down=DOWN\ncontrols.p1_right=RIGHT\n",
+            "controls.p2_up=W\ncontrols.p2_left=A\ncontrols.p2_down=S\ncontrols.p2_right=D\n",
+        ));
+        let defaults = Settings::new();
+        assert_eq!(settings.controls[0], PlayerControls { boost: defaults.controls[0].boost, ..defaults.controls[1] });
+        assert_eq!(settings.controls[1], PlayerControls { boost: defaults.controls[1].boost, ..defaults.controls[0] });
+    }
+
+    #[test]
+    fn files_round_trip() {
+        let mut settings = Settings::new();
+        settings.set("audio.music_volume", "0.3").unwrap();
+        settings.set("graphics.theme", "night").unwrap();
+        settings.set("controls.p2_boost", "left shift").unwrap();
+        settings.set("gameplay.golden.points", "9").unwrap();
+        let text = entries_to_text(&settings.entries());
+        let (read_back, version) = Settings::from_text(&text);
+        assert_eq!(version, SETTINGS_VERSION);
+        assert_eq!(read_back.entries(), settings.entries());
+    }
+
+    #[test]
+    fn a_set_override_keeps_the_files_value() {
+        let file = TempFile::new("override", Some("version=1\naudio.music_volume=0.30\n"));
+        let (mut settings_file, mut settings) = file.load();
+        settings_file.apply_overrides(&mut settings, &["audio.music_volume=0.8".to_string()]);
+        assert_eq!(settings.music_volume, 0.8);
+
+        // Something else changes in the game, the override isn't written out with it
+        settings.effects_volume = 0.25;
+        settings_file.save(&settings);
+        assert_eq!(file.value("audio.music_volume").as_deref(), Some("0.30"));
+        assert_eq!(file.value("audio.effects_volume").as_deref(), Some("0.25"));
+    }
+
+    #[test]
+    fn a_set_override_without_a_file_saves_the_default() {
+        let file = TempFile::new("override-default", None);
+        let (mut settings_file, mut settings) = file.load();
+        settings_file.apply_overrides(&mut settings, &["audio.music_volume=0.1".to_string(), "oops".to_string()]);
+        settings.fullscreen = true;
+        settings_file.save(&settings);
+        assert_eq!(file.value("audio.music_volume"), Some(format!("{:.2}", Settings::new().music_volume)));
+        assert_eq!(file.value("graphics.fullscreen").as_deref(), Some("true"));
+    }
+
+    #[test]
+    fn an_override_changed_in_the_game_is_saved() {
+        let file = TempFile::new("override-changed", Some("version=1\naudio.music_volume=0.30\n"));
+        let (mut settings_file, mut settings) = file.load();
+        settings_file.apply_overrides(&mut settings, &["audio.music_volume=0.8".to_string()]);
+        settings.music_volume = 0.5;
+        settings_file.save(&settings);
+        assert_eq!(file.value("audio.music_volume").as_deref(), Some("0.50"));
+    }
+
+    #[test]
+    fn a_newer_file_is_never_written() {
+        let text = "version=99\naudio.music_volume=0.40\nfuture.setting=1\n";
+        let file = TempFile::new("newer", Some(text));
+        let (mut settings_file, mut settings) = file.load();
+        assert_eq!(settings.music_volume, 0.4);
+
+        settings.music_volume = 0.9;
+        settings_file.save(&settings);
+        assert_eq!(fs::read_to_string(&file.path).unwrap(), text);
+    }
+
+    #[test]
+    fn old_files_go_through_every_migration() {
+        // As if the game were at version 3 and the file still at 1
+        fn rename_volume(entries: &mut Vec<(String, String)>) {
+            entries.retain(|(key, _)| key != "sound.muted");
+            for (key, _) in entries.iter_mut() {
+                if key == "sound.volume" {
+                    *key = "audio.music_volume".to_string();
+                }
+            }
+        }
+        fn split_volume(entries: &mut Vec<(String, String)>) {
+            let music = entries.iter().find(|(key, _)| key == "audio.music_volume").map(|(_, value)| value.clone());
+            if let Some(volume) = music {
+                entries.push(("audio.effects_volume".to_string(), volume));
+            }
+        }
+
+        let migrations: [Migration; 2] = [rename_volume, split_volume];
+        let (settings, version) = Settings::from_text_migrated("version=1\nsound.volume=0.4\nsound.muted=false\n", &migrations);
+        assert_eq!(version, 1);
+        assert_eq!(settings.music_volume, 0.4);
+        assert_eq!(settings.effects_volume, 0.4);
+    }
+}
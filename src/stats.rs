@@ -0,0 +1,299 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::food::{FoodType, FOOD_TYPES};
+use crate::events::{GameEvent, Subscriber};
+use crate::game::{DeathCause, Game, TICK_DURATION};
+use crate::storage;
+
+// @note: Stats follow one player, the one sitting at player 1's controls, and
+//  only in games a person plays for real. Demo, computer only and practice
+//  games (where rewinding would count the same apple twice) are left out by
+//  the caller. In versus every round counts as a game.
+//
+// Lifetime stats are saved as plain `key=value` lines after every game. Keys
+//  that aren't recognised are skipped, so older and newer versions of the game
+//  can share the file.
+
+const STATS_FILE:    &str = "stats.txt";
+const STATS_VERSION: u32  = 1;
+
+// Player 1, the one whose stats and achievements are tracked
+pub const TRACKED_PLAYER: usize = 0;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub games_played:   u32,
+    // Indexed like FOOD_TYPES
+    pub apples_eaten:   [u32; FOOD_TYPES.len()],
+    pub longest_snake:  usize,
+    pub total_score:    u64,
+    pub best_score:     u16,
+    // Seconds
+    pub time_played:    f32,
+    pub turns:          u32,
+    pub boost_time:     f32,
+    pub wall_deaths:    u32,
+    pub self_deaths:    u32,
+    pub snake_deaths:   u32
+}
+
+fn map_food_type_to_key(food_type: FoodType) -> &'static str {
+    match food_type {
+        FoodType::APPLE  => "apple",
+        FoodType::GOLDEN => "golden",
+    }
+}
+
+impl Stats {
+    pub fn apples_of(self: &Self, food_type: FoodType) -> u32 {
+        FOOD_TYPES.iter().position(|other| *other == food_type).map(|index| self.apples_eaten[index]).unwrap_or(0)
+    }
+
+    pub fn total_apples(self: &Self) -> u32 {
+        self.apples_eaten.iter().sum()
+    }
+
+    pub fn average_score(self: &Self) -> f32 {
+        if self.games_played == 0 {
+            return 0.0;
+        }
+        self.total_score as f32 / self.games_played as f32
+    }
+
+    pub fn add(self: &mut Self, other: &Stats) {
+        self.games_played += other.games_played;
+        for (index, count) in other.apples_eaten.iter().enumerate() {
+            self.apples_eaten[index] += count;
+        }
+        self.longest_snake = self.longest_snake.max(other.longest_snake);
+        self.total_score  += other.total_score;
+        self.best_score    = self.best_score.max(other.best_score);
+        self.time_played  += other.time_played;
+        self.turns        += other.turns;
+        self.boost_time   += other.boost_time;
+        self.wall_deaths  += other.wall_deaths;
+        self.self_deaths  += other.self_deaths;
+        self.snake_deaths += other.snake_deaths;
+    }
+
+    pub fn to_text(self: &Self) -> String {
+        let mut text = format!("version={}\n", STATS_VERSION);
+        text += &format!("games_played={}\n", self.games_played);
+        for food_type in FOOD_TYPES {
+            text += &format!("apples_{}={}\n", map_food_type_to_key(food_type), self.apples_of(food_type));
+        }
+        text += &format!("longest_snake={}\n", self.longest_snake);
+        text += &format!("total_score={}\n", self.total_score);
+        text += &format!("best_score={}\n", self.best_score);
+        text += &format!("time_played={:.3}\n", self.time_played);
+        text += &format!("turns={}\n", self.turns);
+        text += &format!("boost_time={:.3}\n", self.boost_time);
+        text += &format!("wall_deaths={}\n", self.wall_deaths);
+        text += &format!("self_deaths={}\n", self.self_deaths);
+        text += &format!("snake_deaths={}\n", self.snake_deaths);
+        text
+    }
+
+    pub fn from_text(text: &str) -> Stats {
+        let mut stats = Stats::default();
+
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else { continue };
+            let (key, value) = (key.trim(), value.trim());
+            let number = value.parse::<f64>().unwrap_or(0.0).max(0.0);
+
+            match key {
+                "games_played"  => stats.games_played  = number as u32,
+                "longest_snake" => stats.longest_snake = number as usize,
+                "total_score"   => stats.total_score   = number as u64,
+                "best_score"    => stats.best_score    = number as u16,
+                "time_played"   => stats.time_played   = number as f32,
+                "turns"         => stats.turns         = number as u32,
+                "boost_time"    => stats.boost_time    = number as f32,
+                "wall_deaths"   => stats.wall_deaths   = number as u32,
+                "self_deaths"   => stats.self_deaths   = number as u32,
+                "snake_deaths"  => stats.snake_deaths  = number as u32,
+                _ => {
+                    let food_index = FOOD_TYPES.iter()
+                        .position(|food_type| key == format!("apples_{}", map_food_type_to_key(*food_type)));
+                    if let Some(index) = food_index {
+                        stats.apples_eaten[index] = number as u32;
+                    }
+                },
+            }
+        }
+
+        stats
+    }
+}
+
+pub struct StatsTracker {
+    // The game being played, or the last one once it is over
+    pub current:    Stats,
+    pub session:    Stats,
+    pub lifetime:   Stats,
+    in_game:        bool,
+    path:           PathBuf
+}
+
+impl StatsTracker {
+    pub fn load() -> Self {
+        let path = storage::data_file(STATS_FILE);
+        let lifetime = fs::read_to_string(&path).map(|text| Stats::from_text(&text)).unwrap_or_default();
+
+        StatsTracker {
+            current:        Stats::default(),
+            session:        Stats::default(),
+            lifetime:       lifetime,
+            in_game:        false,
+            path:           path
+        }
+    }
+
+    fn finish_game(self: &mut Self) {
+        if !self.in_game {
+            return;
+        }
+        self.in_game = false;
+
+        // @note: The score is counted from the apples eaten rather than read
+        //  off the player, versus scores carry over between rounds and only
+        //  this round's points count.
+        let current = &mut self.current;
+        current.games_played = 1;
+        current.best_score   = current.total_score.min(u16::MAX as u64) as u16;
+
+        self.session.add(&self.current);
+        self.lifetime.add(&self.current);
+
+        if let Err(error) = storage::write_atomically(&self.path, &self.lifetime.to_text()) {
+            println!("WARN: Failed to save stats to {}: {}", self.path.display(), error);
+        }
+    }
+}
+
+// The first step after a game finished starts a new one, the end of the round
+//  finishes and saves it.
+impl Subscriber for StatsTracker {
+    fn on_event(self: &mut Self, game: &Game, event: &GameEvent) {
+        match *event {
+            GameEvent::TICKED => {
+                if !self.in_game {
+                    self.current = Stats::default();
+                    self.in_game = true;
+                }
+                self.current.time_played += TICK_DURATION;
+                if let Some(player) = game.players.get(TRACKED_PLAYER) {
+                    self.current.longest_snake = self.current.longest_snake.max(player.snake.parts.len());
+                }
+            },
+            GameEvent::TURNED(TRACKED_PLAYER, _) => self.current.turns += 1,
+            GameEvent::BOOSTED(TRACKED_PLAYER)   => self.current.boost_time += TICK_DURATION,
+            GameEvent::GREW(TRACKED_PLAYER, length) => {
+                self.current.longest_snake = self.current.longest_snake.max(length);
+            },
+            GameEvent::EATEN(TRACKED_PLAYER, apple) => {
+                self.current.total_score += apple.score_value() as u64;
+                if let Some(food_index) = FOOD_TYPES.iter().position(|food_type| *food_type == apple.food_type) {
+                    self.current.apples_eaten[food_index] += 1;
+                }
+            },
+            GameEvent::DIED(TRACKED_PLAYER, death) => match death.cause {
+                DeathCause::WALL(_) | DeathCause::OBSTACLE => self.current.wall_deaths  = 1,
+                DeathCause::SELF(_)                        => self.current.self_deaths  = 1,
+                DeathCause::SNAKE(_, _)                    => self.current.snake_deaths = 1,
+            },
+            GameEvent::DECIDED(_) => self.finish_game(),
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::{Apple, FoodTable};
+    use crate::game::{play_area_for_screen, Death, Direction, GameMode, WallSide};
+
+    #[test]
+    fn stats_survive_the_text_format() {
+        let stats = Stats {
+            games_played:   12,
+            apples_eaten:   [40, 3],
+            longest_snake:  27,
+            total_score:    131,
+            best_score:     30,
+            time_played:    412.5,
+            turns:          880,
+            boost_time:     31.25,
+            wall_deaths:    7,
+            self_deaths:    4,
+            snake_deaths:   1
+        };
+        assert_eq!(Stats::from_text(&stats.to_text()), stats);
+        assert_eq!(Stats::from_text("games_played=3\nunknown=9\nturns=lots\n"), Stats { games_played: 3, ..Stats::default() });
+    }
+
+    #[test]
+    fn versus_rounds_count_the_apples_not_the_score() {
+        let mut game = Game::new(GameMode::VERSUS, play_area_for_screen(640, 480), 1);
+        // Points carried over from earlier rounds of the match
+        game.players[TRACKED_PLAYER].score = 50;
+
+        let path = std::env::temp_dir().join(format!("snake-stats-{}.txt", std::process::id()));
+        let lifetime = Stats { games_played: 4, total_score: 20, best_score: 9, ..Stats::default() };
+        let mut tracker = StatsTracker {
+            current:    Stats::default(),
+            session:    Stats::default(),
+            lifetime:   lifetime.clone(),
+            in_game:    false,
+            path:       path.clone()
+        };
+
+        let food = FoodTable::default();
+        let apple  = Apple::new(0.0, 0.0, FoodType::APPLE, food.get(FoodType::APPLE));
+        let golden = Apple::new(0.0, 0.0, FoodType::GOLDEN, food.get(FoodType::GOLDEN));
+        let death = Death { cause: DeathCause::WALL(WallSide::TOP), x: 0.0, y: 0.0, tick: 2, boosting: true, length: 4 };
+        let events = [
+            GameEvent::TICKED,
+            GameEvent::EATEN(TRACKED_PLAYER, apple),
+            GameEvent::EATEN(1, golden),
+            GameEvent::TICKED,
+            GameEvent::TURNED(TRACKED_PLAYER, Direction::UP),
+            GameEvent::TURNED(1, Direction::DOWN),
+            GameEvent::BOOSTED(TRACKED_PLAYER),
+            GameEvent::GREW(TRACKED_PLAYER, 4),
+            GameEvent::DIED(TRACKED_PLAYER, death),
+            GameEvent::DECIDED(Some(1))
+        ];
+        for event in &events {
+            tracker.on_event(&game, event);
+        }
+
+        let current = &tracker.current;
+        assert_eq!(current.games_played, 1);
+        assert_eq!(current.total_score, apple.score_value() as u64);
+        assert_eq!(current.best_score, apple.score_value());
+        assert_eq!(current.apples_of(FoodType::APPLE), 1);
+        assert_eq!(current.apples_of(FoodType::GOLDEN), 0);
+        assert_eq!(current.turns, 1);
+        assert_eq!(current.boost_time, TICK_DURATION);
+        assert_eq!(current.time_played, TICK_DURATION * 2.0);
+        assert_eq!(current.longest_snake, 4);
+        assert_eq!((current.wall_deaths, current.self_deaths, current.snake_deaths), (1, 0, 0));
+
+        assert_eq!(tracker.session, tracker.current);
+        assert_eq!(tracker.lifetime.games_played, 5);
+        assert_eq!(tracker.lifetime.total_score, 20 + apple.score_value() as u64);
+        assert_eq!(tracker.lifetime.best_score, 9);
+        assert_eq!(Stats::from_text(&fs::read_to_string(&path).unwrap()).games_played, 5);
+
+        // The next round starts a game of its own
+        tracker.on_event(&game, &GameEvent::TICKED);
+        assert_eq!(tracker.current.total_score, 0);
+        tracker.on_event(&game, &GameEvent::DECIDED(None));
+        assert_eq!(tracker.session.games_played, 2);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,72 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// @note: Where the game keeps things between runs (high scores and the like).
+//  SNAKE_DATA_DIR overrides the platform default, which is handy for tests and
+//  for keeping several profiles around.
+
+pub fn data_directory() -> PathBuf {
+    if let Some(directory) = env::var_os("SNAKE_DATA_DIR") {
+        return PathBuf::from(directory);
+    }
+
+    if cfg!(windows) {
+        if let Some(app_data) = env::var_os("APPDATA") {
+            return PathBuf::from(app_data).join("snake");
+        }
+    } else {
+        if let Some(data_home) = env::var_os("XDG_DATA_HOME") {
+            return PathBuf::from(data_home).join("snake");
+        }
+        if let Some(home) = env::var_os("HOME") {
+            return PathBuf::from(home).join(".local").join("share").join("snake");
+        }
+    }
+
+    PathBuf::from(".")
+}
+
+pub fn data_file(name: &str) -> PathBuf {
+    data_directory().join(name)
+}
+
+// Same idea for things the player sets up rather than earns, SNAKE_CONFIG_DIR
+//  overrides it
+pub fn config_directory() -> PathBuf {
+    if let Some(directory) = env::var_os("SNAKE_CONFIG_DIR") {
+        return PathBuf::from(directory);
+    }
+
+    if cfg!(windows) {
+        if let Some(app_data) = env::var_os("APPDATA") {
+            return PathBuf::from(app_data).join("snake");
+        }
+    } else {
+        if let Some(config_home) = env::var_os("XDG_CONFIG_HOME") {
+            return PathBuf::from(config_home).join("snake");
+        }
+        if let Some(home) = env::var_os("HOME") {
+            return PathBuf::from(home).join(".config").join("snake");
+        }
+    }
+
+    PathBuf::from(".")
+}
+
+pub fn config_file(name: &str) -> PathBuf {
+    config_directory().join(name)
+}
+
+// Writes to a temporary file first and renames it over the old one, so a crash
+//  halfway through never leaves a half written file behind.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, contents)?;
+    fs::rename(&temporary_path, path)
+}
@@ -0,0 +1,374 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::ai::{self, Strategy};
+use crate::cli;
+use crate::game::{Bounds, DeathCause, Game, GameMode};
+
+// @note: Plays lots of headless solo games per AI strategy and reports how
+//  they went. Every game is fully determined by its seed, so the same options
+//  always give the same report no matter how many threads run it, which makes
+//  a changed number a real regression and not noise.
+
+pub const USAGE: &str = "\
+Usage: snake tournament [options]
+
+Plays headless solo games for each AI strategy and writes a report.
+
+Options:
+  --games <n>           Games per strategy (default 100)
+  --seed <n>            First seed, games use seed..seed+games (default 0)
+  --strategies <list>   Comma separated: greedy,pathfinding,hamiltonian (default all)
+  --threads <n>         Worker threads (default: one per core)
+  --max-ticks <n>       Stop a game that is still going after this many ticks (default 20000)
+  --format <csv|json>   Report format (default csv)
+  --out <file>          Write the report here instead of stdout. For csv the
+                        summary goes next to it in <file stem>_summary.csv
+  --help                Show this message";
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ReportFormat {
+    CSV,
+    JSON
+}
+
+#[derive(Clone, Debug)]
+pub struct TournamentOptions {
+    pub strategies: Vec<Strategy>,
+    pub games:      u32,
+    pub first_seed: u64,
+    pub threads:    usize,
+    // Strategies like the Hamiltonian cycle can survive forever
+    pub max_ticks:  u64,
+    pub format:     ReportFormat,
+    pub output:     Option<String>
+}
+
+impl Default for TournamentOptions {
+    fn default() -> Self {
+        TournamentOptions {
+            strategies: ai::STRATEGIES.to_vec(),
+            games:      100,
+            first_seed: 0,
+            threads:    thread::available_parallelism().map(|count| count.get()).unwrap_or(1),
+            max_ticks:  20_000,
+            format:     ReportFormat::CSV,
+            output:     None
+        }
+    }
+}
+
+// `args` are the ones after the subcommand, `run` has already handled --help
+pub fn parse_options(args: &[String]) -> Result<TournamentOptions, String> {
+    let mut options = TournamentOptions::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let known = ["--games", "--seed", "--threads", "--max-ticks", "--out", "--format", "--strategies"];
+        if !known.contains(&arg.as_str()) {
+            return Err(format!("Unknown option '{}'\n\n{}", arg, USAGE));
+        }
+
+        let value = args.next().ok_or(format!("Missing value for {}\n\n{}", arg, USAGE))?;
+        let parse_number = |value: &str| -> Result<u64, String> {
+            value.parse::<u64>().map_err(|_| format!("Expected a number for {}, got '{}'", arg, value))
+        };
+
+        match arg.as_str() {
+            "--games"     => {
+                options.games = value.parse::<u32>()
+                    .map_err(|_| format!("Expected a number up to {} for {}, got '{}'", u32::MAX, arg, value))?;
+            },
+            "--seed"      => options.first_seed = parse_number(value)?,
+            "--threads"   => options.threads    = (parse_number(value)? as usize).max(1),
+            "--max-ticks" => options.max_ticks  = parse_number(value)?,
+            "--out"       => options.output     = Some(value.clone()),
+            "--format"    => {
+                options.format = match value.as_str() {
+                    "csv"  => ReportFormat::CSV,
+                    "json" => ReportFormat::JSON,
+                    other  => return Err(format!("Unknown report format '{}', expected csv or json", other)),
+                };
+            },
+            "--strategies" => {
+                options.strategies = value.split(',')
+                    .map(|name| ai::parse_strategy(name.trim()).ok_or(format!("Unknown strategy '{}'", name)))
+                    .collect::<Result<Vec<Strategy>, String>>()?;
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(options)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GameRecord {
+    pub strategy:       Strategy,
+    pub seed:           u64,
+    pub score:          u16,
+    pub length:         usize,
+    pub ticks:          u64,
+    // None if the game hit the tick limit
+    pub death_cause:    Option<DeathCause>
+}
+
+pub fn play_game(strategy: Strategy, seed: u64, play_area: Bounds, max_ticks: u64) -> GameRecord {
+    let mut game = Game::new(GameMode::SOLO, play_area, seed);
+    while !game.round_over && game.tick < max_ticks {
+        ai::steer_ai_players(&mut game, &[Some(strategy)]);
+        game.step();
+    }
+
+    let player = &game.players[0];
+    GameRecord {
+        strategy:       strategy,
+        seed:           seed,
+        score:          player.score,
+        length:         player.snake.parts.len(),
+        ticks:          game.tick,
+        death_cause:    player.death.map(|death| death.cause)
+    }
+}
+
+// Hands games out to the worker threads one at a time, the results come back
+//  sorted by strategy then seed.
+pub fn run_tournament(options: &TournamentOptions, play_area: Bounds) -> Result<Vec<GameRecord>, String> {
+    let seeds: Vec<u64> = cli::seeds(options.first_seed, options.games as u64)?.collect();
+    let jobs: Vec<(Strategy, u64)> = options.strategies.iter()
+        .flat_map(|strategy| seeds.iter().map(move |seed| (*strategy, *seed)))
+        .collect();
+
+    let next_job = AtomicUsize::new(0);
+    let records: Mutex<Vec<(usize, GameRecord)>> = Mutex::new(Vec::with_capacity(jobs.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..options.threads.max(1) {
+            scope.spawn(|| {
+                loop {
+                    let index = next_job.fetch_add(1, Ordering::Relaxed);
+                    let Some((strategy, seed)) = jobs.get(index) else { break };
+                    let record = play_game(*strategy, *seed, play_area, options.max_ticks);
+                    records.lock().unwrap().push((index, record));
+                }
+            });
+        }
+    });
+
+    let mut records = records.into_inner().unwrap();
+    records.sort_by_key(|(index, _)| *index);
+    Ok(records.into_iter().map(|(_, record)| record).collect())
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StrategySummary {
+    pub strategy:       Strategy,
+    pub games:          usize,
+    pub mean_score:     f64,
+    pub median_score:   f64,
+    pub min_score:      u16,
+    pub max_score:      u16,
+    pub mean_length:    f64,
+    pub mean_ticks:     f64,
+    pub wall_deaths:    usize,
+    pub self_deaths:    usize,
+    // Games that ran into the tick limit
+    pub survived:       usize
+}
+
+pub fn summarize(records: &[GameRecord], strategies: &[Strategy]) -> Vec<StrategySummary> {
+    strategies.iter().filter_map(|strategy| {
+        let games: Vec<&GameRecord> = records.iter().filter(|record| record.strategy == *strategy).collect();
+        if games.is_empty() {
+            return None;
+        }
+
+        let count = games.len() as f64;
+        let mut scores: Vec<u16> = games.iter().map(|record| record.score).collect();
+        scores.sort();
+        let middle = scores.len() / 2;
+        let median_score = if scores.len().is_multiple_of(2) {
+            (scores[middle - 1] as f64 + scores[middle] as f64) / 2.0
+        } else {
+            scores[middle] as f64
+        };
+
+        Some(StrategySummary {
+            strategy:       *strategy,
+            games:          games.len(),
+            mean_score:     scores.iter().map(|score| *score as f64).sum::<f64>() / count,
+            median_score:   median_score,
+            min_score:      scores[0],
+            max_score:      scores[scores.len() - 1],
+            mean_length:    games.iter().map(|record| record.length as f64).sum::<f64>() / count,
+            mean_ticks:     games.iter().map(|record| record.ticks as f64).sum::<f64>() / count,
+            wall_deaths:    games.iter().filter(|record| matches!(record.death_cause, Some(DeathCause::WALL(_)) | Some(DeathCause::OBSTACLE))).count(),
+            self_deaths:    games.iter().filter(|record| matches!(record.death_cause, Some(DeathCause::SELF(_)))).count(),
+            survived:       games.iter().filter(|record| record.death_cause.is_none()).count()
+        })
+    }).collect()
+}
+
+pub fn map_death_cause_to_name(cause: Option<DeathCause>) -> &'static str {
+    match cause {
+        None                           => "survived",
+        Some(DeathCause::WALL(_))      => "wall",
+        Some(DeathCause::SELF(_))      => "self",
+        Some(DeathCause::SNAKE(_, _))  => "snake",
+        Some(DeathCause::OBSTACLE)     => "obstacle",
+    }
+}
+
+pub fn write_games_csv(out: &mut impl Write, records: &[GameRecord]) -> io::Result<()> {
+    writeln!(out, "strategy,seed,score,length,ticks,death_cause")?;
+    for record in records {
+        writeln!(
+            out, "{},{},{},{},{},{}",
+            ai::strategy_name(record.strategy), record.seed, record.score, record.length, record.ticks,
+            map_death_cause_to_name(record.death_cause)
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_summary_csv(out: &mut impl Write, summaries: &[StrategySummary]) -> io::Result<()> {
+    writeln!(out, "strategy,games,mean_score,median_score,min_score,max_score,mean_length,mean_ticks,wall_deaths,self_deaths,survived")?;
+    for summary in summaries {
+        writeln!(
+            out, "{},{},{:.2},{:.1},{},{},{:.2},{:.1},{},{},{}",
+            ai::strategy_name(summary.strategy), summary.games, summary.mean_score, summary.median_score,
+            summary.min_score, summary.max_score, summary.mean_length, summary.mean_ticks,
+            summary.wall_deaths, summary.self_deaths, summary.survived
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_json(out: &mut impl Write, records: &[GameRecord], summaries: &[StrategySummary]) -> io::Result<()> {
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"summary\": [")?;
+    for (index, summary) in summaries.iter().enumerate() {
+        let separator = if index + 1 < summaries.len() { "," } else { "" };
+        writeln!(
+            out,
+            "    {{\"strategy\":\"{}\",\"games\":{},\"mean_score\":{:.2},\"median_score\":{:.1},\"min_score\":{},\"max_score\":{},\"mean_length\":{:.2},\"mean_ticks\":{:.1},\"wall_deaths\":{},\"self_deaths\":{},\"survived\":{}}}{}",
+            ai::strategy_name(summary.strategy), summary.games, summary.mean_score, summary.median_score,
+            summary.min_score, summary.max_score, summary.mean_length, summary.mean_ticks,
+            summary.wall_deaths, summary.self_deaths, summary.survived, separator
+        )?;
+    }
+    writeln!(out, "  ],")?;
+    writeln!(out, "  \"games\": [")?;
+    for (index, record) in records.iter().enumerate() {
+        let separator = if index + 1 < records.len() { "," } else { "" };
+        writeln!(
+            out,
+            "    {{\"strategy\":\"{}\",\"seed\":{},\"score\":{},\"length\":{},\"ticks\":{},\"death_cause\":\"{}\"}}{}",
+            ai::strategy_name(record.strategy), record.seed, record.score, record.length, record.ticks,
+            map_death_cause_to_name(record.death_cause), separator
+        )?;
+    }
+    writeln!(out, "  ]")?;
+    writeln!(out, "}}")
+}
+
+fn write_report(options: &TournamentOptions, records: &[GameRecord], summaries: &[StrategySummary]) -> io::Result<()> {
+    let Some(output) = &options.output else {
+        let mut stdout = io::stdout().lock();
+        return match options.format {
+            ReportFormat::CSV => {
+                write_games_csv(&mut stdout, records)?;
+                writeln!(stdout)?;
+                write_summary_csv(&mut stdout, summaries)
+            },
+            ReportFormat::JSON => write_json(&mut stdout, records, summaries),
+        };
+    };
+
+    match options.format {
+        ReportFormat::CSV => {
+            write_games_csv(&mut File::create(output)?, records)?;
+
+            let path = Path::new(output);
+            let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("tournament");
+            let summary_path = path.with_file_name(format!("{}_summary.csv", stem));
+            write_summary_csv(&mut File::create(&summary_path)?, summaries)?;
+            println!("Wrote {} and {}", output, summary_path.display());
+        },
+        ReportFormat::JSON => {
+            write_json(&mut File::create(output)?, records, summaries)?;
+            println!("Wrote {}", output);
+        },
+    }
+    write_summary_csv(&mut io::stdout().lock(), summaries)
+}
+
+// The `snake tournament` subcommand
+pub fn run(args: &[String], play_area: Bounds) -> Result<(), String> {
+    if cli::wants_help(args) {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let options = parse_options(args)?;
+    let records = run_tournament(&options, play_area)?;
+    let summaries = summarize(&records, &options.strategies);
+
+    write_report(&options, &records, &summaries)
+        .map_err(|error| format!("Failed to write the tournament report: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn games_has_to_fit_a_u32() {
+        assert_eq!(parse_options(&args("--games 250")).unwrap().games, 250);
+        assert!(parse_options(&args("--games 5000000000")).is_err());
+        assert!(parse_options(&args("--games -1")).is_err());
+    }
+
+    #[test]
+    fn median_of_even_and_odd_counts() {
+        let record = |score: u16| GameRecord { strategy: Strategy::GREEDY, seed: 0, score: score, length: 3, ticks: 10, death_cause: None };
+        let summaries = summarize(&[record(1), record(4), record(9)], &[Strategy::GREEDY]);
+        assert_eq!(summaries[0].median_score, 4.0);
+        let summaries = summarize(&[record(1), record(4), record(9), record(2)], &[Strategy::GREEDY]);
+        assert_eq!(summaries[0].median_score, 3.0);
+    }
+
+    #[test]
+    fn seeds_past_the_last_one_are_errors() {
+        let options = parse_options(&args("--seed 18446744073709551615 --games 2")).unwrap();
+        assert!(run_tournament(&options, crate::game::play_area_for_screen(640, 480)).is_err());
+    }
+
+    #[test]
+    fn thread_count_doesnt_change_the_results() {
+        let play_area = crate::game::Bounds { x: 20.0, y: 40.0, width: 200.0, height: 160.0 };
+        let records = |threads: &str| {
+            let options = parse_options(&args(&format!("--games 6 --seed 3 --max-ticks 500 --threads {}", threads))).unwrap();
+            run_tournament(&options, play_area).unwrap()
+        };
+
+        let single = records("1");
+        let several = records("4");
+        assert_eq!(single.len(), 6 * ai::STRATEGIES.len());
+        assert_eq!(single.len(), several.len());
+        for (single, several) in single.iter().zip(&several) {
+            assert_eq!(
+                (single.strategy, single.seed, single.score, single.length, single.ticks, single.death_cause),
+                (several.strategy, several.seed, several.score, several.length, several.ticks, several.death_cause)
+            );
+        }
+    }
+}
@@ -0,0 +1,469 @@
+use raylib::prelude::*;
+
+use crate::hud::{Anchor, Hud, TextStyle};
+
+// @note: A small immediate-mode UI for menus. Every frame the screen calls the
+//  widget functions in order and each one draws itself and says whether it
+//  was used, there's no tree of widgets kept around. The only state is which
+//  widget has focus, counted in the order they're declared, and which one has
+//  taken over the input for a moment (an open dropdown, a list being scrolled
+//  or a key binding waiting for a key).
+//
+//  Input is read once in `begin`, before drawing starts, so everything works
+//  the same from the keyboard (arrows or WASD, ENTER/SPACE, BACKSPACE), the
+//  first gamepad (d-pad, A, B) and the mouse (hover to focus, click to use).
+
+const ROW_HEIGHT:   f32 = 30.0;
+const ROW_GAP:      f32 = 4.0;
+const PADDING:      f32 = 8.0;
+const TEXT_SIZE:    f32 = 20.0;
+// How much of a row the label gets, the control takes the rest
+const LABEL_SHARE:  f32 = 0.45;
+
+const TEXT_COLOR:   Color = Color { r: 245, g: 245, b: 245, a: 255 };
+const MUTED_COLOR:  Color = Color { r: 160, g: 160, b: 160, a: 255 };
+const CONTROL_COLOR: Color = Color { r: 70, g: 70, b: 70, a: 255 };
+const FILL_COLOR:   Color = Color { r: 0, g: 158, b: 47, a: 255 };
+const FOCUS_COLOR:  Color = Color { r: 255, g: 203, b: 0, a: 255 };
+pub const PANEL_COLOR: Color = Color { r: 30, g: 30, b: 30, a: 235 };
+
+struct UiInput {
+    up:             bool,
+    down:           bool,
+    left:           bool,
+    right:          bool,
+    activate:       bool,
+    back:           bool,
+    mouse:          Vector2,
+    mouse_moved:    bool,
+    clicked:        bool,
+    mouse_down:     bool,
+    key:            Option<KeyboardKey>
+}
+
+struct OpenDropdown {
+    id:             usize,
+    options:        Vec<String>,
+    highlighted:    usize,
+    // The closed dropdown, the options open underneath it
+    area:           Rectangle
+}
+
+pub struct Ui<'a> {
+    hud:            &'a Hud,
+    input:          UiInput,
+    focus:          usize,
+    // Widgets declared last frame, so moving the focus can wrap around
+    widget_count:   usize,
+    next_id:        usize,
+    // The widget that has the input to itself right now
+    active:         Option<usize>,
+    dropdown:       Option<OpenDropdown>,
+    // (dropdown id, what got picked) for the dropdown to apply next time it's drawn
+    dropdown_result: Option<(usize, Option<usize>)>,
+    // A slider the mouse is holding on to
+    dragging:       Option<usize>,
+    // The key just bound, letting go of it shouldn't also use whatever has focus
+    bound_key:      Option<KeyboardKey>,
+    back_pressed:   bool,
+    cursor:         Vector2,
+    column_width:   f32
+}
+
+impl<'a> Ui<'a> {
+    pub fn new(hud: &'a Hud) -> Self {
+        Ui {
+            hud:            hud,
+            input:          UiInput {
+                up: false, down: false, left: false, right: false, activate: false, back: false,
+                mouse: Vector2::zero(), mouse_moved: false, clicked: false, mouse_down: false, key: None
+            },
+            focus:          0,
+            widget_count:   0,
+            next_id:        0,
+            active:         None,
+            dropdown:       None,
+            dropdown_result: None,
+            dragging:       None,
+            bound_key:      None,
+            back_pressed:   false,
+            cursor:         Vector2::zero(),
+            column_width:   0.0
+        }
+    }
+
+    // Drops the focus and anything half done, e.g. when a menu is opened again
+    pub fn reset(self: &mut Self) {
+        self.focus = 0;
+        self.active = None;
+        self.dropdown = None;
+        self.dropdown_result = None;
+        self.dragging = None;
+        self.bound_key = None;
+    }
+
+    // Reads this frame's input, has to happen before `begin_drawing`
+    pub fn begin(self: &mut Self, raylib: &mut RaylibHandle) {
+        // @note: Moving around goes on the press, using something waits for the
+        //  release like the rest of the game does. Otherwise the ENTER that
+        //  closes a menu gets released over whatever screen comes next.
+        let gamepad = raylib.is_gamepad_available(0);
+        let pressed = |keys: &[KeyboardKey], button: GamepadButton| {
+            keys.iter().any(|key| raylib.is_key_pressed(*key))
+                || (gamepad && raylib.is_gamepad_button_pressed(0, button))
+        };
+        let released = |keys: &[KeyboardKey], button: GamepadButton| {
+            keys.iter().any(|key| raylib.is_key_released(*key))
+                || (gamepad && raylib.is_gamepad_button_released(0, button))
+        };
+
+        self.input.up       = pressed(&[KeyboardKey::KEY_UP, KeyboardKey::KEY_W], GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP);
+        self.input.down     = pressed(&[KeyboardKey::KEY_DOWN, KeyboardKey::KEY_S], GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN);
+        self.input.left     = pressed(&[KeyboardKey::KEY_LEFT, KeyboardKey::KEY_A], GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT);
+        self.input.right    = pressed(&[KeyboardKey::KEY_RIGHT, KeyboardKey::KEY_D], GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT);
+        self.input.activate = released(&[KeyboardKey::KEY_ENTER, KeyboardKey::KEY_SPACE], GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN);
+        self.input.back     = released(&[KeyboardKey::KEY_BACKSPACE], GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT);
+
+        let mouse_delta = raylib.get_mouse_delta();
+        self.input.mouse        = raylib.get_mouse_position();
+        self.input.mouse_moved  = mouse_delta.x != 0.0 || mouse_delta.y != 0.0;
+        self.input.clicked      = raylib.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+        self.input.mouse_down   = raylib.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT);
+        self.input.key          = raylib.get_key_pressed();
+
+        if let Some(key) = self.bound_key {
+            if !raylib.is_key_down(key) {
+                self.input.activate = false;
+                self.input.back = false;
+                self.bound_key = None;
+            }
+        }
+
+        self.widget_count = self.next_id;
+        self.next_id = 0;
+        self.back_pressed = self.input.back && self.active.is_none();
+        if !self.input.mouse_down {
+            self.dragging = None;
+        }
+
+        if self.active.is_none() && self.widget_count > 0 {
+            if self.input.up {
+                self.focus = (self.focus + self.widget_count - 1) % self.widget_count;
+            }
+            if self.input.down {
+                self.focus = (self.focus + 1) % self.widget_count;
+            }
+            self.focus = self.focus.min(self.widget_count - 1);
+        }
+
+        self.update_dropdown();
+    }
+
+    fn update_dropdown(self: &mut Self) {
+        let Some(dropdown) = self.dropdown.as_mut() else { return };
+
+        if self.input.up {
+            dropdown.highlighted = dropdown.highlighted.saturating_sub(1);
+        }
+        if self.input.down {
+            dropdown.highlighted = (dropdown.highlighted + 1).min(dropdown.options.len().saturating_sub(1));
+        }
+
+        let mut result = None;
+        for index in 0..dropdown.options.len() {
+            if !option_area(dropdown.area, index).check_collision_point_rec(self.input.mouse) {
+                continue;
+            }
+            if self.input.mouse_moved {
+                dropdown.highlighted = index;
+            }
+            if self.input.clicked {
+                result = Some(Some(index));
+            }
+        }
+
+        if self.input.activate {
+            result = Some(Some(dropdown.highlighted));
+        } else if self.input.back || (self.input.clicked && result.is_none()) {
+            result = Some(None);
+        }
+
+        if let Some(choice) = result {
+            self.dropdown_result = Some((dropdown.id, choice));
+            self.dropdown = None;
+            // The click or key that closed it doesn't get to do anything else
+            self.input.clicked = false;
+            self.input.activate = false;
+        }
+    }
+
+    // Where the next widgets go, one under the other down the area
+    pub fn column(self: &mut Self, area: Rectangle) {
+        self.cursor = Vector2 { x: area.x, y: area.y };
+        self.column_width = area.width;
+    }
+
+    // Whether back was pressed with nothing else taking it, i.e. leave the menu
+    pub fn back_pressed(self: &Self) -> bool {
+        self.back_pressed
+    }
+
+    // Lays out the next row. Returns its id, the whole row, whether it has the
+    //  focus and whether it was just used (clicked, or ENTER while focused).
+    fn row(self: &mut Self, draw_context: &mut impl RaylibDraw, height: f32) -> (usize, Rectangle, bool, bool) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let area = Rectangle { x: self.cursor.x, y: self.cursor.y, width: self.column_width, height: height };
+        self.cursor.y += height + ROW_GAP;
+
+        let free = self.active.is_none() && self.dropdown.is_none();
+        let hovered = area.check_collision_point_rec(self.input.mouse);
+        if free && hovered && (self.input.mouse_moved || self.input.clicked) {
+            self.focus = id;
+        }
+
+        let focused = self.focus == id;
+        let used = free && ((focused && self.input.activate) || (hovered && self.input.clicked));
+
+        if focused {
+            draw_context.draw_rectangle_rec(area, FOCUS_COLOR.fade(0.15));
+            draw_context.draw_rectangle_lines_ex(area, 2.0, FOCUS_COLOR);
+        }
+        (id, area, focused, used)
+    }
+
+    fn label(self: &Self, draw_context: &mut impl RaylibDraw, area: Rectangle, text: &str) -> Rectangle {
+        self.hud.text_at(
+            draw_context, text, Vector2 { x: area.x + PADDING, y: area.y + area.height / 2.0 },
+            Anchor::LEFT, TextStyle::new(TEXT_SIZE, TEXT_COLOR).fixed()
+        );
+
+        let label_width = area.width * LABEL_SHARE;
+        Rectangle {
+            x:      area.x + label_width,
+            y:      area.y + 3.0,
+            width:  area.width - label_width - PADDING,
+            height: area.height - 6.0
+        }
+    }
+
+    fn centered_text(self: &Self, draw_context: &mut impl RaylibDraw, area: Rectangle, text: &str, color: Color) {
+        self.hud.text_at(
+            draw_context, text, Vector2 { x: area.x + area.width / 2.0, y: area.y + area.height / 2.0 },
+            Anchor::CENTER, TextStyle::new(TEXT_SIZE, color).fixed()
+        );
+    }
+
+    pub fn heading(self: &mut Self, draw_context: &mut impl RaylibDraw, text: &str) {
+        self.hud.text_at(
+            draw_context, text, self.cursor, Anchor::TOPLEFT, TextStyle::new(TEXT_SIZE * 1.5, TEXT_COLOR).fixed().shadowed()
+        );
+        self.cursor.y += TEXT_SIZE * 1.5 + ROW_GAP * 2.0;
+    }
+
+    pub fn button(self: &mut Self, draw_context: &mut impl RaylibDraw, text: &str) -> bool {
+        let (_, area, _, used) = self.row(draw_context, ROW_HEIGHT);
+        let button = Rectangle { x: area.x + 3.0, y: area.y + 3.0, width: area.width - 6.0, height: area.height - 6.0 };
+        draw_context.draw_rectangle_rec(button, CONTROL_COLOR);
+        self.centered_text(draw_context, button, text, TEXT_COLOR);
+        used
+    }
+
+    pub fn toggle(self: &mut Self, draw_context: &mut impl RaylibDraw, text: &str, value: &mut bool) -> bool {
+        let (_, area, focused, used) = self.row(draw_context, ROW_HEIGHT);
+        let control = self.label(draw_context, area, text);
+
+        let changed = used || (focused && self.active.is_none() && (self.input.left || self.input.right));
+        if changed {
+            *value = !*value;
+        }
+
+        draw_context.draw_rectangle_rec(control, if *value { FILL_COLOR } else { CONTROL_COLOR });
+        self.centered_text(draw_context, control, if *value { "On" } else { "Off" }, TEXT_COLOR);
+        changed
+    }
+
+    // LEFT/RIGHT move it by `step`, the mouse can drag it anywhere
+    pub fn slider(self: &mut Self, draw_context: &mut impl RaylibDraw, text: &str, value: &mut f32, min: f32, max: f32, step: f32) -> bool {
+        let (id, area, focused, _) = self.row(draw_context, ROW_HEIGHT);
+        let control = self.label(draw_context, area, text);
+        let before = *value;
+
+        if focused && self.active.is_none() {
+            if self.input.left {
+                *value -= step;
+            }
+            if self.input.right {
+                *value += step;
+            }
+        }
+
+        let free = self.active.is_none() && self.dropdown.is_none();
+        if free && self.input.clicked && control.check_collision_point_rec(self.input.mouse) {
+            self.dragging = Some(id);
+        }
+        if self.dragging == Some(id) {
+            let fraction = ((self.input.mouse.x - control.x) / control.width).clamp(0.0, 1.0);
+            *value = min + ((max - min) * fraction / step).round() * step;
+        }
+        *value = value.clamp(min, max);
+
+        let fraction = if max > min { (*value - min) / (max - min) } else { 0.0 };
+        draw_context.draw_rectangle_rec(control, CONTROL_COLOR);
+        draw_context.draw_rectangle_rec(
+            Rectangle { width: control.width * fraction, ..control }, FILL_COLOR
+        );
+        self.centered_text(draw_context, control, &format!("{:.0}%", fraction * 100.0), TEXT_COLOR);
+
+        *value != before
+    }
+
+    // LEFT/RIGHT step through the options without opening it
+    pub fn dropdown(self: &mut Self, draw_context: &mut impl RaylibDraw, text: &str, options: &[&str], selected: &mut usize) -> bool {
+        let (id, area, focused, used) = self.row(draw_context, ROW_HEIGHT);
+        let control = self.label(draw_context, area, text);
+        let before = *selected;
+
+        if let Some((result_id, choice)) = self.dropdown_result {
+            if result_id == id {
+                if let Some(choice) = choice {
+                    *selected = choice;
+                }
+                self.dropdown_result = None;
+                self.active = None;
+            }
+        }
+
+        if focused && self.active.is_none() && !options.is_empty() {
+            if self.input.left {
+                *selected = (*selected + options.len() - 1) % options.len();
+            }
+            if self.input.right {
+                *selected = (*selected + 1) % options.len();
+            }
+        }
+
+        if used && !options.is_empty() {
+            self.active = Some(id);
+            self.dropdown = Some(OpenDropdown {
+                id:             id,
+                options:        options.iter().map(|option| option.to_string()).collect(),
+                highlighted:    *selected,
+                area:           control
+            });
+        }
+
+        draw_context.draw_rectangle_rec(control, CONTROL_COLOR);
+        self.centered_text(draw_context, control, options.get(*selected).copied().unwrap_or(""), TEXT_COLOR);
+        draw_context.draw_triangle(
+            Vector2 { x: control.x + control.width - 16.0, y: control.y + 9.0 },
+            Vector2 { x: control.x + control.width - 11.0, y: control.y + control.height - 9.0 },
+            Vector2 { x: control.x + control.width - 6.0,  y: control.y + 9.0 },
+            MUTED_COLOR
+        );
+
+        *selected != before
+    }
+
+    // Waits for the next key once used, BACKSPACE (or B) gives up on it
+    pub fn key_binding(self: &mut Self, draw_context: &mut impl RaylibDraw, text: &str, key: &mut KeyboardKey) -> bool {
+        let (id, area, _, used) = self.row(draw_context, ROW_HEIGHT);
+        let control = self.label(draw_context, area, text);
+        let mut changed = false;
+
+        if used {
+            self.active = Some(id);
+        } else if self.active == Some(id) {
+            // BACKSPACE gives up once it's let go, so it can't be bound
+            if self.input.back {
+                self.active = None;
+            } else if let Some(pressed) = self.input.key.filter(|pressed| *pressed != KeyboardKey::KEY_BACKSPACE) {
+                changed = *key != pressed;
+                *key = pressed;
+                self.active = None;
+                self.bound_key = Some(pressed);
+            }
+        }
+
+        let waiting = self.active == Some(id);
+        draw_context.draw_rectangle_rec(control, if waiting { FOCUS_COLOR.fade(0.4) } else { CONTROL_COLOR });
+        if waiting {
+            self.centered_text(draw_context, control, "Press a key...", TEXT_COLOR);
+        } else {
+            self.centered_text(draw_context, control, &key_name(*key), TEXT_COLOR);
+        }
+        changed
+    }
+
+    // @note: A list is one stop for the focus. Using it lets UP/DOWN move the
+    //  selection until it's used again or backed out of, the mouse can just
+    //  click an item. Scrolls to keep the selection in view.
+    pub fn list(self: &mut Self, draw_context: &mut impl RaylibDraw, items: &[&str], selected: &mut usize, visible_rows: usize) -> bool {
+        let visible_rows = visible_rows.max(1);
+        let (id, area, _, used) = self.row(draw_context, visible_rows as f32 * ROW_HEIGHT);
+        let before = *selected;
+
+        if self.active == Some(id) {
+            if self.input.up {
+                *selected = selected.saturating_sub(1);
+            }
+            if self.input.down {
+                *selected = (*selected + 1).min(items.len().saturating_sub(1));
+            }
+            if self.input.activate || self.input.back {
+                self.active = None;
+            }
+        } else if used && self.input.activate {
+            self.active = Some(id);
+        }
+
+        let first = (*selected + 1).saturating_sub(visible_rows);
+        for (row, index) in (first..items.len()).take(visible_rows).enumerate() {
+            let item_area = Rectangle {
+                x:      area.x + 3.0,
+                y:      area.y + row as f32 * ROW_HEIGHT + 2.0,
+                width:  area.width - 6.0,
+                height: ROW_HEIGHT - 4.0
+            };
+            let free = self.active.is_none() && self.dropdown.is_none();
+            if free && self.input.clicked && item_area.check_collision_point_rec(self.input.mouse) {
+                *selected = index;
+            }
+
+            if index == *selected {
+                let color = if self.active == Some(id) { FOCUS_COLOR.fade(0.5) } else { FILL_COLOR };
+                draw_context.draw_rectangle_rec(item_area, color);
+            }
+            self.hud.text_at(
+                draw_context, items[index], Vector2 { x: item_area.x + PADDING, y: item_area.y + item_area.height / 2.0 },
+                Anchor::LEFT, TextStyle::new(TEXT_SIZE, TEXT_COLOR).fixed()
+            );
+        }
+
+        *selected != before
+    }
+
+    // Draws whatever sits on top of the other widgets, call it after all of them
+    pub fn end(self: &mut Self, draw_context: &mut impl RaylibDraw) {
+        let Some(dropdown) = self.dropdown.as_ref() else { return };
+
+        for (index, option) in dropdown.options.iter().enumerate() {
+            let area = option_area(dropdown.area, index);
+            let color = if index == dropdown.highlighted { FILL_COLOR } else { CONTROL_COLOR };
+            draw_context.draw_rectangle_rec(area, color);
+            draw_context.draw_rectangle_lines_ex(area, 1.0, PANEL_COLOR);
+            self.centered_text(draw_context, area, option, TEXT_COLOR);
+        }
+    }
+}
+
+fn option_area(dropdown: Rectangle, index: usize) -> Rectangle {
+    Rectangle { y: dropdown.y + dropdown.height * (index + 1) as f32, ..dropdown }
+}
+
+// KEY_RIGHT_SHIFT -> "RIGHT SHIFT"
+pub fn key_name(key: KeyboardKey) -> String {
+    let name = format!("{:?}", key);
+    name.trim_start_matches("KEY_").replace('_', " ")
+}